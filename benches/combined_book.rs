@@ -0,0 +1,72 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use orderbooks::combined_book::CombinedBook;
+use orderbooks::exchange::{Exchange, ExchangeOrder, Orderbook};
+
+const EXCHANGES: [Exchange; 10] = [
+    Exchange::BINANCE,
+    Exchange::BITSTAMP,
+    Exchange::new("Venue3"),
+    Exchange::new("Venue4"),
+    Exchange::new("Venue5"),
+    Exchange::new("Venue6"),
+    Exchange::new("Venue7"),
+    Exchange::new("Venue8"),
+    Exchange::new("Venue9"),
+    Exchange::new("Venue10"),
+];
+
+fn book(exchange: &Exchange, depth: usize, offset: f64) -> Orderbook {
+    let level = |price: f64, i: usize| ExchangeOrder {
+        exchange: *exchange,
+        price,
+        amount: 1.0 + (i % 7) as f64,
+        original_price: None,
+    };
+    Orderbook {
+        bids: (0..depth)
+            .map(|i| level(100.0 + offset - i as f64 * 0.01, i))
+            .collect(),
+        asks: (0..depth)
+            .map(|i| level(100.01 + offset + i as f64 * 0.01, i))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn combined_book_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("combined_book_update");
+    for venues in [2, 5, 10] {
+        for depth in [10, 100, 1000] {
+            let mut combined_book = CombinedBook::new(depth);
+            for (i, exchange) in EXCHANGES.iter().take(venues).enumerate() {
+                combined_book.update(book(exchange, depth, i as f64 * 0.001));
+            }
+            // Alternate between two books for one venue so every update moves
+            // its levels.
+            let updates = [
+                book(&EXCHANGES[0], depth, 0.002),
+                book(&EXCHANGES[0], depth, 0.0),
+            ];
+            let mut next = 0;
+
+            group.bench_with_input(
+                BenchmarkId::new(format!("{venues}_venues"), depth),
+                &depth,
+                |b, _| {
+                    b.iter_batched(
+                        || {
+                            next ^= 1;
+                            updates[next].clone()
+                        },
+                        |update| combined_book.update(update),
+                        BatchSize::SmallInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, combined_book_benchmarks);
+criterion_main!(benches);
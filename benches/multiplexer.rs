@@ -0,0 +1,88 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures::executor::block_on;
+use futures::stream::{self, BoxStream, StreamExt};
+use orderbooks::multiplexer::{MultiplexerEvent, StreamMultiplexer};
+use std::pin::Pin;
+use std::task::Poll;
+use tokio_stream::StreamMap;
+
+const EVENTS: usize = 10_000;
+
+/// A venue that always has another update ready, i.e. a feed under load.
+fn busy_venue(venue: usize) -> BoxStream<'static, usize> {
+    stream::repeat(venue).boxed()
+}
+
+fn multiplexer(venues: usize) -> StreamMultiplexer<usize, BoxStream<'static, usize>> {
+    let mut multiplexer = StreamMultiplexer::new();
+    for venue in 0..venues {
+        multiplexer.insert(venue, busy_venue(venue));
+    }
+    multiplexer
+}
+
+/// Consumes `EVENTS` items and returns how many came from each venue.
+fn drain_multiplexer(venues: usize) -> Vec<usize> {
+    let mut multiplexer = multiplexer(venues);
+    let mut counts = vec![0; venues];
+    block_on(async {
+        for _ in 0..EVENTS {
+            if let Some(MultiplexerEvent::Item(venue, _)) = multiplexer.next().await {
+                counts[venue] += 1;
+            }
+        }
+    });
+    counts
+}
+
+/// The previous approach: a fresh `StreamMap` built on every poll.
+fn drain_rebuilt_stream_map(venues: usize) -> Vec<usize> {
+    let mut streams: Vec<_> = (0..venues).map(busy_venue).collect();
+    let mut counts = vec![0; venues];
+    block_on(
+        stream::poll_fn(|cx| {
+            for _ in 0..EVENTS {
+                let mut stream_map = StreamMap::new();
+                for (index, stream) in streams.iter_mut().enumerate() {
+                    stream_map.insert(index, Pin::new(stream));
+                }
+                if let Poll::Ready(Some((venue, _))) = stream_map.poll_next_unpin(cx) {
+                    counts[venue] += 1;
+                }
+            }
+            Poll::Ready(None::<()>)
+        })
+        .collect::<Vec<_>>(),
+    );
+    counts
+}
+
+fn multiplexer_benchmarks(c: &mut Criterion) {
+    for venues in [2, 5, 10] {
+        let counts = drain_multiplexer(venues);
+        let (min, max) = (counts.iter().min(), counts.iter().max());
+        assert!(
+            max.zip(min).is_some_and(|(max, min)| max - min <= 1),
+            "{venues} busy venues shared events unevenly: {counts:?}"
+        );
+    }
+
+    let mut group = c.benchmark_group("multiplexer");
+    group.throughput(Throughput::Elements(EVENTS as u64));
+    for venues in [2, 5, 10] {
+        group.bench_with_input(
+            BenchmarkId::new("persistent", venues),
+            &venues,
+            |b, &venues| b.iter(|| drain_multiplexer(venues)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("rebuilt_stream_map", venues),
+            &venues,
+            |b, &venues| b.iter(|| drain_rebuilt_stream_map(venues)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, multiplexer_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,147 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use orderbooks::exchange::{binance, bitstamp, Exchange, ExchangeOrder};
+use serde::Deserialize;
+
+/// The parsing replaced by the streaming deserializer, kept as the
+/// baseline: every level deserialized into owned strings, then cut to
+/// `max_orders`. Unread fields are still deserialized, as they were.
+#[allow(dead_code)]
+mod baseline {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Order(String, String);
+
+    #[derive(Deserialize)]
+    struct BinanceOrderbook {
+        #[serde(rename = "lastUpdateId")]
+        last_update_id: u64,
+        bids: Vec<Order>,
+        asks: Vec<Order>,
+    }
+
+    #[derive(Deserialize)]
+    struct BitstampOrderbook {
+        data: BitstampData,
+        channel: String,
+        event: String,
+    }
+
+    #[derive(Deserialize)]
+    struct BitstampData {
+        microtimestamp: String,
+        bids: Vec<Order>,
+        asks: Vec<Order>,
+    }
+
+    fn levels(
+        exchange: Exchange,
+        orders: Vec<Order>,
+        max_orders: usize,
+    ) -> Result<Vec<ExchangeOrder>, std::num::ParseFloatError> {
+        orders
+            .into_iter()
+            .take(max_orders)
+            .map(|Order(price, amount)| {
+                Ok(ExchangeOrder {
+                    exchange,
+                    price: price.parse()?,
+                    amount: amount.parse()?,
+                    original_price: None,
+                })
+            })
+            .collect()
+    }
+
+    pub fn binance(
+        message: &str,
+        max_orders: usize,
+    ) -> Result<(Vec<ExchangeOrder>, Vec<ExchangeOrder>), Box<dyn std::error::Error>> {
+        let book: BinanceOrderbook = serde_json::from_str(message)?;
+        Ok((
+            levels(Exchange::BINANCE, book.bids, max_orders)?,
+            levels(Exchange::BINANCE, book.asks, max_orders)?,
+        ))
+    }
+
+    pub fn bitstamp(
+        message: &str,
+        max_orders: usize,
+    ) -> Result<(Vec<ExchangeOrder>, Vec<ExchangeOrder>), Box<dyn std::error::Error>> {
+        let book: BitstampOrderbook = serde_json::from_str(message)?;
+        Ok((
+            levels(Exchange::BITSTAMP, book.data.bids, max_orders)?,
+            levels(Exchange::BITSTAMP, book.data.asks, max_orders)?,
+        ))
+    }
+}
+
+fn levels(count: usize, best: f64, step: f64) -> String {
+    let levels: Vec<String> = (0..count)
+        .map(|i| {
+            format!(
+                r#"["{:.8}","{:.8}"]"#,
+                best + step * i as f64,
+                0.5 + i as f64 * 0.01
+            )
+        })
+        .collect();
+    format!("[{}]", levels.join(","))
+}
+
+/// Binance partial depth streams carry at most 20 levels per side.
+fn binance_message() -> String {
+    format!(
+        r#"{{"lastUpdateId":1027024,"bids":{},"asks":{}}}"#,
+        levels(20, 0.05123, -0.00001),
+        levels(20, 0.05124, 0.00001)
+    )
+}
+
+/// Bitstamp order book channels carry 100 levels per side.
+fn bitstamp_message() -> String {
+    format!(
+        r#"{{"data":{{"timestamp":"1700000000","microtimestamp":"1700000000123456","bids":{},"asks":{}}},"channel":"order_book_ethbtc","event":"data"}}"#,
+        levels(100, 0.05123, -0.00001),
+        levels(100, 0.05124, 0.00001)
+    )
+}
+
+fn parse_benchmarks(c: &mut Criterion) {
+    let binance = binance_message();
+    let bitstamp = bitstamp_message();
+
+    let mut group = c.benchmark_group("parse_orderbook");
+    for max_orders in [10, 100] {
+        group.bench_with_input(
+            BenchmarkId::new("binance", max_orders),
+            &max_orders,
+            |b, &max_orders| {
+                b.iter(|| binance::parse_orderbook(black_box(&binance), max_orders).unwrap())
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("bitstamp", max_orders),
+            &max_orders,
+            |b, &max_orders| {
+                b.iter(|| bitstamp::parse_message(black_box(&bitstamp), max_orders).unwrap())
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("binance_baseline", max_orders),
+            &max_orders,
+            |b, &max_orders| b.iter(|| baseline::binance(black_box(&binance), max_orders).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("bitstamp_baseline", max_orders),
+            &max_orders,
+            |b, &max_orders| {
+                b.iter(|| baseline::bitstamp(black_box(&bitstamp), max_orders).unwrap())
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_benchmarks);
+criterion_main!(benches);
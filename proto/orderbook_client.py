@@ -1,3 +1,4 @@
+import os
 import grpc
 import orderbook_pb2
 import orderbook_pb2_grpc
@@ -8,7 +9,14 @@ from time import sleep
 def fetch_book_summary():
     with grpc.insecure_channel('localhost:50051') as channel:
         stub = orderbook_pb2_grpc.OrderbookAggregatorStub(channel)
-        for summary in stub.BookSummary(orderbook_pb2.Empty()):
+        token = os.environ.get("ORDERBOOK_TOKEN")
+        metadata = [("authorization", f"Bearer {token}")] if token else None
+        exchanges = os.environ.get("ORDERBOOK_EXCHANGES")
+        request = orderbook_pb2.BookSummaryRequest(
+            instrument=os.environ.get("ORDERBOOK_INSTRUMENT", ""),
+            exchanges=exchanges.split(",") if exchanges else [],
+        )
+        for summary in stub.BookSummary(request, metadata=metadata):
             yield summary
 
 def get_exchange_color(exchange):
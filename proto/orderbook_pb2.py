@@ -24,19 +24,85 @@ _sym_db = _symbol_database.Default()
 
 
 
-DESCRIPTOR = _descriptor_pool.Default().AddSerializedFile(b'\n\x0forderbook.proto\x12\torderbook\"\x07\n\x05\x45mpty\"Y\n\x07Summary\x12\x0e\n\x06spread\x18\x01 \x01(\x01\x12\x1e\n\x04\x62ids\x18\x02 \x03(\x0b\x32\x10.orderbook.Level\x12\x1e\n\x04\x61sks\x18\x03 \x03(\x0b\x32\x10.orderbook.Level\"8\n\x05Level\x12\x10\n\x08\x65xchange\x18\x01 \x01(\t\x12\r\n\x05price\x18\x02 \x01(\x01\x12\x0e\n\x06\x61mount\x18\x03 \x01(\x01\x32L\n\x13OrderbookAggregator\x12\x35\n\x0b\x42ookSummary\x12\x10.orderbook.Empty\x1a\x12.orderbook.Summary0\x01\x62\x06proto3')
+DESCRIPTOR = _descriptor_pool.Default().AddSerializedFile(b'\n\x0forderbook.proto\x12\torderbook\"\x07\n\x05\x45mpty\"\x8f\x01\n\x12\x42ookSummaryRequest\x12\x12\n\ninstrument\x18\x01 \x01(\t\x12\x11\n\texchanges\x18\x02 \x03(\t\x12\r\n\x05\x64\x65pth\x18\x03 \x01(\r\x12\x17\n\x0fmin_interval_ms\x18\x04 \x01(\r\x12\x10\n\x08\x63onflate\x18\x05 \x01(\x08\x12\x18\n\x10top_of_book_only\x18\x06 \x01(\x08\"Y\n\x07Summary\x12\x0e\n\x06spread\x18\x01 \x01(\x01\x12\x1e\n\x04\x62ids\x18\x02 \x03(\x0b\x32\x10.orderbook.Level\x12\x1e\n\x04\x61sks\x18\x03 \x03(\x0b\x32\x10.orderbook.Level\"h\n\x05Level\x12\x10\n\x08\x65xchange\x18\x01 \x01(\t\x12\r\n\x05price\x18\x02 \x01(\x01\x12\x0e\n\x06\x61mount\x18\x03 \x01(\x01\x12\x1b\n\x0eoriginal_price\x18\x04 \x01(\x01H\x00\x88\x01\x01\x42\x11\n\x0f_original_price\"\x12\n\x10\x46\x65\x65\x64StatsRequest\":\n\x11\x46\x65\x65\x64StatsResponse\x12%\n\x06venues\x18\x01 \x03(\x0b\x32\x15.orderbook.VenueStats\"\x8c\x02\n\nVenueStats\x12\x10\n\x08\x65xchange\x18\x01 \x01(\t\x12\x10\n\x08messages\x18\x02 \x01(\x04\x12\x1a\n\rlast_sequence\x18\x03 \x01(\x04H\x00\x88\x01\x01\x12\x1f\n\x12last_event_time_us\x18\x04 \x01(\x04H\x01\x88\x01\x01\x12\x1d\n\x15last_received_time_us\x18\x05 \x01(\x04\x12(\n\x07latency\x18\x06 \x01(\x0b\x32\x17.orderbook.LatencyStats\x12\x14\n\x0cout_of_order\x18\x07 \x01(\x04\x12\x15\n\rsequence_gaps\x18\x08 \x01(\x04\x42\x10\n\x0e_last_sequenceB\x15\n\x13_last_event_time_us\"v\n\x0cLatencyStats\x12\x0f\n\x07samples\x18\x01 \x01(\r\x12\x0e\n\x06p50_us\x18\x02 \x01(\x03\x12\x0e\n\x06p90_us\x18\x03 \x01(\x03\x12\x0e\n\x06p99_us\x18\x04 \x01(\x03\x12\x0e\n\x06max_us\x18\x05 \x01(\x03\x12\x15\n\rclock_skew_us\x18\x06 \x01(\x03\"6\n\rTradesRequest\x12\x12\n\ninstrument\x18\x01 \x01(\t\x12\x11\n\texchanges\x18\x02 \x03(\t\"\xa4\x01\n\x05Trade\x12\x10\n\x08\x65xchange\x18\x01 \x01(\t\x12\r\n\x05price\x18\x02 \x01(\x01\x12\x0e\n\x06\x61mount\x18\x03 \x01(\x01\x12\'\n\taggressor\x18\x04 \x01(\x0e\x32\x14.orderbook.Aggressor\x12\x10\n\x08trade_id\x18\x05 \x01(\x04\x12\x15\n\revent_time_us\x18\x06 \x01(\x04\x12\x18\n\x10received_time_us\x18\x07 \x01(\x04\"\x83\x01\n\x0e\x43\x61ndlesRequest\x12\x12\n\ninstrument\x18\x01 \x01(\t\x12\x10\n\x08\x65xchange\x18\x02 \x01(\t\x12\x13\n\x0binterval_ms\x18\x03 \x01(\r\x12\'\n\x06source\x18\x04 \x01(\x0e\x32\x17.orderbook.CandleSource\x12\r\n\x05\x63ount\x18\x05 \x01(\r\"\xc6\x01\n\x06\x43\x61ndle\x12\x10\n\x08\x65xchange\x18\x01 \x01(\t\x12\x13\n\x0binterval_ms\x18\x02 \x01(\r\x12\'\n\x06source\x18\x03 \x01(\x0e\x32\x17.orderbook.CandleSource\x12\x14\n\x0copen_time_us\x18\x04 \x01(\x04\x12\x0c\n\x04open\x18\x05 \x01(\x01\x12\x0c\n\x04high\x18\x06 \x01(\x01\x12\x0b\n\x03low\x18\x07 \x01(\x01\x12\r\n\x05\x63lose\x18\x08 \x01(\x01\x12\x0e\n\x06volume\x18\t \x01(\x01\x12\x0e\n\x06trades\x18\n \x01(\x04\"5\n\x0f\x43\x61ndlesResponse\x12\"\n\x07\x63\x61ndles\x18\x01 \x03(\x0b\x32\x11.orderbook.Candle\"d\n\x10\x41nalyticsRequest\x12\x12\n\ninstrument\x18\x01 \x01(\t\x12\x11\n\texchanges\x18\x02 \x03(\t\x12\x17\n\x0fmin_interval_ms\x18\x03 \x01(\r\x12\x10\n\x08\x63onflate\x18\x04 \x01(\x08\"\xa6\x01\n\rBookAnalytics\x12\x10\n\x03mid\x18\x01 \x01(\x01H\x00\x88\x01\x01\x12\x17\n\nmicroprice\x18\x02 \x01(\x01H\x01\x88\x01\x01\x12(\n\nimbalances\x18\x03 \x03(\x0b\x32\x14.orderbook.Imbalance\x12)\n\x0b\x64\x65pth_bands\x18\x04 \x03(\x0b\x32\x14.orderbook.DepthBandB\x06\n\x04_midB\r\n\x0b_microprice\"-\n\tImbalance\x12\r\n\x05\x64\x65pth\x18\x01 \x01(\r\x12\x11\n\timbalance\x18\x02 \x01(\x01\"R\n\tDepthBand\x12\x0b\n\x03\x62ps\x18\x01 \x01(\x01\x12\x10\n\x08\x65xchange\x18\x02 \x01(\t\x12\x12\n\nbid_amount\x18\x03 \x01(\x01\x12\x12\n\nask_amount\x18\x04 \x01(\x01\"*\n\x15LiquidityStatsRequest\x12\x11\n\texchanges\x18\x01 \x03(\t\"B\n\x16LiquidityStatsResponse\x12(\n\x05stats\x18\x01 \x03(\x0b\x32\x19.orderbook.LiquidityStats\"\xe5\x01\n\x0eLiquidityStats\x12\x10\n\x08\x65xchange\x18\x01 \x01(\t\x12\x11\n\twindow_ms\x18\x02 \x01(\x04\x12\x13\n\x0bobserved_ms\x18\x03 \x01(\x04\x12\x13\n\x0bmean_spread\x18\x04 \x01(\x01\x12\x12\n\nmin_spread\x18\x05 \x01(\x01\x12\x12\n\nmax_spread\x18\x06 \x01(\x01\x12\x15\n\rmean_bid_size\x18\x07 \x01(\x01\x12\x15\n\rmean_ask_size\x18\x08 \x01(\x01\x12\x16\n\x0e\x62\x65st_bid_share\x18\t \x01(\x01\x12\x16\n\x0e\x62\x65st_ask_share\x18\n \x01(\x01\"\x81\x01\n\x11PaperOrderRequest\x12\x1d\n\x04side\x18\x01 \x01(\x0e\x32\x0f.orderbook.Side\x12\x10\n\x08quantity\x18\x02 \x01(\x01\x12\x18\n\x0blimit_price\x18\x03 \x01(\x01H\x00\x88\x01\x01\x12\x11\n\texchanges\x18\x04 \x03(\tB\x0e\n\x0c_limit_price\"\x8c\x02\n\nPaperOrder\x12\x10\n\x08order_id\x18\x01 \x01(\x04\x12\x1d\n\x04side\x18\x02 \x01(\x0e\x32\x0f.orderbook.Side\x12\x10\n\x08quantity\x18\x03 \x01(\x01\x12\x18\n\x0blimit_price\x18\x04 \x01(\x01H\x00\x88\x01\x01\x12\x11\n\texchanges\x18\x05 \x03(\t\x12+\n\x06status\x18\x06 \x01(\x0e\x32\x1b.orderbook.PaperOrderStatus\x12\x0e\n\x06\x66illed\x18\x07 \x01(\x01\x12\x1a\n\raverage_price\x18\x08 \x01(\x01H\x01\x88\x01\x01\x12\x13\n\x0bqueue_ahead\x18\t \x01(\x01\x42\x0e\n\x0c_limit_priceB\x10\n\x0e_average_price\"+\n\x17\x43\x61ncelPaperOrderRequest\x12\x10\n\x08order_id\x18\x01 \x01(\x04\"\x13\n\x11PaperFillsRequest\"\xb6\x01\n\tPaperFill\x12\x10\n\x08order_id\x18\x01 \x01(\x04\x12\x10\n\x08\x65xchange\x18\x02 \x01(\t\x12\x1d\n\x04side\x18\x03 \x01(\x0e\x32\x0f.orderbook.Side\x12\r\n\x05price\x18\x04 \x01(\x01\x12\x10\n\x08quantity\x18\x05 \x01(\x01\x12\x0b\n\x03\x66\x65\x65\x18\x06 \x01(\x01\x12\'\n\tliquidity\x18\x07 \x01(\x0e\x32\x14.orderbook.Liquidity\x12\x0f\n\x07time_us\x18\x08 \x01(\x04\"\x17\n\x15PaperPositionsRequest\"q\n\x16PaperPositionsResponse\x12+\n\tpositions\x18\x01 \x03(\x0b\x32\x18.orderbook.PaperPosition\x12*\n\x0bopen_orders\x18\x02 \x03(\x0b\x32\x15.orderbook.PaperOrder\"L\n\rPaperPosition\x12\x10\n\x08\x65xchange\x18\x01 \x01(\t\x12\x0c\n\x04\x62\x61se\x18\x02 \x01(\x01\x12\r\n\x05quote\x18\x03 \x01(\x01\x12\x0c\n\x04\x66\x65\x65s\x18\x04 \x01(\x01\"\x95\x01\n\x11RouteOrderRequest\x12\x12\n\ninstrument\x18\x01 \x01(\t\x12\x1d\n\x04side\x18\x02 \x01(\x0e\x32\x0f.orderbook.Side\x12\x10\n\x08quantity\x18\x03 \x01(\x01\x12\x18\n\x0blimit_price\x18\x04 \x01(\x01H\x00\x88\x01\x01\x12\x11\n\texchanges\x18\x05 \x03(\tB\x0e\n\x0c_limit_price\"\xa8\x01\n\x12RouteOrderResponse\x12+\n\x0c\x63hild_orders\x18\x01 \x03(\x0b\x32\x15.orderbook.ChildOrder\x12\x0e\n\x06\x66illed\x18\x02 \x01(\x01\x12\x10\n\x08unfilled\x18\x03 \x01(\x01\x12\x1a\n\raverage_price\x18\x04 \x01(\x01H\x00\x88\x01\x01\x12\x15\n\rexpected_fees\x18\x05 \x01(\x01\x42\x10\n\x0e_average_price\"s\n\nChildOrder\x12\x10\n\x08\x65xchange\x18\x01 \x01(\t\x12\x10\n\x08quantity\x18\x02 \x01(\x01\x12\x13\n\x0blimit_price\x18\x03 \x01(\x01\x12\x16\n\x0e\x65xpected_price\x18\x04 \x01(\x01\x12\x14\n\x0c\x65xpected_fee\x18\x05 \x01(\x01*M\n\tAggressor\x12\x19\n\x15\x41GGRESSOR_UNSPECIFIED\x10\x00\x12\x11\n\rAGGRESSOR_BUY\x10\x01\x12\x12\n\x0e\x41GGRESSOR_SELL\x10\x02*?\n\x0c\x43\x61ndleSource\x12\x15\n\x11\x43\x41NDLE_SOURCE_MID\x10\x00\x12\x18\n\x14\x43\x41NDLE_SOURCE_TRADES\x10\x01*9\n\x04Side\x12\x14\n\x10SIDE_UNSPECIFIED\x10\x00\x12\x0c\n\x08SIDE_BUY\x10\x01\x12\r\n\tSIDE_SELL\x10\x02*\x90\x01\n\x10PaperOrderStatus\x12\x1e\n\x1aPAPER_ORDER_STATUS_PENDING\x10\x00\x12\x1b\n\x17PAPER_ORDER_STATUS_OPEN\x10\x01\x12\x1d\n\x19PAPER_ORDER_STATUS_FILLED\x10\x02\x12 \n\x1cPAPER_ORDER_STATUS_CANCELLED\x10\x03*5\n\tLiquidity\x12\x13\n\x0fLIQUIDITY_TAKER\x10\x00\x12\x13\n\x0fLIQUIDITY_MAKER\x10\x01\x32\x8f\x07\n\x13OrderbookAggregator\x12\x42\n\x0b\x42ookSummary\x12\x1d.orderbook.BookSummaryRequest\x1a\x12.orderbook.Summary0\x01\x12I\n\x0cGetFeedStats\x12\x1b.orderbook.FeedStatsRequest\x1a\x1c.orderbook.FeedStatsResponse\x12<\n\x0cStreamTrades\x12\x18.orderbook.TradesRequest\x1a\x10.orderbook.Trade0\x01\x12?\n\rStreamCandles\x12\x19.orderbook.CandlesRequest\x1a\x11.orderbook.Candle0\x01\x12\x43\n\nGetCandles\x12\x19.orderbook.CandlesRequest\x1a\x1a.orderbook.CandlesResponse\x12\x44\n\tAnalytics\x12\x1b.orderbook.AnalyticsRequest\x1a\x18.orderbook.BookAnalytics0\x01\x12X\n\x11GetLiquidityStats\x12 .orderbook.LiquidityStatsRequest\x1a!.orderbook.LiquidityStatsResponse\x12G\n\x10SubmitPaperOrder\x12\x1c.orderbook.PaperOrderRequest\x1a\x15.orderbook.PaperOrder\x12M\n\x10\x43\x61ncelPaperOrder\x12\".orderbook.CancelPaperOrderRequest\x1a\x15.orderbook.PaperOrder\x12H\n\x10StreamPaperFills\x12\x1c.orderbook.PaperFillsRequest\x1a\x14.orderbook.PaperFill0\x01\x12X\n\x11GetPaperPositions\x12 .orderbook.PaperPositionsRequest\x1a!.orderbook.PaperPositionsResponse\x12I\n\nRouteOrder\x12\x1c.orderbook.RouteOrderRequest\x1a\x1d.orderbook.RouteOrderResponseb\x06proto3')
 
 _globals = globals()
 _builder.BuildMessageAndEnumDescriptors(DESCRIPTOR, _globals)
 _builder.BuildTopDescriptorsAndMessages(DESCRIPTOR, 'orderbook_pb2', _globals)
 if not _descriptor._USE_C_DESCRIPTORS:
   DESCRIPTOR._loaded_options = None
+  _globals['_AGGRESSOR']._serialized_start=3524
+  _globals['_AGGRESSOR']._serialized_end=3601
+  _globals['_CANDLESOURCE']._serialized_start=3603
+  _globals['_CANDLESOURCE']._serialized_end=3666
+  _globals['_SIDE']._serialized_start=3668
+  _globals['_SIDE']._serialized_end=3725
+  _globals['_PAPERORDERSTATUS']._serialized_start=3728
+  _globals['_PAPERORDERSTATUS']._serialized_end=3872
+  _globals['_LIQUIDITY']._serialized_start=3874
+  _globals['_LIQUIDITY']._serialized_end=3927
   _globals['_EMPTY']._serialized_start=30
   _globals['_EMPTY']._serialized_end=37
-  _globals['_SUMMARY']._serialized_start=39
-  _globals['_SUMMARY']._serialized_end=128
-  _globals['_LEVEL']._serialized_start=130
-  _globals['_LEVEL']._serialized_end=186
-  _globals['_ORDERBOOKAGGREGATOR']._serialized_start=188
-  _globals['_ORDERBOOKAGGREGATOR']._serialized_end=264
+  _globals['_BOOKSUMMARYREQUEST']._serialized_start=40
+  _globals['_BOOKSUMMARYREQUEST']._serialized_end=183
+  _globals['_SUMMARY']._serialized_start=185
+  _globals['_SUMMARY']._serialized_end=274
+  _globals['_LEVEL']._serialized_start=276
+  _globals['_LEVEL']._serialized_end=380
+  _globals['_FEEDSTATSREQUEST']._serialized_start=382
+  _globals['_FEEDSTATSREQUEST']._serialized_end=400
+  _globals['_FEEDSTATSRESPONSE']._serialized_start=402
+  _globals['_FEEDSTATSRESPONSE']._serialized_end=460
+  _globals['_VENUESTATS']._serialized_start=463
+  _globals['_VENUESTATS']._serialized_end=731
+  _globals['_LATENCYSTATS']._serialized_start=733
+  _globals['_LATENCYSTATS']._serialized_end=851
+  _globals['_TRADESREQUEST']._serialized_start=853
+  _globals['_TRADESREQUEST']._serialized_end=907
+  _globals['_TRADE']._serialized_start=910
+  _globals['_TRADE']._serialized_end=1074
+  _globals['_CANDLESREQUEST']._serialized_start=1077
+  _globals['_CANDLESREQUEST']._serialized_end=1208
+  _globals['_CANDLE']._serialized_start=1211
+  _globals['_CANDLE']._serialized_end=1409
+  _globals['_CANDLESRESPONSE']._serialized_start=1411
+  _globals['_CANDLESRESPONSE']._serialized_end=1464
+  _globals['_ANALYTICSREQUEST']._serialized_start=1466
+  _globals['_ANALYTICSREQUEST']._serialized_end=1566
+  _globals['_BOOKANALYTICS']._serialized_start=1569
+  _globals['_BOOKANALYTICS']._serialized_end=1735
+  _globals['_IMBALANCE']._serialized_start=1737
+  _globals['_IMBALANCE']._serialized_end=1782
+  _globals['_DEPTHBAND']._serialized_start=1784
+  _globals['_DEPTHBAND']._serialized_end=1866
+  _globals['_LIQUIDITYSTATSREQUEST']._serialized_start=1868
+  _globals['_LIQUIDITYSTATSREQUEST']._serialized_end=1910
+  _globals['_LIQUIDITYSTATSRESPONSE']._serialized_start=1912
+  _globals['_LIQUIDITYSTATSRESPONSE']._serialized_end=1978
+  _globals['_LIQUIDITYSTATS']._serialized_start=1981
+  _globals['_LIQUIDITYSTATS']._serialized_end=2210
+  _globals['_PAPERORDERREQUEST']._serialized_start=2213
+  _globals['_PAPERORDERREQUEST']._serialized_end=2342
+  _globals['_PAPERORDER']._serialized_start=2345
+  _globals['_PAPERORDER']._serialized_end=2613
+  _globals['_CANCELPAPERORDERREQUEST']._serialized_start=2615
+  _globals['_CANCELPAPERORDERREQUEST']._serialized_end=2658
+  _globals['_PAPERFILLSREQUEST']._serialized_start=2660
+  _globals['_PAPERFILLSREQUEST']._serialized_end=2679
+  _globals['_PAPERFILL']._serialized_start=2682
+  _globals['_PAPERFILL']._serialized_end=2864
+  _globals['_PAPERPOSITIONSREQUEST']._serialized_start=2866
+  _globals['_PAPERPOSITIONSREQUEST']._serialized_end=2889
+  _globals['_PAPERPOSITIONSRESPONSE']._serialized_start=2891
+  _globals['_PAPERPOSITIONSRESPONSE']._serialized_end=3004
+  _globals['_PAPERPOSITION']._serialized_start=3006
+  _globals['_PAPERPOSITION']._serialized_end=3082
+  _globals['_ROUTEORDERREQUEST']._serialized_start=3085
+  _globals['_ROUTEORDERREQUEST']._serialized_end=3234
+  _globals['_ROUTEORDERRESPONSE']._serialized_start=3237
+  _globals['_ROUTEORDERRESPONSE']._serialized_end=3405
+  _globals['_CHILDORDER']._serialized_start=3407
+  _globals['_CHILDORDER']._serialized_end=3522
+  _globals['_ORDERBOOKAGGREGATOR']._serialized_start=3930
+  _globals['_ORDERBOOKAGGREGATOR']._serialized_end=4841
 # @@protoc_insertion_point(module_scope)
@@ -36,9 +36,64 @@ class OrderbookAggregatorStub(object):
         """
         self.BookSummary = channel.unary_stream(
                 '/orderbook.OrderbookAggregator/BookSummary',
-                request_serializer=orderbook__pb2.Empty.SerializeToString,
+                request_serializer=orderbook__pb2.BookSummaryRequest.SerializeToString,
                 response_deserializer=orderbook__pb2.Summary.FromString,
                 _registered_method=True)
+        self.GetFeedStats = channel.unary_unary(
+                '/orderbook.OrderbookAggregator/GetFeedStats',
+                request_serializer=orderbook__pb2.FeedStatsRequest.SerializeToString,
+                response_deserializer=orderbook__pb2.FeedStatsResponse.FromString,
+                _registered_method=True)
+        self.StreamTrades = channel.unary_stream(
+                '/orderbook.OrderbookAggregator/StreamTrades',
+                request_serializer=orderbook__pb2.TradesRequest.SerializeToString,
+                response_deserializer=orderbook__pb2.Trade.FromString,
+                _registered_method=True)
+        self.StreamCandles = channel.unary_stream(
+                '/orderbook.OrderbookAggregator/StreamCandles',
+                request_serializer=orderbook__pb2.CandlesRequest.SerializeToString,
+                response_deserializer=orderbook__pb2.Candle.FromString,
+                _registered_method=True)
+        self.GetCandles = channel.unary_unary(
+                '/orderbook.OrderbookAggregator/GetCandles',
+                request_serializer=orderbook__pb2.CandlesRequest.SerializeToString,
+                response_deserializer=orderbook__pb2.CandlesResponse.FromString,
+                _registered_method=True)
+        self.Analytics = channel.unary_stream(
+                '/orderbook.OrderbookAggregator/Analytics',
+                request_serializer=orderbook__pb2.AnalyticsRequest.SerializeToString,
+                response_deserializer=orderbook__pb2.BookAnalytics.FromString,
+                _registered_method=True)
+        self.GetLiquidityStats = channel.unary_unary(
+                '/orderbook.OrderbookAggregator/GetLiquidityStats',
+                request_serializer=orderbook__pb2.LiquidityStatsRequest.SerializeToString,
+                response_deserializer=orderbook__pb2.LiquidityStatsResponse.FromString,
+                _registered_method=True)
+        self.SubmitPaperOrder = channel.unary_unary(
+                '/orderbook.OrderbookAggregator/SubmitPaperOrder',
+                request_serializer=orderbook__pb2.PaperOrderRequest.SerializeToString,
+                response_deserializer=orderbook__pb2.PaperOrder.FromString,
+                _registered_method=True)
+        self.CancelPaperOrder = channel.unary_unary(
+                '/orderbook.OrderbookAggregator/CancelPaperOrder',
+                request_serializer=orderbook__pb2.CancelPaperOrderRequest.SerializeToString,
+                response_deserializer=orderbook__pb2.PaperOrder.FromString,
+                _registered_method=True)
+        self.StreamPaperFills = channel.unary_stream(
+                '/orderbook.OrderbookAggregator/StreamPaperFills',
+                request_serializer=orderbook__pb2.PaperFillsRequest.SerializeToString,
+                response_deserializer=orderbook__pb2.PaperFill.FromString,
+                _registered_method=True)
+        self.GetPaperPositions = channel.unary_unary(
+                '/orderbook.OrderbookAggregator/GetPaperPositions',
+                request_serializer=orderbook__pb2.PaperPositionsRequest.SerializeToString,
+                response_deserializer=orderbook__pb2.PaperPositionsResponse.FromString,
+                _registered_method=True)
+        self.RouteOrder = channel.unary_unary(
+                '/orderbook.OrderbookAggregator/RouteOrder',
+                request_serializer=orderbook__pb2.RouteOrderRequest.SerializeToString,
+                response_deserializer=orderbook__pb2.RouteOrderResponse.FromString,
+                _registered_method=True)
 
 
 class OrderbookAggregatorServicer(object):
@@ -50,14 +105,143 @@ class OrderbookAggregatorServicer(object):
         context.set_details('Method not implemented!')
         raise NotImplementedError('Method not implemented!')
 
+    def GetFeedStats(self, request, context):
+        """Missing associated documentation comment in .proto file."""
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
+    def StreamTrades(self, request, context):
+        """Missing associated documentation comment in .proto file."""
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
+    def StreamCandles(self, request, context):
+        """Bars as they close.
+        """
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
+    def GetCandles(self, request, context):
+        """The most recent closed bars kept in memory.
+        """
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
+    def Analytics(self, request, context):
+        """Microstructure figures computed on every book update the request
+        lets through.
+        """
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
+    def GetLiquidityStats(self, request, context):
+        """Missing associated documentation comment in .proto file."""
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
+    def SubmitPaperOrder(self, request, context):
+        """Simulated orders matched against the combined book. Orders, fills
+        and positions belong to the authenticated client.
+        """
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
+    def CancelPaperOrder(self, request, context):
+        """Missing associated documentation comment in .proto file."""
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
+    def StreamPaperFills(self, request, context):
+        """Missing associated documentation comment in .proto file."""
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
+    def GetPaperPositions(self, request, context):
+        """Missing associated documentation comment in .proto file."""
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
+    def RouteOrder(self, request, context):
+        """Splits an order across venues at the current book, net of taker fees
+        and within venue minimum order sizes.
+        """
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
 
 def add_OrderbookAggregatorServicer_to_server(servicer, server):
     rpc_method_handlers = {
             'BookSummary': grpc.unary_stream_rpc_method_handler(
                     servicer.BookSummary,
-                    request_deserializer=orderbook__pb2.Empty.FromString,
+                    request_deserializer=orderbook__pb2.BookSummaryRequest.FromString,
                     response_serializer=orderbook__pb2.Summary.SerializeToString,
             ),
+            'GetFeedStats': grpc.unary_unary_rpc_method_handler(
+                    servicer.GetFeedStats,
+                    request_deserializer=orderbook__pb2.FeedStatsRequest.FromString,
+                    response_serializer=orderbook__pb2.FeedStatsResponse.SerializeToString,
+            ),
+            'StreamTrades': grpc.unary_stream_rpc_method_handler(
+                    servicer.StreamTrades,
+                    request_deserializer=orderbook__pb2.TradesRequest.FromString,
+                    response_serializer=orderbook__pb2.Trade.SerializeToString,
+            ),
+            'StreamCandles': grpc.unary_stream_rpc_method_handler(
+                    servicer.StreamCandles,
+                    request_deserializer=orderbook__pb2.CandlesRequest.FromString,
+                    response_serializer=orderbook__pb2.Candle.SerializeToString,
+            ),
+            'GetCandles': grpc.unary_unary_rpc_method_handler(
+                    servicer.GetCandles,
+                    request_deserializer=orderbook__pb2.CandlesRequest.FromString,
+                    response_serializer=orderbook__pb2.CandlesResponse.SerializeToString,
+            ),
+            'Analytics': grpc.unary_stream_rpc_method_handler(
+                    servicer.Analytics,
+                    request_deserializer=orderbook__pb2.AnalyticsRequest.FromString,
+                    response_serializer=orderbook__pb2.BookAnalytics.SerializeToString,
+            ),
+            'GetLiquidityStats': grpc.unary_unary_rpc_method_handler(
+                    servicer.GetLiquidityStats,
+                    request_deserializer=orderbook__pb2.LiquidityStatsRequest.FromString,
+                    response_serializer=orderbook__pb2.LiquidityStatsResponse.SerializeToString,
+            ),
+            'SubmitPaperOrder': grpc.unary_unary_rpc_method_handler(
+                    servicer.SubmitPaperOrder,
+                    request_deserializer=orderbook__pb2.PaperOrderRequest.FromString,
+                    response_serializer=orderbook__pb2.PaperOrder.SerializeToString,
+            ),
+            'CancelPaperOrder': grpc.unary_unary_rpc_method_handler(
+                    servicer.CancelPaperOrder,
+                    request_deserializer=orderbook__pb2.CancelPaperOrderRequest.FromString,
+                    response_serializer=orderbook__pb2.PaperOrder.SerializeToString,
+            ),
+            'StreamPaperFills': grpc.unary_stream_rpc_method_handler(
+                    servicer.StreamPaperFills,
+                    request_deserializer=orderbook__pb2.PaperFillsRequest.FromString,
+                    response_serializer=orderbook__pb2.PaperFill.SerializeToString,
+            ),
+            'GetPaperPositions': grpc.unary_unary_rpc_method_handler(
+                    servicer.GetPaperPositions,
+                    request_deserializer=orderbook__pb2.PaperPositionsRequest.FromString,
+                    response_serializer=orderbook__pb2.PaperPositionsResponse.SerializeToString,
+            ),
+            'RouteOrder': grpc.unary_unary_rpc_method_handler(
+                    servicer.RouteOrder,
+                    request_deserializer=orderbook__pb2.RouteOrderRequest.FromString,
+                    response_serializer=orderbook__pb2.RouteOrderResponse.SerializeToString,
+            ),
     }
     generic_handler = grpc.method_handlers_generic_handler(
             'orderbook.OrderbookAggregator', rpc_method_handlers)
@@ -84,7 +268,7 @@ class OrderbookAggregator(object):
             request,
             target,
             '/orderbook.OrderbookAggregator/BookSummary',
-            orderbook__pb2.Empty.SerializeToString,
+            orderbook__pb2.BookSummaryRequest.SerializeToString,
             orderbook__pb2.Summary.FromString,
             options,
             channel_credentials,
@@ -95,3 +279,300 @@ class OrderbookAggregator(object):
             timeout,
             metadata,
             _registered_method=True)
+
+    @staticmethod
+    def GetFeedStats(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_unary(
+            request,
+            target,
+            '/orderbook.OrderbookAggregator/GetFeedStats',
+            orderbook__pb2.FeedStatsRequest.SerializeToString,
+            orderbook__pb2.FeedStatsResponse.FromString,
+            options,
+            channel_credentials,
+            insecure,
+            call_credentials,
+            compression,
+            wait_for_ready,
+            timeout,
+            metadata,
+            _registered_method=True)
+
+    @staticmethod
+    def StreamTrades(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_stream(
+            request,
+            target,
+            '/orderbook.OrderbookAggregator/StreamTrades',
+            orderbook__pb2.TradesRequest.SerializeToString,
+            orderbook__pb2.Trade.FromString,
+            options,
+            channel_credentials,
+            insecure,
+            call_credentials,
+            compression,
+            wait_for_ready,
+            timeout,
+            metadata,
+            _registered_method=True)
+
+    @staticmethod
+    def StreamCandles(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_stream(
+            request,
+            target,
+            '/orderbook.OrderbookAggregator/StreamCandles',
+            orderbook__pb2.CandlesRequest.SerializeToString,
+            orderbook__pb2.Candle.FromString,
+            options,
+            channel_credentials,
+            insecure,
+            call_credentials,
+            compression,
+            wait_for_ready,
+            timeout,
+            metadata,
+            _registered_method=True)
+
+    @staticmethod
+    def GetCandles(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_unary(
+            request,
+            target,
+            '/orderbook.OrderbookAggregator/GetCandles',
+            orderbook__pb2.CandlesRequest.SerializeToString,
+            orderbook__pb2.CandlesResponse.FromString,
+            options,
+            channel_credentials,
+            insecure,
+            call_credentials,
+            compression,
+            wait_for_ready,
+            timeout,
+            metadata,
+            _registered_method=True)
+
+    @staticmethod
+    def Analytics(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_stream(
+            request,
+            target,
+            '/orderbook.OrderbookAggregator/Analytics',
+            orderbook__pb2.AnalyticsRequest.SerializeToString,
+            orderbook__pb2.BookAnalytics.FromString,
+            options,
+            channel_credentials,
+            insecure,
+            call_credentials,
+            compression,
+            wait_for_ready,
+            timeout,
+            metadata,
+            _registered_method=True)
+
+    @staticmethod
+    def GetLiquidityStats(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_unary(
+            request,
+            target,
+            '/orderbook.OrderbookAggregator/GetLiquidityStats',
+            orderbook__pb2.LiquidityStatsRequest.SerializeToString,
+            orderbook__pb2.LiquidityStatsResponse.FromString,
+            options,
+            channel_credentials,
+            insecure,
+            call_credentials,
+            compression,
+            wait_for_ready,
+            timeout,
+            metadata,
+            _registered_method=True)
+
+    @staticmethod
+    def SubmitPaperOrder(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_unary(
+            request,
+            target,
+            '/orderbook.OrderbookAggregator/SubmitPaperOrder',
+            orderbook__pb2.PaperOrderRequest.SerializeToString,
+            orderbook__pb2.PaperOrder.FromString,
+            options,
+            channel_credentials,
+            insecure,
+            call_credentials,
+            compression,
+            wait_for_ready,
+            timeout,
+            metadata,
+            _registered_method=True)
+
+    @staticmethod
+    def CancelPaperOrder(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_unary(
+            request,
+            target,
+            '/orderbook.OrderbookAggregator/CancelPaperOrder',
+            orderbook__pb2.CancelPaperOrderRequest.SerializeToString,
+            orderbook__pb2.PaperOrder.FromString,
+            options,
+            channel_credentials,
+            insecure,
+            call_credentials,
+            compression,
+            wait_for_ready,
+            timeout,
+            metadata,
+            _registered_method=True)
+
+    @staticmethod
+    def StreamPaperFills(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_stream(
+            request,
+            target,
+            '/orderbook.OrderbookAggregator/StreamPaperFills',
+            orderbook__pb2.PaperFillsRequest.SerializeToString,
+            orderbook__pb2.PaperFill.FromString,
+            options,
+            channel_credentials,
+            insecure,
+            call_credentials,
+            compression,
+            wait_for_ready,
+            timeout,
+            metadata,
+            _registered_method=True)
+
+    @staticmethod
+    def GetPaperPositions(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_unary(
+            request,
+            target,
+            '/orderbook.OrderbookAggregator/GetPaperPositions',
+            orderbook__pb2.PaperPositionsRequest.SerializeToString,
+            orderbook__pb2.PaperPositionsResponse.FromString,
+            options,
+            channel_credentials,
+            insecure,
+            call_credentials,
+            compression,
+            wait_for_ready,
+            timeout,
+            metadata,
+            _registered_method=True)
+
+    @staticmethod
+    def RouteOrder(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_unary(
+            request,
+            target,
+            '/orderbook.OrderbookAggregator/RouteOrder',
+            orderbook__pb2.RouteOrderRequest.SerializeToString,
+            orderbook__pb2.RouteOrderResponse.FromString,
+            options,
+            channel_credentials,
+            insecure,
+            call_credentials,
+            compression,
+            wait_for_ready,
+            timeout,
+            metadata,
+            _registered_method=True)
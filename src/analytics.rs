@@ -0,0 +1,182 @@
+use crate::combined_book::CombinedBookSnapshot;
+use crate::config::AnalyticsConfig;
+use crate::exchange::{Exchange, ExchangeOrder};
+
+/// `(bid size - ask size) / (bid size + ask size)` over the best `depth`
+/// levels per side, from -1 (all asks) to 1 (all bids).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Imbalance {
+    pub depth: usize,
+    pub imbalance: f64,
+}
+
+/// Size resting within `bps` of the mid on each side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthBand {
+    pub bps: f64,
+    /// `None` for all venues together.
+    pub exchange: Option<Exchange>,
+    pub bid_amount: f64,
+    pub ask_amount: f64,
+}
+
+/// Microstructure figures for one combined book. Prices are `None` while
+/// either side is empty.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookAnalytics {
+    pub mid: Option<f64>,
+    /// Mid weighted towards the side with less size at the top, i.e.
+    /// `(bid * ask size + ask * bid size) / (bid size + ask size)`.
+    pub microprice: Option<f64>,
+    pub imbalances: Vec<Imbalance>,
+    pub depth_bands: Vec<DepthBand>,
+}
+
+/// Computes the configured figures for `snapshot`. Only the levels the
+/// combined book keeps are seen, so wide bands on a shallow book understate
+/// the size available.
+pub fn compute(snapshot: &CombinedBookSnapshot, config: &AnalyticsConfig) -> BookAnalytics {
+    let (Some(bid), Some(ask)) = (snapshot.bids.first(), snapshot.asks.first()) else {
+        return BookAnalytics::default();
+    };
+    let mid = (bid.price + ask.price) / 2.0;
+    let top_size = bid.amount + ask.amount;
+    let microprice = if top_size > 0.0 {
+        (bid.price * ask.amount + ask.price * bid.amount) / top_size
+    } else {
+        mid
+    };
+
+    let imbalances = config
+        .imbalance_depths
+        .iter()
+        .map(|&depth| {
+            let bids = total(snapshot.bids.iter().take(depth));
+            let asks = total(snapshot.asks.iter().take(depth));
+            Imbalance {
+                depth,
+                imbalance: if bids + asks > 0.0 {
+                    (bids - asks) / (bids + asks)
+                } else {
+                    0.0
+                },
+            }
+        })
+        .collect();
+
+    let mut venues: Vec<Exchange> = Vec::new();
+    for order in snapshot.bids.iter().chain(&snapshot.asks) {
+        if !venues.contains(&order.exchange) {
+            venues.push(order.exchange);
+        }
+    }
+    let mut depth_bands = Vec::new();
+    for &bps in &config.depth_bands_bps {
+        let distance = mid * bps / 10_000.0;
+        let within = |levels: &[ExchangeOrder], exchange: Option<Exchange>| {
+            total(levels.iter().filter(|level| {
+                (level.price - mid).abs() <= distance
+                    && exchange.is_none_or(|exchange| level.exchange == exchange)
+            }))
+        };
+        for exchange in std::iter::once(None).chain(venues.iter().copied().map(Some)) {
+            depth_bands.push(DepthBand {
+                bps,
+                exchange,
+                bid_amount: within(&snapshot.bids, exchange),
+                ask_amount: within(&snapshot.asks, exchange),
+            });
+        }
+    }
+
+    BookAnalytics {
+        mid: Some(mid),
+        microprice: Some(microprice),
+        imbalances,
+        depth_bands,
+    }
+}
+
+fn total<'a>(levels: impl Iterator<Item = &'a ExchangeOrder>) -> f64 {
+    levels.map(|level| level.amount).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_computes_prices_imbalance_and_bands() {
+        let snapshot = CombinedBookSnapshot {
+            spread: 2.0,
+            bids: vec![
+                ExchangeOrder::new(Exchange::BINANCE, 999.0, 3.0),
+                ExchangeOrder::new(Exchange::BITSTAMP, 998.0, 2.0),
+                ExchangeOrder::new(Exchange::BINANCE, 990.0, 5.0),
+            ],
+            asks: vec![
+                ExchangeOrder::new(Exchange::BITSTAMP, 1001.0, 1.0),
+                ExchangeOrder::new(Exchange::BINANCE, 1002.0, 4.0),
+            ],
+        };
+        let config = AnalyticsConfig {
+            imbalance_depths: vec![1, 10],
+            depth_bands_bps: vec![25.0],
+        };
+
+        let analytics = compute(&snapshot, &config);
+
+        assert_eq!(analytics.mid, Some(1000.0));
+        assert_eq!(analytics.microprice, Some((999.0 + 1001.0 * 3.0) / 4.0));
+        assert_eq!(
+            analytics.imbalances,
+            vec![
+                Imbalance {
+                    depth: 1,
+                    imbalance: 0.5
+                },
+                Imbalance {
+                    depth: 10,
+                    imbalance: 5.0 / 15.0
+                },
+            ]
+        );
+        // 25bps of 1000 is 2.5 either side, which leaves out the 990 bid.
+        assert_eq!(
+            analytics.depth_bands,
+            vec![
+                DepthBand {
+                    bps: 25.0,
+                    exchange: None,
+                    bid_amount: 5.0,
+                    ask_amount: 5.0,
+                },
+                DepthBand {
+                    bps: 25.0,
+                    exchange: Some(Exchange::BINANCE),
+                    bid_amount: 3.0,
+                    ask_amount: 4.0,
+                },
+                DepthBand {
+                    bps: 25.0,
+                    exchange: Some(Exchange::BITSTAMP),
+                    bid_amount: 2.0,
+                    ask_amount: 1.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_one_sided_book_has_no_prices() {
+        let snapshot = CombinedBookSnapshot {
+            spread: 0.0,
+            bids: vec![ExchangeOrder::new(Exchange::BINANCE, 999.0, 3.0)],
+            asks: Vec::new(),
+        };
+        assert_eq!(
+            compute(&snapshot, &AnalyticsConfig::default()),
+            BookAnalytics::default()
+        );
+    }
+}
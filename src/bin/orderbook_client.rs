@@ -0,0 +1,173 @@
+use clap::Parser;
+use futures_util::StreamExt;
+use orderbooks::grpc::client::OrderbookClient;
+use orderbooks::grpc::tls::load_client_tls_config;
+use orderbooks::json::JsonBook;
+use orderbooks::orderbook::{BookSummaryRequest, Level, Summary, TradesRequest};
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::time::Duration;
+use tracing::error;
+
+/// Subscribes to an orderbook aggregator and prints the combined book.
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(long, default_value = "http://127.0.0.1:50051")]
+    addr: String,
+    /// Defaults to the server's instrument.
+    #[arg(long, default_value = "")]
+    instrument: String,
+    /// Only include these venues; repeat for several.
+    #[arg(long = "exchange")]
+    exchanges: Vec<String>,
+    /// Levels per side; 0 for the full book.
+    #[arg(long, default_value_t = 0)]
+    depth: u32,
+    #[arg(long, default_value_t = 0)]
+    min_interval_ms: u32,
+    /// Send the latest book once a held-back interval passes.
+    #[arg(long)]
+    conflate: bool,
+    /// Only receive updates that move the best bid or ask.
+    #[arg(long)]
+    top_of_book_only: bool,
+    #[arg(long, env = "ORDERBOOK_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    /// Connect over TLS, verifying the server with this CA instead of the
+    /// system roots.
+    #[arg(long)]
+    ca_cert: Option<String>,
+    /// Client certificate and key for mutual TLS.
+    #[arg(long, requires = "client_key")]
+    client_cert: Option<String>,
+    #[arg(long, requires = "client_cert")]
+    client_key: Option<String>,
+    /// Server name to verify, if it differs from the address.
+    #[arg(long)]
+    domain: Option<String>,
+    /// Print JSON lines instead of a ladder.
+    #[arg(long)]
+    json: bool,
+    /// Append every summary to this file as JSON lines.
+    #[arg(long)]
+    record: Option<String>,
+    #[arg(long, default_value_t = 1000)]
+    reconnect_delay_ms: u64,
+    /// Print per-venue feed statistics and exit.
+    #[arg(long)]
+    feed_stats: bool,
+    /// Print trades instead of the book.
+    #[arg(long)]
+    trades: bool,
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
+    if let Err(err) = run(Args::parse()).await {
+        error!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let mut client = OrderbookClient::new(&args.addr)?
+        .with_reconnect_delay(Duration::from_millis(args.reconnect_delay_ms));
+    if let Some(token) = args.token {
+        client = client.with_token(token);
+    }
+    let tls =
+        args.addr.starts_with("https://") || args.ca_cert.is_some() || args.client_cert.is_some();
+    if tls {
+        let identity = args.client_cert.as_deref().zip(args.client_key.as_deref());
+        client = client.with_tls(load_client_tls_config(
+            args.ca_cert.as_deref(),
+            identity,
+            args.domain.as_deref(),
+        )?)?;
+    }
+
+    if args.feed_stats {
+        for venue in client.feed_stats().await?.venues {
+            println!("{:#?}", venue);
+        }
+        return Ok(());
+    }
+
+    if args.trades {
+        let request = TradesRequest {
+            instrument: args.instrument,
+            exchanges: args.exchanges,
+        };
+        let mut trades = client.stream_trades(request).await?;
+        while let Some(trade) = trades.next().await {
+            let trade = trade?;
+            println!(
+                "{:<10} {:<4} {:>16.8} {:>16.8}  {}",
+                trade.exchange,
+                trade.aggressor().as_str_name(),
+                trade.price,
+                trade.amount,
+                trade.trade_id
+            );
+        }
+        return Ok(());
+    }
+
+    let mut recorder = match &args.record {
+        Some(path) => Some(BufWriter::new(
+            OpenOptions::new().create(true).append(true).open(path)?,
+        )),
+        None => None,
+    };
+
+    let request = BookSummaryRequest {
+        instrument: args.instrument,
+        exchanges: args.exchanges,
+        depth: args.depth,
+        min_interval_ms: args.min_interval_ms,
+        conflate: args.conflate,
+        top_of_book_only: args.top_of_book_only,
+    };
+    let mut summaries = Box::pin(client.subscribe(request));
+    while let Some(result) = summaries.next().await {
+        let summary = match result {
+            Ok(summary) => summary,
+            Err(err) if err.is_retryable() => continue,
+            Err(err) => return Err(err.into()),
+        };
+        if let Some(recorder) = recorder.as_mut() {
+            serde_json::to_writer(&mut *recorder, &JsonBook::from(&summary))?;
+            writeln!(recorder)?;
+            recorder.flush()?;
+        }
+        if args.json {
+            println!("{}", serde_json::to_string(&JsonBook::from(&summary))?);
+        } else {
+            print_ladder(&summary);
+        }
+    }
+    Ok(())
+}
+
+/// Redraws the book with asks above the spread and bids below.
+fn print_ladder(summary: &Summary) {
+    let row = |side: &str, level: &Level| {
+        println!(
+            "{:<4} {:>16.8} {:>16.8}  {}",
+            side, level.price, level.amount, level.exchange
+        )
+    };
+    print!("\x1b[2J\x1b[H");
+    println!("{:<4} {:>16} {:>16}  exchange", "", "price", "amount");
+    for ask in summary.asks.iter().rev() {
+        row("ask", ask);
+    }
+    println!("---- spread {:.8}", summary.spread);
+    for bid in &summary.bids {
+        row("bid", bid);
+    }
+}
@@ -0,0 +1,242 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use orderbooks::orderbook::{Summary, VenueStats};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Window the update rate is averaged over.
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+/// What a feed task reports for one instrument.
+pub enum FeedEvent {
+    Book(Summary),
+    Stats(Vec<VenueStats>),
+    Error(String),
+}
+
+pub struct InstrumentView {
+    pub name: String,
+    pub book: Option<Summary>,
+    pub venues: Vec<VenueStats>,
+    pub error: Option<String>,
+    updates: VecDeque<Instant>,
+}
+
+impl InstrumentView {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            book: None,
+            venues: Vec::new(),
+            error: None,
+            updates: VecDeque::new(),
+        }
+    }
+
+    /// Book updates per second over the last few seconds.
+    pub fn update_rate(&self, now: Instant) -> f64 {
+        let recent = self
+            .updates
+            .iter()
+            .filter(|at| now.duration_since(**at) <= RATE_WINDOW)
+            .count();
+        recent as f64 / RATE_WINDOW.as_secs_f64()
+    }
+}
+
+/// Screen state: one view per instrument, of which one is shown, filtered
+/// to all venues or just one.
+pub struct App {
+    pub instruments: Vec<InstrumentView>,
+    pub selected: usize,
+    /// Every venue seen so far, in order of appearance, which also fixes
+    /// each venue's colour.
+    pub venues: Vec<String>,
+    pub venue_filter: Option<usize>,
+    pub quit: bool,
+}
+
+impl App {
+    pub fn new(instruments: Vec<String>) -> Self {
+        Self {
+            instruments: instruments.into_iter().map(InstrumentView::new).collect(),
+            selected: 0,
+            venues: Vec::new(),
+            venue_filter: None,
+            quit: false,
+        }
+    }
+
+    pub fn current(&self) -> &InstrumentView {
+        &self.instruments[self.selected]
+    }
+
+    pub fn on_feed_event(&mut self, instrument: usize, event: FeedEvent, now: Instant) {
+        let view = &mut self.instruments[instrument];
+        match event {
+            FeedEvent::Book(book) => {
+                view.updates.push_back(now);
+                while view
+                    .updates
+                    .front()
+                    .is_some_and(|at| now.duration_since(*at) > RATE_WINDOW)
+                {
+                    view.updates.pop_front();
+                }
+                view.error = None;
+                let names: Vec<String> = book
+                    .bids
+                    .iter()
+                    .chain(&book.asks)
+                    .map(|level| level.exchange.clone())
+                    .collect();
+                view.book = Some(book);
+                names.into_iter().for_each(|name| self.add_venue(name));
+            }
+            FeedEvent::Stats(venues) => {
+                let names: Vec<String> = venues.iter().map(|v| v.exchange.clone()).collect();
+                view.venues = venues;
+                names.into_iter().for_each(|name| self.add_venue(name));
+            }
+            FeedEvent::Error(error) => view.error = Some(error),
+        }
+    }
+
+    fn add_venue(&mut self, name: String) {
+        if !self.venues.contains(&name) {
+            self.venues.push(name);
+        }
+    }
+
+    pub fn venue_index(&self, name: &str) -> usize {
+        self.venues.iter().position(|v| v == name).unwrap_or(0)
+    }
+
+    pub fn venue_filter_name(&self) -> Option<&str> {
+        self.venue_filter.map(|index| self.venues[index].as_str())
+    }
+
+    /// The selected instrument's book, limited to the selected venue.
+    pub fn visible_book(&self) -> Option<Summary> {
+        let book = self.current().book.as_ref()?;
+        let Some(venue) = self.venue_filter_name() else {
+            return Some(book.clone());
+        };
+        let bids: Vec<_> = book
+            .bids
+            .iter()
+            .filter(|l| l.exchange == venue)
+            .cloned()
+            .collect();
+        let asks: Vec<_> = book
+            .asks
+            .iter()
+            .filter(|l| l.exchange == venue)
+            .cloned()
+            .collect();
+        let spread = match (asks.first(), bids.first()) {
+            (Some(ask), Some(bid)) => ask.price - bid.price,
+            _ => 0.0,
+        };
+        Some(Summary { spread, bids, asks })
+    }
+
+    pub fn on_key(&mut self, key: KeyEvent) {
+        let instruments = self.instruments.len();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Tab | KeyCode::Right => self.selected = (self.selected + 1) % instruments,
+            KeyCode::BackTab | KeyCode::Left => {
+                self.selected = (self.selected + instruments - 1) % instruments
+            }
+            // Cycles all venues -> first -> ... -> last -> all venues.
+            KeyCode::Char('v') | KeyCode::Down => {
+                self.venue_filter = match self.venue_filter {
+                    None if !self.venues.is_empty() => Some(0),
+                    Some(index) if index + 1 < self.venues.len() => Some(index + 1),
+                    _ => None,
+                }
+            }
+            KeyCode::Up => {
+                self.venue_filter = match self.venue_filter {
+                    None => self.venues.len().checked_sub(1),
+                    Some(0) => None,
+                    Some(index) => Some(index - 1),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orderbooks::orderbook::Level;
+
+    fn level(exchange: &str, price: f64) -> Level {
+        Level {
+            exchange: exchange.to_string(),
+            price,
+            amount: 1.0,
+            original_price: None,
+        }
+    }
+
+    fn book() -> Summary {
+        Summary {
+            spread: 0.5,
+            bids: vec![level("Binance", 100.0), level("Bitstamp", 99.0)],
+            asks: vec![level("Bitstamp", 100.5), level("Binance", 101.0)],
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_venue_filter_cycles_and_recomputes_spread() {
+        let mut app = App::new(vec!["ethbtc".to_string()]);
+        app.on_feed_event(0, FeedEvent::Book(book()), Instant::now());
+        assert_eq!(app.venues, vec!["Binance", "Bitstamp"]);
+
+        app.on_key(key(KeyCode::Char('v')));
+        let visible = app.visible_book().unwrap();
+        assert_eq!(app.venue_filter_name(), Some("Binance"));
+        assert_eq!(visible.bids, vec![level("Binance", 100.0)]);
+        assert_eq!(visible.spread, 1.0);
+
+        app.on_key(key(KeyCode::Char('v')));
+        app.on_key(key(KeyCode::Char('v')));
+        assert_eq!(app.venue_filter, None);
+        assert_eq!(app.visible_book().unwrap().spread, 0.5);
+    }
+
+    #[test]
+    fn test_switches_instruments_and_tracks_rates() {
+        let mut app = App::new(vec!["ethbtc".to_string(), "btcusd".to_string()]);
+        let start = Instant::now();
+        for i in 0..10 {
+            app.on_feed_event(
+                1,
+                FeedEvent::Book(book()),
+                start + Duration::from_millis(i * 100),
+            );
+        }
+
+        app.on_key(key(KeyCode::Tab));
+        assert_eq!(app.current().name, "btcusd");
+        assert_eq!(
+            app.current().update_rate(start + Duration::from_secs(1)),
+            2.0
+        );
+        assert_eq!(
+            app.current().update_rate(start + Duration::from_secs(10)),
+            0.0
+        );
+        app.on_key(key(KeyCode::Tab));
+        assert_eq!(app.current().name, "ethbtc");
+        assert!(app.visible_book().is_none());
+    }
+}
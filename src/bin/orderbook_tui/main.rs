@@ -0,0 +1,210 @@
+mod app;
+mod ui;
+
+use app::{App, FeedEvent};
+use clap::Parser;
+use crossterm::event::{Event, EventStream, KeyEventKind};
+use futures_util::StreamExt;
+use orderbooks::config::{load_config, Config};
+use orderbooks::grpc::client::OrderbookClient;
+use orderbooks::grpc::tls::load_client_tls_config;
+use orderbooks::orderbook::{BookSummaryRequest, Summary, VenueStats};
+use orderbooks::OrderbookProcessor;
+use std::error::Error;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::time::interval;
+
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+
+type FeedSender = UnboundedSender<(usize, FeedEvent)>;
+
+/// Live view of the combined book, either from an aggregator over gRPC or
+/// from venues connected in-process.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Aggregate in-process using this config instead of connecting to a
+    /// server.
+    #[arg(long, conflicts_with = "addr")]
+    config: Option<String>,
+    #[arg(long, default_value = "http://127.0.0.1:50051")]
+    addr: String,
+    /// Instruments to switch between; repeat for several. Defaults to the
+    /// configured or server's instrument.
+    #[arg(long = "instrument")]
+    instruments: Vec<String>,
+    #[arg(long, env = "ORDERBOOK_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    /// Connect over TLS, verifying the server with this CA.
+    #[arg(long)]
+    ca_cert: Option<String>,
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(err) = run(Args::parse()).await {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let (sender, mut feed) = unbounded_channel();
+    let instruments = match &args.config {
+        Some(path) => spawn_in_process(&load_config(path)?, args.instruments, &sender)?,
+        None => spawn_grpc(&args, &sender)?,
+    };
+
+    let mut app = App::new(instruments);
+    let mut terminal = ratatui::init();
+    let mut events = EventStream::new();
+    let mut redraw = interval(REDRAW_INTERVAL);
+    let result = loop {
+        if let Err(err) = terminal.draw(|frame| ui::draw(frame, &app)) {
+            break Err(err.into());
+        }
+        tokio::select! {
+            Some(event) = events.next() => match event {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => app.on_key(key),
+                Ok(_) => {}
+                Err(err) => break Err(err.into()),
+            },
+            Some((instrument, event)) = feed.recv() => {
+                app.on_feed_event(instrument, event, Instant::now())
+            }
+            _ = redraw.tick() => {}
+        }
+        if app.quit {
+            break Ok(());
+        }
+    };
+    ratatui::restore();
+    result
+}
+
+/// Runs a processor per instrument over the configured venues.
+fn spawn_in_process(
+    config: &Config,
+    instruments: Vec<String>,
+    sender: &FeedSender,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let instruments = if instruments.is_empty() {
+        vec![config.trading_pair.clone()]
+    } else {
+        instruments
+    };
+    for (index, instrument) in instruments.iter().enumerate() {
+        let mut builder = OrderbookProcessor::builder(instrument, config.max_orders);
+        for exchange in &config.exchanges {
+            builder = builder.with_exchange(exchange);
+        }
+        for (exchange, heartbeat) in &config.heartbeats {
+            builder = builder.with_heartbeat(exchange, heartbeat.clone());
+        }
+        let processor = builder.build()?;
+
+        let mut snapshots = processor.subscribe();
+        let books = sender.clone();
+        tokio::spawn(async move {
+            while snapshots.changed().await.is_ok() {
+                let book = Summary::from(snapshots.borrow_and_update().clone());
+                if books.send((index, FeedEvent::Book(book))).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let feed_stats = processor.feed_stats();
+        let stats = sender.clone();
+        tokio::spawn(async move {
+            let mut ticks = interval(STATS_INTERVAL);
+            loop {
+                ticks.tick().await;
+                let venues = feed_stats
+                    .venues()
+                    .into_iter()
+                    .map(VenueStats::from)
+                    .collect();
+                if stats.send((index, FeedEvent::Stats(venues))).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let errors = sender.clone();
+        tokio::spawn(async move {
+            if let Err(err) = processor.run().await {
+                let _ = errors.send((index, FeedEvent::Error(err.to_string())));
+            }
+        });
+    }
+    Ok(instruments)
+}
+
+/// Subscribes to each instrument and polls the server's feed statistics.
+fn spawn_grpc(args: &Args, sender: &FeedSender) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut client = OrderbookClient::new(&args.addr)?;
+    if let Some(token) = &args.token {
+        client = client.with_token(token.clone());
+    }
+    if args.addr.starts_with("https://") || args.ca_cert.is_some() {
+        client = client.with_tls(load_client_tls_config(args.ca_cert.as_deref(), None, None)?)?;
+    }
+    // An empty instrument asks for the server's default.
+    let instruments = if args.instruments.is_empty() {
+        vec![String::new()]
+    } else {
+        args.instruments.clone()
+    };
+
+    for (index, instrument) in instruments.iter().enumerate() {
+        let request = BookSummaryRequest {
+            instrument: instrument.clone(),
+            ..Default::default()
+        };
+        let mut summaries = Box::pin(client.subscribe(request));
+        let books = sender.clone();
+        tokio::spawn(async move {
+            while let Some(result) = summaries.next().await {
+                let event = match result {
+                    Ok(book) => FeedEvent::Book(book),
+                    Err(err) => FeedEvent::Error(err.to_string()),
+                };
+                if books.send((index, event)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    let (client, stats) = (client.clone(), sender.clone());
+    let count = instruments.len();
+    tokio::spawn(async move {
+        let mut ticks = interval(STATS_INTERVAL);
+        loop {
+            ticks.tick().await;
+            // Venue health is per server, so every instrument shows it.
+            let Ok(response) = client.feed_stats().await else {
+                continue;
+            };
+            for index in 0..count {
+                let event = FeedEvent::Stats(response.venues.clone());
+                if stats.send((index, event)).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(instruments
+        .into_iter()
+        .map(|name| {
+            if name.is_empty() {
+                "default".to_string()
+            } else {
+                name
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,232 @@
+use crate::app::App;
+use orderbooks::exchange::unix_time_us;
+use orderbooks::orderbook::{Level, VenueStats};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Bar, BarChart, BarGroup, Block, Paragraph, Row, Table, Tabs};
+use ratatui::Frame;
+use std::time::Instant;
+
+const VENUE_COLOURS: [Color; 8] = [
+    Color::Yellow,
+    Color::Cyan,
+    Color::Magenta,
+    Color::LightBlue,
+    Color::LightGreen,
+    Color::LightRed,
+    Color::White,
+    Color::LightYellow,
+];
+
+/// A venue with no update for this long is shown as stale.
+const STALE_AFTER_US: u64 = 5_000_000;
+
+fn venue_colour(app: &App, venue: &str) -> Color {
+    VENUE_COLOURS[app.venue_index(venue) % VENUE_COLOURS.len()]
+}
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let venue_rows = app.current().venues.len().max(1) as u16;
+    let [tabs, header, body, venues, help] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(1),
+        Constraint::Min(6),
+        Constraint::Length(venue_rows + 3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [ladder, depth] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(body);
+
+    draw_tabs(frame, app, tabs);
+    draw_header(frame, app, header);
+    draw_ladder(frame, app, ladder);
+    draw_depth(frame, app, depth);
+    draw_venues(frame, app, venues);
+    frame.render_widget(
+        Paragraph::new("Tab/←→ instrument   v/↑↓ venue   q quit").dark_gray(),
+        help,
+    );
+}
+
+fn draw_tabs(frame: &mut Frame, app: &App, area: Rect) {
+    let titles = app.instruments.iter().map(|view| view.name.clone());
+    let tabs = Tabs::new(titles)
+        .select(app.selected)
+        .highlight_style(Style::new().bold().reversed())
+        .block(Block::bordered().title(" Instruments "));
+    frame.render_widget(tabs, area);
+}
+
+fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
+    let view = app.current();
+    let venue = app.venue_filter_name().unwrap_or("all venues");
+    let mut spans = vec![
+        Span::raw(" Spread: "),
+        Span::raw(
+            app.visible_book()
+                .map_or("-".to_string(), |book| format!("{:.8}", book.spread)),
+        )
+        .bold(),
+        Span::raw(format!(
+            "   Updates/s: {:.1}   Venues: ",
+            view.update_rate(Instant::now())
+        )),
+        Span::raw(venue).bold(),
+    ];
+    if let Some(error) = &view.error {
+        spans.push(Span::raw(format!("   {}", error)).red());
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+fn draw_ladder(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::bordered().title(" Combined book ");
+    let Some(book) = app.visible_book() else {
+        frame.render_widget(Paragraph::new("Waiting for data...").block(block), area);
+        return;
+    };
+    let row = |level: &Level, side: Color| {
+        Row::new(vec![
+            Span::raw(format!("{:.8}", level.price)).fg(side),
+            Span::raw(format!("{:.8}", level.amount)),
+            Span::raw(level.exchange.clone()).fg(venue_colour(app, &level.exchange)),
+        ])
+    };
+    // Asks above the spread, best ask nearest it.
+    let rows: Vec<Row> = book
+        .asks
+        .iter()
+        .rev()
+        .map(|level| row(level, Color::Red))
+        .chain(std::iter::once(
+            Row::new(vec![format!("spread {:.8}", book.spread)])
+                .style(Style::new().add_modifier(Modifier::DIM)),
+        ))
+        .chain(book.bids.iter().map(|level| row(level, Color::Green)))
+        .collect();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(20),
+            Constraint::Length(20),
+            Constraint::Min(10),
+        ],
+    )
+    .header(Row::new(vec!["Price", "Amount", "Exchange"]).bold())
+    .block(block);
+    frame.render_widget(table, area);
+}
+
+/// Cumulative size at each level, asks above bids like the ladder.
+fn draw_depth(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::bordered().title(" Depth ");
+    let Some(book) = app.visible_book() else {
+        frame.render_widget(block, area);
+        return;
+    };
+    let cumulative = |levels: &[Level], colour: Color| {
+        let mut total = 0.0;
+        levels
+            .iter()
+            .map(|level| {
+                total += level.amount;
+                Bar::default()
+                    .value((total * 1_000.0) as u64)
+                    .text_value(format!("{:.4}", total))
+                    .label(Line::from(format!("{:.8}", level.price)))
+                    .style(Style::new().fg(colour))
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut bars = cumulative(&book.asks, Color::Red);
+    bars.reverse();
+    bars.extend(cumulative(&book.bids, Color::Green));
+
+    let chart = BarChart::default()
+        .direction(Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0)
+        .data(BarGroup::default().bars(&bars))
+        .block(block);
+    frame.render_widget(chart, area);
+}
+
+fn draw_venues(frame: &mut Frame, app: &App, area: Rect) {
+    let now_us = unix_time_us();
+    let rows = app.current().venues.iter().map(|venue: &VenueStats| {
+        let stale = now_us.saturating_sub(venue.last_received_time_us) > STALE_AFTER_US;
+        let status = if stale {
+            Span::raw("stale").red()
+        } else {
+            Span::raw("live").green()
+        };
+        let latency = venue.latency.as_ref();
+        Row::new(vec![
+            Span::raw(venue.exchange.clone()).fg(venue_colour(app, &venue.exchange)),
+            status,
+            Span::raw(venue.messages.to_string()),
+            Span::raw(latency.map_or("-".to_string(), |l| format!("{}us", l.p50_us))),
+            Span::raw(latency.map_or("-".to_string(), |l| format!("{}us", l.p99_us))),
+            Span::raw(latency.map_or("-".to_string(), |l| format!("{}us", l.clock_skew_us))),
+            Span::raw(venue.out_of_order.to_string()),
+            Span::raw(venue.sequence_gaps.to_string()),
+        ])
+    });
+    let table = Table::new(rows, [Constraint::Length(12); 8])
+        .header(
+            Row::new(vec![
+                "Venue",
+                "Status",
+                "Messages",
+                "p50",
+                "p99",
+                "Skew",
+                "Out of order",
+                "Gaps",
+            ])
+            .bold(),
+        )
+        .block(Block::bordered().title(" Venues "));
+    frame.render_widget(table, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::FeedEvent;
+    use orderbooks::orderbook::Summary;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    #[test]
+    fn test_draws_ladder_and_venues() {
+        let mut app = App::new(vec!["ethbtc".to_string()]);
+        let level = |exchange: &str, price| Level {
+            exchange: exchange.to_string(),
+            price,
+            amount: 2.0,
+            original_price: None,
+        };
+        let book = Summary {
+            spread: 0.5,
+            bids: vec![level("Binance", 100.0)],
+            asks: vec![level("Bitstamp", 100.5)],
+        };
+        app.on_feed_event(0, FeedEvent::Book(book), Instant::now());
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("100.50000000"));
+        assert!(screen.contains("Bitstamp"));
+        assert!(screen.contains("spread 0.50000000"));
+    }
+}
@@ -0,0 +1,472 @@
+use crate::combined_book::CombinedBookSnapshot;
+use crate::exchange::{unix_time_us, Exchange, ExchangeOrder, Trade};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::warn;
+
+/// Closed bars kept per series by default.
+pub const DEFAULT_HISTORY: usize = 1000;
+
+/// How often bars are closed when no events arrive.
+const CLOSE_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Closed bars buffered per subscriber.
+const CANDLE_CHANNEL_CAPACITY: usize = 1024;
+
+/// What a bar's prices come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandleSource {
+    /// Mid of the best bid and ask; volume is always zero.
+    Mid,
+    Trades,
+}
+
+/// One bar series: a source, a venue or all venues, and an interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeriesKey {
+    pub source: CandleSource,
+    /// `None` for the consolidated series across venues.
+    pub exchange: Option<Exchange>,
+    pub interval: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candle {
+    pub key: SeriesKey,
+    /// Start of the bar in microseconds since the Unix epoch, aligned to
+    /// the interval.
+    pub open_time_us: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    pub trades: u64,
+}
+
+impl Candle {
+    fn open(key: SeriesKey, open_time_us: u64, price: f64) -> Self {
+        Candle {
+            key,
+            open_time_us,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: 0.0,
+            trades: 0,
+        }
+    }
+
+    fn update(&mut self, price: f64) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+    }
+
+    fn close_time_us(&self) -> u64 {
+        self.open_time_us + self.key.interval.as_micros() as u64
+    }
+}
+
+struct Series {
+    key: SeriesKey,
+    current: Option<Candle>,
+    history: VecDeque<Candle>,
+}
+
+struct CandlesInner {
+    intervals: Vec<Duration>,
+    history: usize,
+    series: Vec<Series>,
+}
+
+impl CandlesInner {
+    fn series_mut(&mut self, key: SeriesKey) -> &mut Series {
+        match self.series.iter().position(|s| s.key == key) {
+            Some(index) => &mut self.series[index],
+            None => {
+                self.series.push(Series {
+                    key,
+                    current: None,
+                    history: VecDeque::new(),
+                });
+                self.series.last_mut().expect("series was just added")
+            }
+        }
+    }
+
+    /// Adds a price to every interval of one venue's (or the consolidated)
+    /// series, returning the bars it closed.
+    fn record(
+        &mut self,
+        source: CandleSource,
+        exchange: Option<Exchange>,
+        time_us: u64,
+        price: f64,
+        amount: f64,
+    ) -> Vec<Candle> {
+        let mut closed = Vec::new();
+        let history = self.history;
+        for interval in self.intervals.clone() {
+            let key = SeriesKey {
+                source,
+                exchange,
+                interval,
+            };
+            let series = self.series_mut(key);
+            if let Some(current) = &series.current {
+                if time_us >= current.close_time_us() {
+                    closed.extend(series.close(history));
+                }
+            }
+            let interval_us = (interval.as_micros() as u64).max(1);
+            let candle = series
+                .current
+                .get_or_insert_with(|| Candle::open(key, time_us - time_us % interval_us, price));
+            candle.update(price);
+            if source == CandleSource::Trades {
+                candle.volume += amount;
+                candle.trades += 1;
+            }
+        }
+        closed
+    }
+}
+
+impl Series {
+    fn close(&mut self, history: usize) -> Option<Candle> {
+        let candle = self.current.take()?;
+        if self.history.len() == history {
+            self.history.pop_front();
+        }
+        self.history.push_back(candle.clone());
+        Some(candle)
+    }
+}
+
+/// OHLCV bars per venue and consolidated, built from trades and book mids
+/// and timed by the server's receive clock. Closed bars are kept for
+/// queries and broadcast to subscribers. Cheap to clone.
+#[derive(Clone)]
+pub struct Candles {
+    inner: Arc<Mutex<CandlesInner>>,
+    sender: broadcast::Sender<Candle>,
+}
+
+impl Candles {
+    pub fn new(intervals: Vec<Duration>, history: usize) -> Self {
+        let (sender, _) = broadcast::channel(CANDLE_CHANNEL_CAPACITY);
+        Self {
+            inner: Arc::new(Mutex::new(CandlesInner {
+                intervals,
+                history: history.max(1),
+                series: Vec::new(),
+            })),
+            sender,
+        }
+    }
+
+    pub fn intervals(&self) -> Vec<Duration> {
+        let inner = self.inner.lock().expect("candles lock poisoned");
+        inner.intervals.clone()
+    }
+
+    pub fn record_trade(&self, trade: &Trade) {
+        let time_us = trade.received_time_us;
+        let mut inner = self.inner.lock().expect("candles lock poisoned");
+        let mut closed = Vec::new();
+        for exchange in [Some(trade.exchange), None] {
+            closed.extend(inner.record(
+                CandleSource::Trades,
+                exchange,
+                time_us,
+                trade.price,
+                trade.amount,
+            ));
+        }
+        drop(inner);
+        self.publish(closed);
+    }
+
+    /// Records the consolidated mid from the merged book.
+    pub fn record_snapshot(&self, snapshot: &CombinedBookSnapshot, time_us: u64) {
+        if let Some(mid) = mid(snapshot.bids.first(), snapshot.asks.first()) {
+            self.record_mids(vec![(None, mid)], time_us);
+        }
+    }
+
+    /// Records each venue's own mid from its best bid and ask, which the
+    /// merged book may have cut for depth.
+    pub fn record_venue_tops(&self, tops: &CombinedBookSnapshot, time_us: u64) {
+        let mut venues: Vec<Exchange> = Vec::new();
+        for order in tops.bids.iter().chain(&tops.asks) {
+            if !venues.contains(&order.exchange) {
+                venues.push(order.exchange);
+            }
+        }
+        let mids = venues
+            .into_iter()
+            .filter_map(|venue| {
+                let bid = tops.bids.iter().find(|o| o.exchange == venue);
+                let ask = tops.asks.iter().find(|o| o.exchange == venue);
+                Some((Some(venue), mid(bid, ask)?))
+            })
+            .collect();
+        self.record_mids(mids, time_us);
+    }
+
+    fn record_mids(&self, mids: Vec<(Option<Exchange>, f64)>, time_us: u64) {
+        let mut inner = self.inner.lock().expect("candles lock poisoned");
+        let mut closed = Vec::new();
+        for (exchange, mid) in mids {
+            closed.extend(inner.record(CandleSource::Mid, exchange, time_us, mid, 0.0));
+        }
+        drop(inner);
+        self.publish(closed);
+    }
+
+    /// Closes bars whose interval has ended by `now_us`. Mid bars carry
+    /// their close into the next bar, since the book still has a mid;
+    /// trade bars only reopen on the next trade.
+    pub fn close_due(&self, now_us: u64) {
+        let mut inner = self.inner.lock().expect("candles lock poisoned");
+        let history = inner.history;
+        let mut closed = Vec::new();
+        for series in &mut inner.series {
+            let Some(close_time_us) = series.current.as_ref().map(Candle::close_time_us) else {
+                continue;
+            };
+            if now_us < close_time_us {
+                continue;
+            }
+            let Some(candle) = series.close(history) else {
+                continue;
+            };
+            if series.key.source == CandleSource::Mid {
+                let interval_us = (series.key.interval.as_micros() as u64).max(1);
+                series.current = Some(Candle::open(
+                    series.key,
+                    now_us - now_us % interval_us,
+                    candle.close,
+                ));
+            }
+            closed.push(candle);
+        }
+        drop(inner);
+        self.publish(closed);
+    }
+
+    /// The last `count` closed bars of a series, oldest first; zero for all
+    /// that are kept.
+    pub fn history(&self, key: &SeriesKey, count: usize) -> Vec<Candle> {
+        let inner = self.inner.lock().expect("candles lock poisoned");
+        let Some(series) = inner.series.iter().find(|s| s.key == *key) else {
+            return Vec::new();
+        };
+        let count = if count == 0 {
+            series.history.len()
+        } else {
+            count.min(series.history.len())
+        };
+        series
+            .history
+            .iter()
+            .skip(series.history.len() - count)
+            .cloned()
+            .collect()
+    }
+
+    /// Receives every bar as it closes.
+    pub fn subscribe(&self) -> broadcast::Receiver<Candle> {
+        self.sender.subscribe()
+    }
+
+    fn publish(&self, closed: Vec<Candle>) {
+        for candle in closed {
+            // No subscribers is not an error; the bar is still in history.
+            let _ = self.sender.send(candle);
+        }
+    }
+
+    /// Builds bars from the merged book, each venue's top of book and, if
+    /// given, trades until the book senders are dropped.
+    pub async fn run(
+        self,
+        mut snapshots: watch::Receiver<CombinedBookSnapshot>,
+        mut venue_tops: watch::Receiver<CombinedBookSnapshot>,
+        mut trades: Option<broadcast::Receiver<Trade>>,
+    ) {
+        let mut close_check = interval(CLOSE_CHECK_INTERVAL);
+        close_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                changed = snapshots.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    let snapshot = snapshots.borrow_and_update().clone();
+                    self.record_snapshot(&snapshot, unix_time_us());
+                }
+                changed = venue_tops.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    let tops = venue_tops.borrow_and_update().clone();
+                    self.record_venue_tops(&tops, unix_time_us());
+                }
+                Some(result) = next_trade(&mut trades) => match result {
+                    Ok(trade) => self.record_trade(&trade),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!(missed, "Candle builder lagged, bars are missing trades");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => trades = None,
+                },
+                _ = close_check.tick() => self.close_due(unix_time_us()),
+            }
+        }
+    }
+}
+
+/// Waits forever once trades are disabled or their sender is gone.
+async fn next_trade(
+    trades: &mut Option<broadcast::Receiver<Trade>>,
+) -> Option<Result<Trade, broadcast::error::RecvError>> {
+    match trades {
+        Some(trades) => Some(trades.recv().await),
+        None => std::future::pending().await,
+    }
+}
+
+fn mid(bid: Option<&ExchangeOrder>, ask: Option<&ExchangeOrder>) -> Option<f64> {
+    Some((bid?.price + ask?.price) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combined_book::CombinedBook;
+    use crate::exchange::{Orderbook, TradeSide};
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn trade(exchange: Exchange, price: f64, amount: f64, received_time_us: u64) -> Trade {
+        Trade {
+            exchange,
+            price,
+            amount,
+            aggressor: TradeSide::Buy,
+            trade_id: 0,
+            event_time_us: received_time_us,
+            received_time_us,
+        }
+    }
+
+    fn key(source: CandleSource, exchange: Option<Exchange>) -> SeriesKey {
+        SeriesKey {
+            source,
+            exchange,
+            interval: SECOND,
+        }
+    }
+
+    #[test]
+    fn test_builds_trade_bars_per_venue_and_consolidated() {
+        let candles = Candles::new(vec![SECOND], 10);
+        let mut closed = candles.subscribe();
+        candles.record_trade(&trade(Exchange::BINANCE, 10.0, 1.0, 1_000_100));
+        candles.record_trade(&trade(Exchange::BITSTAMP, 12.0, 2.0, 1_200_000));
+        candles.record_trade(&trade(Exchange::BINANCE, 9.0, 0.5, 1_900_000));
+        candles.record_trade(&trade(Exchange::BINANCE, 11.0, 1.0, 2_000_000));
+
+        let consolidated = candles.history(&key(CandleSource::Trades, None), 0);
+        assert_eq!(consolidated.len(), 1);
+        let bar = &consolidated[0];
+        assert_eq!(bar.open_time_us, 1_000_000);
+        assert_eq!(
+            (bar.open, bar.high, bar.low, bar.close),
+            (10.0, 12.0, 9.0, 9.0)
+        );
+        assert_eq!((bar.volume, bar.trades), (3.5, 3));
+
+        let binance = candles.history(&key(CandleSource::Trades, Some(Exchange::BINANCE)), 0);
+        assert_eq!((binance[0].high, binance[0].volume), (10.0, 1.5));
+        // Bitstamp's bar is still open: no later trade or close check.
+        assert!(candles
+            .history(&key(CandleSource::Trades, Some(Exchange::BITSTAMP)), 0)
+            .is_empty());
+        assert_eq!(
+            closed.try_recv().unwrap().key.exchange,
+            Some(Exchange::BINANCE)
+        );
+        assert_eq!(closed.try_recv().unwrap().key.exchange, None);
+    }
+
+    #[test]
+    fn test_mid_bars_carry_over_and_history_is_bounded() {
+        let candles = Candles::new(vec![SECOND], 2);
+        let order = |exchange, price| ExchangeOrder::new(exchange, price, 1.0);
+        let snapshot = CombinedBookSnapshot {
+            spread: 1.0,
+            bids: vec![
+                order(Exchange::BINANCE, 100.0),
+                order(Exchange::BITSTAMP, 99.0),
+            ],
+            asks: vec![
+                order(Exchange::BITSTAMP, 101.0),
+                order(Exchange::BINANCE, 103.0),
+            ],
+        };
+        candles.record_snapshot(&snapshot, 1_500_000);
+        candles.record_venue_tops(&snapshot, 1_500_000);
+        for second in 2..6 {
+            candles.close_due(second * 1_000_000);
+        }
+
+        let consolidated = candles.history(&key(CandleSource::Mid, None), 0);
+        assert_eq!(consolidated.len(), 2);
+        assert_eq!(consolidated[0].open_time_us, 3_000_000);
+        assert_eq!(consolidated[1].close, 100.5);
+        let binance = candles.history(&key(CandleSource::Mid, Some(Exchange::BINANCE)), 1);
+        assert_eq!(binance.len(), 1);
+        assert_eq!(binance[0].close, 101.5);
+        assert_eq!(binance[0].open_time_us, 4_000_000);
+    }
+
+    #[test]
+    fn test_venue_mid_bars_include_venues_cut_from_the_merged_book() {
+        let candles = Candles::new(vec![SECOND], 10);
+        let mut book = CombinedBook::new(1);
+        for (exchange, bid, ask) in [
+            (Exchange::BINANCE, 100.0, 101.0),
+            (Exchange::BITSTAMP, 98.0, 103.0),
+        ] {
+            book.update(Orderbook {
+                bids: vec![ExchangeOrder::new(exchange, bid, 1.0)],
+                asks: vec![ExchangeOrder::new(exchange, ask, 1.0)],
+                ..Default::default()
+            });
+        }
+        let snapshot = book.get_snapshot();
+        assert!(snapshot
+            .bids
+            .iter()
+            .chain(&snapshot.asks)
+            .all(|o| o.exchange == Exchange::BINANCE));
+
+        candles.record_snapshot(&snapshot, 1_500_000);
+        candles.record_venue_tops(&book.venue_tops(), 1_500_000);
+        candles.close_due(2_000_000);
+
+        let consolidated = candles.history(&key(CandleSource::Mid, None), 0);
+        assert_eq!(consolidated[0].close, 100.5);
+        let bitstamp = candles.history(&key(CandleSource::Mid, Some(Exchange::BITSTAMP)), 0);
+        assert_eq!(bitstamp.len(), 1);
+        assert_eq!(bitstamp[0].close, 100.5);
+        let binance = candles.history(&key(CandleSource::Mid, Some(Exchange::BINANCE)), 0);
+        assert_eq!(binance[0].close, 100.5);
+    }
+}
@@ -1,4 +1,6 @@
-use crate::exchange::{ExchangeOrder, Orderbook};
+use crate::exchange::{Exchange, ExchangeOrder, Orderbook};
+use crate::instrument::{to_increment, Instrument};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, Default)]
 pub struct CombinedBookSnapshot {
@@ -7,86 +9,313 @@ pub struct CombinedBookSnapshot {
     pub bids: Vec<ExchangeOrder>,
 }
 
+impl CombinedBookSnapshot {
+    /// Restricts the book to `venues` (all venues if `None`) and the best
+    /// `depth` levels per side, recomputing the spread from what remains.
+    pub fn filtered(&self, venues: Option<&[Exchange]>, depth: usize) -> CombinedBookSnapshot {
+        let keep = |order: &&ExchangeOrder| venues.is_none_or(|v| v.contains(&order.exchange));
+        let bids: Vec<_> = self.bids.iter().filter(keep).take(depth).cloned().collect();
+        let asks: Vec<_> = self.asks.iter().filter(keep).take(depth).cloned().collect();
+        let spread = match (bids.first(), asks.first()) {
+            (Some(best_bid), Some(best_ask)) => best_ask.price - best_bid.price,
+            _ => 0.0,
+        };
+        CombinedBookSnapshot { spread, asks, bids }
+    }
+}
+
+/// Levels of the merged top-N view that an update added, resized or
+/// removed. A removed level is reported with an amount of zero.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookChanges {
+    pub bids: Vec<ExchangeOrder>,
+    pub asks: Vec<ExchangeOrder>,
+}
+
+impl BookChanges {
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Side {
+    Bids,
+    Asks,
+}
+
+impl Side {
+    /// Ranks by price, then by the larger amount.
+    fn is_better(self, a: &ExchangeOrder, b: &ExchangeOrder) -> bool {
+        match self {
+            Side::Bids => a.price > b.price || (a.price == b.price && a.amount > b.amount),
+            Side::Asks => a.price < b.price || (a.price == b.price && a.amount > b.amount),
+        }
+    }
+
+    fn is_better_price(self, a: f64, b: f64) -> bool {
+        match self {
+            Side::Bids => a > b,
+            Side::Asks => a < b,
+        }
+    }
+}
+
+/// The latest top `max_orders` levels quoted by one venue, best first.
+struct VenueLadder {
+    exchange: Exchange,
+    bids: Vec<ExchangeOrder>,
+    asks: Vec<ExchangeOrder>,
+}
+
+impl VenueLadder {
+    fn side(&self, side: Side) -> &[ExchangeOrder] {
+        match side {
+            Side::Bids => &self.bids,
+            Side::Asks => &self.asks,
+        }
+    }
+}
+
+/// Keeps a ladder per venue and a merged top-N view across them. An update
+/// only replaces its venue's ladder, and its levels are merged back into
+/// the view only when the venue's changes can reach it.
 pub struct CombinedBook {
+    ladders: Vec<VenueLadder>,
     snapshot: CombinedBookSnapshot,
     max_orders: usize,
+    tick_size: Option<f64>,
+    lot_size: Option<f64>,
 }
 
 impl CombinedBook {
     pub fn new(max_orders: usize) -> Self {
         Self {
+            ladders: Vec::new(),
             snapshot: CombinedBookSnapshot {
                 spread: 0.0,
                 asks: Vec::new(),
                 bids: Vec::new(),
             },
             max_orders,
+            tick_size: None,
+            lot_size: None,
         }
     }
 
-    pub fn update(&mut self, order_book: Orderbook) {
-        let incoming_exchange = &order_book.bids.first().map(|o| o.exchange.clone());
-        if let Some(exchange) = incoming_exchange {
-            self.snapshot
-                .bids
-                .retain(|order| order.exchange != *exchange);
-            self.snapshot
-                .asks
-                .retain(|order| order.exchange != *exchange);
+    /// Rounds and validates incoming levels against the instrument's tick
+    /// and lot sizes.
+    pub fn with_instrument(mut self, instrument: &Instrument) -> Self {
+        self.tick_size = instrument.tick_size;
+        self.lot_size = instrument.lot_size;
+        self
+    }
+
+    /// Replaces the ladder of the venue that sent `order_book`, returning
+    /// the changes to the merged view.
+    pub fn update(&mut self, order_book: Orderbook) -> BookChanges {
+        let incoming_exchange = order_book
+            .bids
+            .first()
+            .or(order_book.asks.first())
+            .map(|o| o.exchange);
+        let Some(exchange) = incoming_exchange else {
+            return BookChanges::default();
+        };
+
+        let index = match self.ladders.iter().position(|l| l.exchange == exchange) {
+            Some(index) => index,
+            None => {
+                self.ladders.push(VenueLadder {
+                    exchange,
+                    bids: Vec::new(),
+                    asks: Vec::new(),
+                });
+                self.ladders.len() - 1
+            }
+        };
+
+        let mut bids = self.normalise(order_book.bids, Side::Bids);
+        let mut asks = self.normalise(order_book.asks, Side::Asks);
+        bids.truncate(self.max_orders);
+        asks.truncate(self.max_orders);
+        let ladder = &mut self.ladders[index];
+        let old_bids = std::mem::replace(&mut ladder.bids, bids);
+        let old_asks = std::mem::replace(&mut ladder.asks, asks);
+
+        let changes = BookChanges {
+            bids: self.refresh_side(Side::Bids, index, &old_bids),
+            asks: self.refresh_side(Side::Asks, index, &old_asks),
+        };
+        self.update_spread();
+        changes
+    }
+
+    /// Moves off-grid prices away from the touch, merging levels that land
+    /// on the same price, then rounds sizes down to whole lots. Levels left
+    /// without a positive price and size are dropped.
+    fn normalise(&self, levels: Vec<ExchangeOrder>, side: Side) -> Vec<ExchangeOrder> {
+        if self.tick_size.is_none() && self.lot_size.is_none() {
+            return levels;
+        }
+        let mut normalised: Vec<ExchangeOrder> = Vec::with_capacity(levels.len());
+        for mut level in levels {
+            if let Some(tick_size) = self.tick_size {
+                level.price = to_increment(level.price, tick_size, matches!(side, Side::Asks));
+            }
+            if !(level.price.is_finite() && level.price > 0.0) {
+                continue;
+            }
+            match normalised.last_mut() {
+                Some(last) if last.price == level.price => last.amount += level.amount,
+                _ => normalised.push(level),
+            }
+        }
+        if let Some(lot_size) = self.lot_size {
+            for level in &mut normalised {
+                level.amount = to_increment(level.amount, lot_size, false);
+            }
         }
+        normalised.retain(|level| level.amount.is_finite() && level.amount > 0.0);
+        normalised
+    }
 
-        fn merge_orders(
-            combined_orders: &mut Vec<ExchangeOrder>,
-            new_orders: &[ExchangeOrder],
-            max_orders: usize,
-            is_better: fn(&ExchangeOrder, &ExchangeOrder) -> bool,
-        ) {
-            let mut result = Vec::with_capacity(max_orders);
-            let mut combined_iter = combined_orders.iter();
-            let mut new_iter = new_orders.iter();
-            let mut combined_next = combined_iter.next();
-            let mut new_next = new_iter.next();
-
-            while result.len() < max_orders {
-                match (combined_next, new_next) {
-                    (Some(c), Some(n)) => {
-                        if is_better(n, c) {
-                            result.push(n.clone());
-                            new_next = new_iter.next();
-                        } else {
-                            result.push(c.clone());
-                            combined_next = combined_iter.next();
-                        }
-                    }
-                    (Some(c), None) => {
-                        result.push(c.clone());
-                        combined_next = combined_iter.next();
-                    }
-                    (None, Some(n)) => {
-                        result.push(n.clone());
-                        new_next = new_iter.next();
-                    }
-                    (None, None) => break,
+    /// Drops every level quoted by `exchange`, e.g. once its feed has ended.
+    pub fn remove_exchange(&mut self, exchange: &Exchange) -> BookChanges {
+        let Some(index) = self.ladders.iter().position(|l| l.exchange == *exchange) else {
+            return BookChanges::default();
+        };
+        self.ladders.remove(index);
+        let changes = BookChanges {
+            bids: self.rebuild_side(Side::Bids),
+            asks: self.rebuild_side(Side::Asks),
+        };
+        self.update_spread();
+        changes
+    }
+
+    /// Updates one merged side after the ladder at `index` replaced `old`,
+    /// unless every changed level ranks below a full merged side.
+    fn refresh_side(
+        &mut self,
+        side: Side,
+        index: usize,
+        old: &[ExchangeOrder],
+    ) -> Vec<ExchangeOrder> {
+        let new = self.ladders[index].side(side);
+        if new == old {
+            return Vec::new();
+        }
+        let merged = self.merged_side(side);
+        if merged.len() == self.max_orders {
+            if let Some(worst) = merged.last() {
+                let outside = |order: &ExchangeOrder| side.is_better(worst, order);
+                if old.iter().all(outside) && new.iter().all(outside) {
+                    return Vec::new();
                 }
             }
+        }
+        self.merge_venue_side(side, index)
+    }
 
-            *combined_orders = result;
+    /// Swaps the venue at `index`'s levels in the merged side for its new
+    /// ladder. The other venues' levels keep their order, so the new ladder
+    /// is merged into them, topped up from below the view if the venue now
+    /// shows fewer levels. Changes come from the venue's own levels and the
+    /// other venues' levels crossing the bottom of the view.
+    fn merge_venue_side(&mut self, side: Side, index: usize) -> Vec<ExchangeOrder> {
+        let exchange = self.ladders[index].exchange;
+        let previous = std::mem::take(match side {
+            Side::Bids => &mut self.snapshot.bids,
+            Side::Asks => &mut self.snapshot.asks,
+        });
+        let (old_view, mut others): (Vec<_>, Vec<_>) = previous
+            .into_iter()
+            .partition(|order| order.exchange == exchange);
+        let kept = others.len();
+        if kept < self.max_orders {
+            // Other venues' levels just below the view, from where their
+            // ladders left off.
+            let heads = self
+                .ladders
+                .iter()
+                .map(|ladder| match ladder.exchange == exchange {
+                    true => ladder.side(side).len(),
+                    false => others
+                        .iter()
+                        .filter(|o| o.exchange == ladder.exchange)
+                        .count(),
+                })
+                .collect();
+            others.extend(k_way_merge_from(
+                &self.ladders,
+                side,
+                heads,
+                self.max_orders - kept,
+            ));
         }
 
-        merge_orders(
-            &mut self.snapshot.bids,
-            &order_book.bids,
-            self.max_orders,
-            |a, b| a.price > b.price || (a.price == b.price && a.amount > b.amount),
-        );
+        let new = self.ladders[index].side(side);
+        let rank = |exchange: Exchange| self.ladders.iter().position(|l| l.exchange == exchange);
+        let mut merged = Vec::with_capacity(self.max_orders);
+        let (mut taken_others, mut taken_new) = (0, 0);
+        while merged.len() < self.max_orders {
+            let take_new = match (others.get(taken_others), new.get(taken_new)) {
+                (Some(other), Some(order)) => {
+                    side.is_better(order, other)
+                        || (!side.is_better(other, order) && Some(index) < rank(other.exchange))
+                }
+                (None, Some(_)) => true,
+                (Some(_), None) => false,
+                (None, None) => break,
+            };
+            if take_new {
+                merged.push(new[taken_new].clone());
+                taken_new += 1;
+            } else {
+                merged.push(others[taken_others].clone());
+                taken_others += 1;
+            }
+        }
 
-        merge_orders(
-            &mut self.snapshot.asks,
-            &order_book.asks,
-            self.max_orders,
-            |a, b| a.price < b.price || (a.price == b.price && a.amount > b.amount),
-        );
+        let (mut updated, mut removed) = ladder_changes(side, &old_view, &new[..taken_new]);
+        if taken_others > kept {
+            updated.extend_from_slice(&others[kept..taken_others]);
+        } else {
+            removed.extend(
+                others[taken_others..kept]
+                    .iter()
+                    .map(|order| ExchangeOrder {
+                        amount: 0.0,
+                        ..order.clone()
+                    }),
+            );
+        }
+        match side {
+            Side::Bids => self.snapshot.bids = merged,
+            Side::Asks => self.snapshot.asks = merged,
+        }
+        updated.extend(removed);
+        updated
+    }
+
+    fn rebuild_side(&mut self, side: Side) -> Vec<ExchangeOrder> {
+        let merged = k_way_merge(&self.ladders, side, self.max_orders);
+        let previous = match side {
+            Side::Bids => std::mem::replace(&mut self.snapshot.bids, merged),
+            Side::Asks => std::mem::replace(&mut self.snapshot.asks, merged),
+        };
+        level_changes(&previous, self.merged_side(side))
+    }
 
+    fn merged_side(&self, side: Side) -> &[ExchangeOrder] {
+        match side {
+            Side::Bids => &self.snapshot.bids,
+            Side::Asks => &self.snapshot.asks,
+        }
+    }
+
+    fn update_spread(&mut self) {
         if let (Some(best_bid), Some(best_ask)) =
             (self.snapshot.bids.first(), self.snapshot.asks.first())
         {
@@ -99,6 +328,122 @@ impl CombinedBook {
     pub fn get_snapshot(&self) -> CombinedBookSnapshot {
         self.snapshot.clone()
     }
+
+    /// Each venue's best bid and ask, best first, including venues whose
+    /// levels rank outside the merged top-N view.
+    pub fn venue_tops(&self) -> CombinedBookSnapshot {
+        let tops = |side: Side| {
+            let mut tops: Vec<ExchangeOrder> = self
+                .ladders
+                .iter()
+                .filter_map(|ladder| ladder.side(side).first().cloned())
+                .collect();
+            // Stable, so ties keep venue order as in the merged view.
+            tops.sort_by(|a, b| match (side.is_better(a, b), side.is_better(b, a)) {
+                (true, _) => std::cmp::Ordering::Less,
+                (_, true) => std::cmp::Ordering::Greater,
+                _ => std::cmp::Ordering::Equal,
+            });
+            tops
+        };
+        let (bids, asks) = (tops(Side::Bids), tops(Side::Asks));
+        let spread = match (bids.first(), asks.first()) {
+            (Some(best_bid), Some(best_ask)) => best_ask.price - best_bid.price,
+            _ => 0.0,
+        };
+        CombinedBookSnapshot { spread, asks, bids }
+    }
+}
+
+/// Merges the best `max_orders` levels of one side across all ladders. The
+/// heads are scanned linearly, which beats a heap for a handful of venues.
+/// Ties go to the venue that was added first.
+fn k_way_merge(ladders: &[VenueLadder], side: Side, max_orders: usize) -> Vec<ExchangeOrder> {
+    k_way_merge_from(ladders, side, vec![0; ladders.len()], max_orders)
+}
+
+/// As [`k_way_merge`], starting each ladder at its entry in `heads`.
+fn k_way_merge_from(
+    ladders: &[VenueLadder],
+    side: Side,
+    mut heads: Vec<usize>,
+    max_orders: usize,
+) -> Vec<ExchangeOrder> {
+    let mut merged = Vec::with_capacity(max_orders);
+    while merged.len() < max_orders {
+        let mut best: Option<(usize, &ExchangeOrder)> = None;
+        for (venue, ladder) in ladders.iter().enumerate() {
+            let Some(order) = ladder.side(side).get(heads[venue]) else {
+                continue;
+            };
+            if best.is_none_or(|(_, best)| side.is_better(order, best)) {
+                best = Some((venue, order));
+            }
+        }
+        let Some((venue, order)) = best else { break };
+        merged.push(order.clone());
+        heads[venue] += 1;
+    }
+    merged
+}
+
+/// Levels one venue added or resized, and those it removed with an amount
+/// of zero, between two of its ladders.
+fn ladder_changes(
+    side: Side,
+    old: &[ExchangeOrder],
+    new: &[ExchangeOrder],
+) -> (Vec<ExchangeOrder>, Vec<ExchangeOrder>) {
+    let (mut updated, mut removed) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    loop {
+        match (old.get(i), new.get(j)) {
+            (Some(before), Some(after)) if before.price == after.price => {
+                if before.amount != after.amount {
+                    updated.push(after.clone());
+                }
+                i += 1;
+                j += 1;
+            }
+            (Some(before), Some(after)) if !side.is_better_price(before.price, after.price) => {
+                updated.push(after.clone());
+                j += 1;
+            }
+            (Some(before), _) => {
+                removed.push(ExchangeOrder {
+                    amount: 0.0,
+                    ..before.clone()
+                });
+                i += 1;
+            }
+            (None, Some(after)) => {
+                updated.push(after.clone());
+                j += 1;
+            }
+            (None, None) => break,
+        }
+    }
+    (updated, removed)
+}
+
+/// Levels keyed by exchange and price that differ between two merged sides.
+fn level_changes(previous: &[ExchangeOrder], current: &[ExchangeOrder]) -> Vec<ExchangeOrder> {
+    let key = |order: &ExchangeOrder| (order.exchange, order.price.to_bits());
+    let previous_amounts: HashMap<_, _> = previous.iter().map(|o| (key(o), o.amount)).collect();
+    let current_keys: HashSet<_> = current.iter().map(key).collect();
+
+    let updated = current
+        .iter()
+        .filter(|order| previous_amounts.get(&key(order)) != Some(&order.amount))
+        .cloned();
+    let removed = previous
+        .iter()
+        .filter(|order| !current_keys.contains(&key(order)))
+        .map(|order| ExchangeOrder {
+            amount: 0.0,
+            ..order.clone()
+        });
+    updated.chain(removed).collect()
 }
 
 #[cfg(test)]
@@ -107,55 +452,81 @@ mod tests {
 
     use super::*;
 
+    /// Builds a mixed book by sending each venue's levels as its own update.
+    fn seed(combined_book: &mut CombinedBook, bids: Vec<ExchangeOrder>, asks: Vec<ExchangeOrder>) {
+        for exchange in [Exchange::BINANCE, Exchange::BITSTAMP] {
+            let of_venue = |orders: &[ExchangeOrder]| -> Vec<ExchangeOrder> {
+                orders
+                    .iter()
+                    .filter(|o| o.exchange == exchange)
+                    .cloned()
+                    .collect()
+            };
+            combined_book.update(Orderbook {
+                bids: of_venue(&bids),
+                asks: of_venue(&asks),
+                ..Default::default()
+            });
+        }
+    }
+
     #[test]
     fn test_update_exceeding_max_orders() {
         let mut combined_book = CombinedBook::new(3);
         let order_book = Orderbook {
-            exchange_ts: 1234567890,
             bids: vec![
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 100.0,
                     amount: 1.0,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 99.0,
                     amount: 2.0,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 98.0,
                     amount: 1.5,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 97.0,
                     amount: 0.5,
+                    original_price: None,
                 },
             ],
             asks: vec![
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 101.0,
                     amount: 1.0,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 102.0,
                     amount: 2.0,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 103.0,
                     amount: 1.5,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 104.0,
                     amount: 0.5,
+                    original_price: None,
                 },
             ],
+            ..Default::default()
         };
 
         combined_book.update(order_book);
@@ -171,67 +542,80 @@ mod tests {
     #[test]
     fn test_update_replacement_of_stale_orders() {
         let mut combined_book = CombinedBook::new(3);
-        combined_book.snapshot.bids = vec![
-            ExchangeOrder {
-                exchange: Exchange::Bitstamp,
-                price: 100.0,
-                amount: 1.0,
-            },
-            ExchangeOrder {
-                exchange: Exchange::Bitstamp,
-                price: 99.0,
-                amount: 2.0,
-            },
-            ExchangeOrder {
-                exchange: Exchange::Bitstamp,
-                price: 98.0,
-                amount: 1.5,
-            },
-        ];
-        combined_book.snapshot.asks = vec![
-            ExchangeOrder {
-                exchange: Exchange::Bitstamp,
-                price: 101.0,
-                amount: 1.0,
-            },
-            ExchangeOrder {
-                exchange: Exchange::Bitstamp,
-                price: 102.0,
-                amount: 2.0,
-            },
-            ExchangeOrder {
-                exchange: Exchange::Bitstamp,
-                price: 103.0,
-                amount: 1.5,
-            },
-        ];
+        seed(
+            &mut combined_book,
+            vec![
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 100.0,
+                    amount: 1.0,
+                    original_price: None,
+                },
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 99.0,
+                    amount: 2.0,
+                    original_price: None,
+                },
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 98.0,
+                    amount: 1.5,
+                    original_price: None,
+                },
+            ],
+            vec![
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 101.0,
+                    amount: 1.0,
+                    original_price: None,
+                },
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 102.0,
+                    amount: 2.0,
+                    original_price: None,
+                },
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 103.0,
+                    amount: 1.5,
+                    original_price: None,
+                },
+            ],
+        );
 
         let order_book = Orderbook {
-            exchange_ts: 1234567890,
             bids: vec![
                 ExchangeOrder {
-                    exchange: Exchange::Bitstamp,
+                    exchange: Exchange::BITSTAMP,
                     price: 101.0,
                     amount: 1.5,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Bitstamp,
+                    exchange: Exchange::BITSTAMP,
                     price: 99.5,
                     amount: 2.5,
+                    original_price: None,
                 },
             ],
             asks: vec![
                 ExchangeOrder {
-                    exchange: Exchange::Bitstamp,
+                    exchange: Exchange::BITSTAMP,
                     price: 100.5,
                     amount: 1.5,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Bitstamp,
+                    exchange: Exchange::BITSTAMP,
                     price: 103.0,
                     amount: 1.0,
+                    original_price: None,
                 },
             ],
+            ..Default::default()
         };
 
         combined_book.update(order_book);
@@ -247,41 +631,47 @@ mod tests {
     fn test_update_empty_combined_book() {
         let mut combined_book = CombinedBook::new(10);
         let order_book = Orderbook {
-            exchange_ts: 1234567890,
             bids: vec![
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 100.0,
                     amount: 1.0,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 99.0,
                     amount: 2.0,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 98.0,
                     amount: 1.5,
+                    original_price: None,
                 },
             ],
             asks: vec![
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 101.0,
                     amount: 1.0,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 102.0,
                     amount: 2.0,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 103.0,
                     amount: 1.5,
+                    original_price: None,
                 },
             ],
+            ..Default::default()
         };
 
         combined_book.update(order_book);
@@ -296,57 +686,68 @@ mod tests {
     #[test]
     fn test_update_existing_combined_book() {
         let mut combined_book = CombinedBook::new(10);
-        combined_book.snapshot.bids = vec![
-            ExchangeOrder {
-                exchange: Exchange::Bitstamp,
-                price: 100.0,
-                amount: 1.0,
-            },
-            ExchangeOrder {
-                exchange: Exchange::Bitstamp,
-                price: 99.0,
-                amount: 2.0,
-            },
-        ];
-        combined_book.snapshot.asks = vec![
-            ExchangeOrder {
-                exchange: Exchange::Bitstamp,
-                price: 101.0,
-                amount: 1.0,
-            },
-            ExchangeOrder {
-                exchange: Exchange::Bitstamp,
-                price: 102.0,
-                amount: 2.0,
-            },
-        ];
+        seed(
+            &mut combined_book,
+            vec![
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 100.0,
+                    amount: 1.0,
+                    original_price: None,
+                },
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 99.0,
+                    amount: 2.0,
+                    original_price: None,
+                },
+            ],
+            vec![
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 101.0,
+                    amount: 1.0,
+                    original_price: None,
+                },
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 102.0,
+                    amount: 2.0,
+                    original_price: None,
+                },
+            ],
+        );
 
         let order_book = Orderbook {
-            exchange_ts: 1234567890,
             bids: vec![
                 ExchangeOrder {
-                    exchange: Exchange::Bitstamp,
+                    exchange: Exchange::BITSTAMP,
                     price: 101.0,
                     amount: 1.5,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Bitstamp,
+                    exchange: Exchange::BITSTAMP,
                     price: 99.5,
                     amount: 2.5,
+                    original_price: None,
                 },
             ],
             asks: vec![
                 ExchangeOrder {
-                    exchange: Exchange::Bitstamp,
+                    exchange: Exchange::BITSTAMP,
                     price: 100.5,
                     amount: 1.5,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Bitstamp,
+                    exchange: Exchange::BITSTAMP,
                     price: 103.0,
                     amount: 1.0,
+                    original_price: None,
                 },
             ],
+            ..Default::default()
         };
 
         combined_book.update(order_book);
@@ -361,67 +762,80 @@ mod tests {
     #[test]
     fn test_update_existing_mixed_book() {
         let mut combined_book = CombinedBook::new(10);
-        combined_book.snapshot.bids = vec![
-            ExchangeOrder {
-                exchange: Exchange::Binance,
-                price: 100.0,
-                amount: 1.0,
-            },
-            ExchangeOrder {
-                exchange: Exchange::Bitstamp,
-                price: 100.0,
-                amount: 0.5,
-            },
-            ExchangeOrder {
-                exchange: Exchange::Binance,
-                price: 99.0,
-                amount: 2.0,
-            },
-        ];
-        combined_book.snapshot.asks = vec![
-            ExchangeOrder {
-                exchange: Exchange::Bitstamp,
-                price: 101.0,
-                amount: 1.0,
-            },
-            ExchangeOrder {
-                exchange: Exchange::Bitstamp,
-                price: 102.0,
-                amount: 4.0,
-            },
-            ExchangeOrder {
-                exchange: Exchange::Binance,
-                price: 102.0,
-                amount: 2.0,
-            },
-        ];
+        seed(
+            &mut combined_book,
+            vec![
+                ExchangeOrder {
+                    exchange: Exchange::BINANCE,
+                    price: 100.0,
+                    amount: 1.0,
+                    original_price: None,
+                },
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 100.0,
+                    amount: 0.5,
+                    original_price: None,
+                },
+                ExchangeOrder {
+                    exchange: Exchange::BINANCE,
+                    price: 99.0,
+                    amount: 2.0,
+                    original_price: None,
+                },
+            ],
+            vec![
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 101.0,
+                    amount: 1.0,
+                    original_price: None,
+                },
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 102.0,
+                    amount: 4.0,
+                    original_price: None,
+                },
+                ExchangeOrder {
+                    exchange: Exchange::BINANCE,
+                    price: 102.0,
+                    amount: 2.0,
+                    original_price: None,
+                },
+            ],
+        );
 
         let order_book = Orderbook {
-            exchange_ts: 1234567890,
             bids: vec![
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 100.0,
                     amount: 0.3,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 99.5,
                     amount: 2.5,
+                    original_price: None,
                 },
             ],
             asks: vec![
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 100.5,
                     amount: 1.5,
+                    original_price: None,
                 },
                 ExchangeOrder {
-                    exchange: Exchange::Binance,
+                    exchange: Exchange::BINANCE,
                     price: 102.0,
                     amount: 5.0,
+                    original_price: None,
                 },
             ],
+            ..Default::default()
         };
 
         combined_book.update(order_book);
@@ -431,36 +845,246 @@ mod tests {
 
         assert_eq!(combined_book.snapshot.bids[0].price, 100.0);
         assert_eq!(combined_book.snapshot.bids[0].amount, 0.5);
-        assert_eq!(combined_book.snapshot.bids[0].exchange, Exchange::Bitstamp);
+        assert_eq!(combined_book.snapshot.bids[0].exchange, Exchange::BITSTAMP);
 
         assert_eq!(combined_book.snapshot.asks[0].price, 100.5);
         assert_eq!(combined_book.snapshot.asks[0].amount, 1.5);
-        assert_eq!(combined_book.snapshot.asks[0].exchange, Exchange::Binance);
+        assert_eq!(combined_book.snapshot.asks[0].exchange, Exchange::BINANCE);
 
         assert_eq!(combined_book.snapshot.bids[1].price, 100.0);
         assert_eq!(combined_book.snapshot.bids[1].amount, 0.3);
-        assert_eq!(combined_book.snapshot.bids[1].exchange, Exchange::Binance);
+        assert_eq!(combined_book.snapshot.bids[1].exchange, Exchange::BINANCE);
 
-        assert_eq!(combined_book.snapshot.asks[2].price, 101.0);
-        assert_eq!(combined_book.snapshot.asks[2].amount, 1.0);
-        assert_eq!(combined_book.snapshot.asks[2].exchange, Exchange::Bitstamp);
+        assert_eq!(combined_book.snapshot.asks[1].price, 101.0);
+        assert_eq!(combined_book.snapshot.asks[1].amount, 1.0);
+        assert_eq!(combined_book.snapshot.asks[1].exchange, Exchange::BITSTAMP);
 
         assert_eq!(combined_book.snapshot.bids[2].price, 99.5);
         assert_eq!(combined_book.snapshot.bids[2].amount, 2.5);
-        assert_eq!(combined_book.snapshot.bids[2].exchange, Exchange::Binance);
+        assert_eq!(combined_book.snapshot.bids[2].exchange, Exchange::BINANCE);
 
         assert_eq!(combined_book.snapshot.asks[2].price, 102.0);
         assert_eq!(combined_book.snapshot.asks[2].amount, 5.0);
-        assert_eq!(combined_book.snapshot.asks[2].exchange, Exchange::Binance);
+        assert_eq!(combined_book.snapshot.asks[2].exchange, Exchange::BINANCE);
 
         assert_eq!(combined_book.snapshot.asks[3].price, 102.0);
         assert_eq!(combined_book.snapshot.asks[3].amount, 4.0);
-        assert_eq!(combined_book.snapshot.asks[3].exchange, Exchange::Bitstamp);
-
-        assert_eq!(combined_book.snapshot.asks[2].price, 102.0);
-        assert_eq!(combined_book.snapshot.asks[2].amount, 4.0);
-        assert_eq!(combined_book.snapshot.asks[2].exchange, Exchange::Bitstamp);
+        assert_eq!(combined_book.snapshot.asks[3].exchange, Exchange::BITSTAMP);
 
         assert_eq!(combined_book.snapshot.spread, 0.5);
     }
+
+    #[test]
+    fn test_update_returns_changes_to_merged_view() {
+        let book = |exchange: Exchange, bids: &[(f64, f64)]| Orderbook {
+            bids: bids
+                .iter()
+                .map(|&(price, amount)| ExchangeOrder::new(exchange, price, amount))
+                .collect(),
+            asks: vec![ExchangeOrder::new(exchange, 200.0, 1.0)],
+            ..Default::default()
+        };
+        let mut combined_book = CombinedBook::new(2);
+        combined_book.update(book(Exchange::BINANCE, &[(100.0, 1.0), (99.0, 1.0)]));
+
+        // Bitstamp's best bid displaces Binance's second level.
+        let changes = combined_book.update(book(Exchange::BITSTAMP, &[(99.5, 2.0), (98.0, 1.0)]));
+        assert_eq!(
+            changes.bids,
+            vec![
+                ExchangeOrder::new(Exchange::BITSTAMP, 99.5, 2.0),
+                ExchangeOrder::new(Exchange::BINANCE, 99.0, 0.0),
+            ]
+        );
+        assert_eq!(
+            changes.asks,
+            vec![ExchangeOrder::new(Exchange::BITSTAMP, 200.0, 1.0)]
+        );
+
+        // Changes below a full merged side leave the view untouched.
+        let changes = combined_book.update(book(Exchange::BITSTAMP, &[(99.5, 2.0), (97.0, 3.0)]));
+        assert!(changes.is_empty());
+
+        let changes = combined_book.update(book(Exchange::BITSTAMP, &[(99.5, 2.5), (97.0, 3.0)]));
+        assert_eq!(
+            changes.bids,
+            vec![ExchangeOrder::new(Exchange::BITSTAMP, 99.5, 2.5)]
+        );
+        assert!(changes.asks.is_empty());
+    }
+
+    #[test]
+    fn test_incremental_merge_matches_full_merge() {
+        let exchanges = [
+            Exchange::BINANCE,
+            Exchange::BITSTAMP,
+            Exchange::new("TestVenue"),
+        ];
+        let mut combined_book = CombinedBook::new(5);
+        let mut view: Vec<ExchangeOrder> = Vec::new();
+        let mut seed = 7u64;
+        let mut next = |modulo: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % modulo
+        };
+        for _ in 0..500 {
+            let exchange = exchanges[next(3) as usize];
+            let mut price = 100.0;
+            let bids = (0..next(5))
+                .map(|_| {
+                    price -= 1.0 + next(2) as f64;
+                    ExchangeOrder {
+                        exchange,
+                        price,
+                        amount: 1.0 + next(3) as f64,
+                        original_price: None,
+                    }
+                })
+                .collect();
+            let changes = combined_book.update(Orderbook {
+                bids,
+                asks: vec![ExchangeOrder {
+                    exchange,
+                    price: 101.0,
+                    amount: 1.0,
+                    original_price: None,
+                }],
+                ..Default::default()
+            });
+
+            let expected = k_way_merge(&combined_book.ladders, Side::Bids, 5);
+            assert_eq!(combined_book.snapshot.bids, expected);
+            // Applying the changes to the previous view gives the new one.
+            for change in changes.bids {
+                view.retain(|o| (o.exchange, o.price) != (change.exchange, change.price));
+                if change.amount > 0.0 {
+                    view.push(change);
+                }
+            }
+            let mut sorted = expected.clone();
+            let key = |o: &ExchangeOrder| (o.exchange, o.price.to_bits());
+            sorted.sort_by_key(key);
+            view.sort_by_key(key);
+            assert_eq!(view, sorted);
+        }
+    }
+
+    #[test]
+    fn test_venue_tops_include_venues_outside_the_view() {
+        let mut combined_book = CombinedBook::new(1);
+        seed(
+            &mut combined_book,
+            vec![
+                ExchangeOrder {
+                    exchange: Exchange::BINANCE,
+                    price: 100.0,
+                    amount: 1.0,
+                    original_price: None,
+                },
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: 99.0,
+                    amount: 2.0,
+                    original_price: None,
+                },
+            ],
+            vec![ExchangeOrder {
+                exchange: Exchange::BITSTAMP,
+                price: 101.0,
+                amount: 1.0,
+                original_price: None,
+            }],
+        );
+        assert_eq!(combined_book.snapshot.bids.len(), 1);
+
+        let tops = combined_book.venue_tops();
+        let bids: Vec<_> = tops.bids.iter().map(|o| (o.exchange, o.price)).collect();
+        assert_eq!(
+            bids,
+            vec![(Exchange::BINANCE, 100.0), (Exchange::BITSTAMP, 99.0)]
+        );
+        assert_eq!(tops.asks.len(), 1);
+        assert_eq!(tops.spread, 1.0);
+    }
+
+    #[test]
+    fn test_remove_exchange() {
+        let mut combined_book = CombinedBook::new(10);
+        seed(
+            &mut combined_book,
+            vec![ExchangeOrder {
+                exchange: Exchange::BITSTAMP,
+                price: 99.0,
+                amount: 1.0,
+                original_price: None,
+            }],
+            vec![ExchangeOrder {
+                exchange: Exchange::BINANCE,
+                price: 101.0,
+                amount: 1.0,
+                original_price: None,
+            }],
+        );
+        assert_eq!(combined_book.snapshot.spread, 2.0);
+
+        let changes = combined_book.remove_exchange(&Exchange::BINANCE);
+
+        assert_eq!(changes.asks[0].amount, 0.0);
+        assert!(changes.bids.is_empty());
+        assert!(combined_book.snapshot.asks.is_empty());
+        assert_eq!(combined_book.snapshot.bids.len(), 1);
+        assert_eq!(combined_book.snapshot.spread, 0.0);
+    }
+
+    #[test]
+    fn test_update_rounds_levels_to_instrument_grid() {
+        let instrument = Instrument::new("ETH", "USDT")
+            .with_tick_size(0.5)
+            .with_lot_size(0.1);
+        let mut combined_book = CombinedBook::new(10).with_instrument(&instrument);
+        let order = |price, amount| ExchangeOrder::new(Exchange::BINANCE, price, amount);
+
+        combined_book.update(Orderbook {
+            bids: vec![order(100.3, 0.25), order(100.1, 0.3), order(99.0, 0.05)],
+            asks: vec![order(101.0, 1.0), order(101.2, 0.5), order(f64::NAN, 1.0)],
+            ..Default::default()
+        });
+
+        let snapshot = combined_book.get_snapshot();
+        assert_eq!(snapshot.bids, vec![order(100.0, 0.5)]);
+        assert_eq!(snapshot.asks, vec![order(101.0, 1.0), order(101.5, 0.5)]);
+        assert_eq!(snapshot.spread, 1.0);
+    }
+
+    #[test]
+    fn test_snapshot_filtered_by_venue_and_depth() {
+        let order = |exchange, price| ExchangeOrder::new(exchange, price, 1.0);
+        let snapshot = CombinedBookSnapshot {
+            spread: 0.5,
+            bids: vec![
+                order(Exchange::BINANCE, 100.0),
+                order(Exchange::BITSTAMP, 99.5),
+                order(Exchange::BITSTAMP, 99.0),
+            ],
+            asks: vec![
+                order(Exchange::BINANCE, 100.5),
+                order(Exchange::BITSTAMP, 101.0),
+                order(Exchange::BITSTAMP, 101.5),
+            ],
+        };
+
+        let filtered = snapshot.filtered(Some(&[Exchange::BITSTAMP]), 1);
+
+        assert_eq!(filtered.bids.len(), 1);
+        assert_eq!(filtered.asks.len(), 1);
+        assert_eq!(filtered.bids[0].price, 99.5);
+        assert_eq!(filtered.asks[0].price, 101.0);
+        assert_eq!(filtered.spread, 1.5);
+
+        let unfiltered = snapshot.filtered(None, usize::MAX);
+        assert_eq!(unfiltered.bids.len(), 3);
+        assert_eq!(unfiltered.spread, 0.5);
+    }
 }
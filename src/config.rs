@@ -1,16 +1,507 @@
+use crate::exchange::registry::registered_exchanges;
+use crate::instrument::{load_instruments, Instrument};
+use crate::subscription::Entitlements;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
 use std::path::Path;
+use thiserror::Error;
+
+const DEFAULT_GRPC_ADDR: &str = "127.0.0.1:50051";
+
+/// Settings that parse but could not work once the server runs.
+#[derive(Error, Debug, PartialEq)]
+pub enum ConfigError {
+    #[error("Synthetic '{0}' has the name of another venue")]
+    SyntheticNameTaken(String),
+    #[error("Synthetic '{name}' cannot build its legs from synthetic '{exchange}'")]
+    SyntheticLegs { name: String, exchange: String },
+    #[error("Instrument {field} must be positive and finite, got {value}")]
+    InvalidIncrement { field: &'static str, value: f64 },
+    #[error("candles.intervals_ms must be non-zero and distinct, got {0}")]
+    CandleInterval(u64),
+    #[error("{section}.{name} is not a registered venue")]
+    UnknownVenue { section: &'static str, name: String },
+}
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
     pub exchanges: Vec<String>,
     pub trading_pair: String,
     pub max_orders: usize,
+    /// Metadata for `trading_pair`; without it every venue subscribes to
+    /// `trading_pair` verbatim.
+    #[serde(default)]
+    pub instrument: Option<Instrument>,
+    /// A JSON file of instruments keyed by trading pair, read by
+    /// [`load_config`] when `instrument` is not given inline.
+    #[serde(default)]
+    pub instruments_path: Option<String>,
+    #[serde(default)]
+    pub grpc: GrpcConfig,
+    #[serde(default)]
+    pub subscriptions: SubscriptionConfig,
+    #[serde(default)]
+    pub websocket: Option<WebSocketConfig>,
+    #[serde(default)]
+    pub http: Option<HttpConfig>,
+    /// Per-exchange overrides of the venue's heartbeat defaults.
+    #[serde(default)]
+    pub heartbeats: HashMap<String, HeartbeatConfig>,
+    /// Per-exchange quote currency and trading settings.
+    #[serde(default)]
+    pub venues: HashMap<String, VenueConfig>,
+    #[serde(default)]
+    pub trades: Option<TradesConfig>,
+    #[serde(default)]
+    pub candles: Option<CandlesConfig>,
+    #[serde(default)]
+    pub analytics: AnalyticsConfig,
+    #[serde(default)]
+    pub liquidity: LiquidityConfig,
+    #[serde(default)]
+    pub paper_trading: Option<PaperTradingConfig>,
+    /// Implied books merged into the combined book as extra venues.
+    #[serde(default)]
+    pub synthetics: Vec<SyntheticConfig>,
+}
+
+impl Config {
+    /// Checks what the registry and the other settings can't catch when
+    /// the venues are built.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let registered = registered_exchanges();
+        for (index, synthetic) in self.synthetics.iter().enumerate() {
+            // Registering over a venue would replace its factory, leaving
+            // legs on that venue building themselves.
+            if registered.iter().any(|e| e.name() == synthetic.name)
+                || self.synthetics[..index]
+                    .iter()
+                    .any(|other| other.name == synthetic.name)
+            {
+                return Err(ConfigError::SyntheticNameTaken(synthetic.name.clone()));
+            }
+            if self
+                .synthetics
+                .iter()
+                .any(|other| other.name == synthetic.exchange)
+            {
+                return Err(ConfigError::SyntheticLegs {
+                    name: synthetic.name.clone(),
+                    exchange: synthetic.exchange.clone(),
+                });
+            }
+        }
+        if let Some(instrument) = &self.instrument {
+            for (field, increment) in [
+                ("tick_size", instrument.tick_size),
+                ("lot_size", instrument.lot_size),
+            ] {
+                if let Some(value) = increment.filter(|value| !(value.is_finite() && *value > 0.0))
+                {
+                    return Err(ConfigError::InvalidIncrement { field, value });
+                }
+            }
+        }
+        // Settings keyed by a misspelt venue would otherwise never apply.
+        let is_venue = |name: &str| {
+            registered.iter().any(|e| e.name() == name)
+                || self.synthetics.iter().any(|s| s.name == name)
+        };
+        for (section, mut names) in [
+            ("venues", self.venues.keys().collect::<Vec<_>>()),
+            ("heartbeats", self.heartbeats.keys().collect()),
+        ] {
+            names.sort();
+            if let Some(name) = names.into_iter().find(|name| !is_venue(name)) {
+                return Err(ConfigError::UnknownVenue {
+                    section,
+                    name: name.clone(),
+                });
+            }
+        }
+        if let Some(candles) = &self.candles {
+            // A zero interval closes a bar on every price, and a repeated
+            // one counts each price twice in the same series.
+            for (index, &interval_ms) in candles.intervals_ms.iter().enumerate() {
+                if interval_ms == 0 || candles.intervals_ms[..index].contains(&interval_ms) {
+                    return Err(ConfigError::CandleInterval(interval_ms));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Trade feeds from the configured exchanges; disabled if absent.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct TradesConfig {
+    /// Use the venues' aggregated trades where they have them.
+    #[serde(default)]
+    pub aggregated: bool,
+}
+
+/// Simulated orders against the combined book over gRPC; disabled if
+/// absent. Fees come from `venues`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PaperTradingConfig {
+    /// Delay before a submitted order reaches the book.
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// Share of the displayed size at a resting order's price assumed to be
+    /// ahead of it; 1 joins the back of the queue.
+    #[serde(default = "default_queue_share")]
+    pub queue_share: f64,
+}
+
+fn default_queue_share() -> f64 {
+    1.0
+}
+
+/// A cross rate implied from two pairs on one exchange that share a quote
+/// currency, e.g. `ethbtc` from `ethusdt` (base leg) and `btcusdt` (quote
+/// leg). It joins the combined book as the venue `name`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SyntheticConfig {
+    pub name: String,
+    pub exchange: String,
+    pub base_leg: String,
+    pub quote_leg: String,
+}
+
+/// Unknown keys, such as a `symbol` now set in `instrument.symbols`, fail
+/// to load.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct VenueConfig {
+    /// Converts a pair quoted in another currency, e.g. USD on a USDT
+    /// book, before it is merged.
+    #[serde(default)]
+    pub quote_conversion: Option<QuoteConversionConfig>,
+    #[serde(default)]
+    pub fees: FeeSchedule,
+    /// Smallest order the venue accepts, in base currency.
+    #[serde(default)]
+    pub min_order_size: f64,
+}
+
+/// Trading fees in basis points of notional.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct FeeSchedule {
+    #[serde(default)]
+    pub maker_bps: f64,
+    #[serde(default)]
+    pub taker_bps: f64,
+}
+
+/// A reference-rate book whose mid converts the venue's quote currency
+/// into the common one. `invert` is for pairs quoted the other way round,
+/// e.g. `usdtusd` to convert USD into USDT.
+#[derive(Deserialize, Debug, Clone)]
+pub struct QuoteConversionConfig {
+    pub exchange: String,
+    pub trading_pair: String,
+    #[serde(default)]
+    pub invert: bool,
+}
+
+/// Per-venue feed settings. Unset fields keep the venue's defaults.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct HeartbeatConfig {
+    /// Reconnect when nothing has been read for this long.
+    #[serde(default)]
+    pub idle_timeout_ms: Option<u64>,
+    /// How often to send the venue's heartbeat; zero disables it.
+    #[serde(default)]
+    pub interval_ms: Option<u64>,
+    /// Count books whose sequence id skips further ahead than this as gaps.
+    #[serde(default)]
+    pub max_sequence_gap: Option<u64>,
+}
+
+/// OHLCV bars from book mids, and from trades if `trades` is enabled;
+/// disabled if absent.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CandlesConfig {
+    #[serde(default = "default_candle_intervals_ms")]
+    pub intervals_ms: Vec<u64>,
+    /// Closed bars kept per series for queries.
+    #[serde(default = "default_candle_history")]
+    pub history: usize,
+}
+
+fn default_candle_intervals_ms() -> Vec<u64> {
+    vec![1_000, 60_000, 300_000]
+}
+
+fn default_candle_history() -> usize {
+    crate::candles::DEFAULT_HISTORY
+}
+
+/// Which figures to compute for each book.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnalyticsConfig {
+    /// Levels per side to measure imbalance over.
+    #[serde(default = "default_imbalance_depths")]
+    pub imbalance_depths: Vec<usize>,
+    /// Distances from mid, in basis points, to sum resting size within.
+    #[serde(default = "default_depth_bands_bps")]
+    pub depth_bands_bps: Vec<f64>,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            imbalance_depths: default_imbalance_depths(),
+            depth_bands_bps: default_depth_bands_bps(),
+        }
+    }
+}
+
+fn default_imbalance_depths() -> Vec<usize> {
+    vec![1, 5, 10]
+}
+
+fn default_depth_bands_bps() -> Vec<f64> {
+    vec![5.0, 10.0, 25.0]
+}
+
+/// Rolling windows for per-venue spread and liquidity statistics.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LiquidityConfig {
+    #[serde(default = "default_liquidity_windows_ms")]
+    pub windows_ms: Vec<u64>,
+}
+
+impl Default for LiquidityConfig {
+    fn default() -> Self {
+        Self {
+            windows_ms: default_liquidity_windows_ms(),
+        }
+    }
+}
+
+fn default_liquidity_windows_ms() -> Vec<u64> {
+    crate::liquidity_stats::DEFAULT_WINDOWS
+        .iter()
+        .map(|window| window.as_millis() as u64)
+        .collect()
+}
+
+/// REST API for ad-hoc book queries; disabled if absent.
+#[derive(Deserialize, Debug)]
+pub struct HttpConfig {
+    pub addr: String,
+}
+
+/// JSON-over-WebSocket publisher for browser clients; disabled if absent.
+#[derive(Deserialize, Debug)]
+pub struct WebSocketConfig {
+    pub addr: String,
+    /// Serves `wss://`; the same settings as `grpc.tls`.
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+}
+
+/// Server-side limits applied to every subscriber.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SubscriptionConfig {
+    #[serde(default)]
+    pub max_subscribers: Option<usize>,
+    /// Floor for the update interval subscribers may request.
+    #[serde(default)]
+    pub min_interval_ms: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GrpcConfig {
+    #[serde(default = "default_grpc_addr")]
+    pub addr: String,
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub auth: Option<AuthConfig>,
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            addr: default_grpc_addr(),
+            tls: None,
+            auth: None,
+        }
+    }
+}
+
+fn default_grpc_addr() -> String {
+    DEFAULT_GRPC_ADDR.to_string()
+}
+
+/// Server certificate and key, plus an optional CA bundle. Setting
+/// `client_ca_path` turns on mutual TLS: clients must present a certificate
+/// signed by that CA.
+#[derive(Deserialize, Debug)]
+pub struct TlsConfig {
+    pub cert_path: String,
+    pub key_path: String,
+    #[serde(default)]
+    pub client_ca_path: Option<String>,
+    /// Client certificate subjects allowed to subscribe. Empty allows any
+    /// client the CA accepts.
+    #[serde(default)]
+    pub allowed_client_subjects: Vec<String>,
+}
+
+/// Bearer token authentication. Tokens are looked up in `tokens` first, then
+/// verified as HS256 JWTs if `jwt_hmac_secret` is set.
+#[derive(Deserialize)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub tokens: Vec<StaticToken>,
+    #[serde(default)]
+    pub jwt_hmac_secret: Option<String>,
+    #[serde(default)]
+    pub jwt_issuer: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct StaticToken {
+    pub client: String,
+    pub token: String,
+    #[serde(flatten)]
+    pub entitlements: Entitlements,
+}
+
+// Secrets are kept out of the "Configuration loaded" debug log.
+impl fmt::Debug for AuthConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthConfig")
+            .field("tokens", &self.tokens)
+            .field(
+                "jwt_hmac_secret",
+                &self.jwt_hmac_secret.as_ref().map(|_| "***"),
+            )
+            .field("jwt_issuer", &self.jwt_issuer)
+            .finish()
+    }
+}
+
+impl fmt::Debug for StaticToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaticToken")
+            .field("client", &self.client)
+            .field("token", &"***")
+            .field("entitlements", &self.entitlements)
+            .finish()
+    }
 }
 
 pub fn load_config<P: AsRef<Path>>(path: P) -> Result<Config, Box<dyn std::error::Error>> {
     let config_str = fs::read_to_string(path)?;
-    let config: Config = json5::from_str(&config_str)?;
+    let mut config: Config = json5::from_str(&config_str)?;
+    if let (None, Some(path)) = (&config.instrument, &config.instruments_path) {
+        let mut instruments = load_instruments(path)?;
+        let instrument = instruments
+            .remove(&config.trading_pair)
+            .ok_or_else(|| format!("{} is not listed in {}", config.trading_pair, path))?;
+        config.instrument = Some(instrument);
+    }
+    config.validate()?;
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(extra: &str) -> Config {
+        json5::from_str(&format!(
+            "{{exchanges: [\"Binance\"], trading_pair: \"ethbtc\", max_orders: 10, {extra}}}"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_rejects_clashing_synthetics() {
+        let valid = config(
+            r#"synthetics: [{name: "TestCross", exchange: "Binance", base_leg: "ethusdt", quote_leg: "btcusdt"}]"#,
+        );
+        assert_eq!(valid.validate(), Ok(()));
+
+        let shadowing = config(
+            r#"synthetics: [{name: "Binance", exchange: "Binance", base_leg: "ethusdt", quote_leg: "btcusdt"}]"#,
+        );
+        assert_eq!(
+            shadowing.validate(),
+            Err(ConfigError::SyntheticNameTaken("Binance".to_string()))
+        );
+
+        let self_referencing = config(
+            r#"synthetics: [{name: "TestLoop", exchange: "TestLoop", base_leg: "ethusdt", quote_leg: "btcusdt"}]"#,
+        );
+        assert_eq!(
+            self_referencing.validate(),
+            Err(ConfigError::SyntheticLegs {
+                name: "TestLoop".to_string(),
+                exchange: "TestLoop".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_instruments() {
+        let zero_tick = config(r#"instrument: {base: "ETH", quote: "BTC", tick_size: 0}"#);
+        assert_eq!(
+            zero_tick.validate(),
+            Err(ConfigError::InvalidIncrement {
+                field: "tick_size",
+                value: 0.0,
+            })
+        );
+        let negative_lot = config(r#"instrument: {base: "ETH", quote: "BTC", lot_size: -0.1}"#);
+        assert!(matches!(
+            negative_lot.validate(),
+            Err(ConfigError::InvalidIncrement {
+                field: "lot_size",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_candle_intervals() {
+        assert_eq!(config("candles: {}").validate(), Ok(()));
+        assert_eq!(
+            config("candles: {intervals_ms: [1000, 0]}").validate(),
+            Err(ConfigError::CandleInterval(0))
+        );
+        assert_eq!(
+            config("candles: {intervals_ms: [1000, 60000, 1000]}").validate(),
+            Err(ConfigError::CandleInterval(1000))
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_venue_keys() {
+        let valid = config(
+            r#"venues: {Bitstamp: {fees: {taker_bps: 10}}, TestCross: {}}, heartbeats: {Binance: {}},
+            synthetics: [{name: "TestCross", exchange: "Binance", base_leg: "ethusdt", quote_leg: "btcusdt"}]"#,
+        );
+        assert_eq!(valid.validate(), Ok(()));
+        assert_eq!(
+            config("venues: {Bitstmp: {}}").validate(),
+            Err(ConfigError::UnknownVenue {
+                section: "venues",
+                name: "Bitstmp".to_string(),
+            })
+        );
+        assert_eq!(
+            config("heartbeats: {binance: {}}").validate(),
+            Err(ConfigError::UnknownVenue {
+                section: "heartbeats",
+                name: "binance".to_string(),
+            })
+        );
+    }
+}
@@ -1,163 +1,323 @@
-use crate::exchange::{Exchange, ExchangeError, ExchangeOrder, ExchangeWebSocket, Orderbook};
-use async_trait::async_trait;
-use futures_util::stream::SplitSink;
-use futures_util::stream::Stream;
-use futures_util::{stream::SplitStream, StreamExt};
-use serde::Deserialize;
-use std::pin::Pin;
-use std::task::{Context, Poll};
-use tokio::net::TcpStream;
-use tokio_tungstenite::tungstenite::Error as WsError;
-use tokio_tungstenite::MaybeTlsStream;
-use tokio_tungstenite::{connect_async, tungstenite::Message, WebSocketStream};
-
-#[derive(Deserialize, Debug)]
-struct BinanceOrderbook {
+use crate::exchange::levels::BookSideSeed;
+use crate::exchange::ws_exchange::{VenueMessage, VenueProtocol, WsExchange};
+use crate::exchange::{Exchange, ExchangeError, Orderbook, Trade, TradeSide};
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Deserialize)]
+#[serde(field_identifier)]
+enum Field {
     #[serde(rename = "lastUpdateId")]
-    last_update_id: u64,
-    // TODO: potential optimization:
-    // only deserialize first 10 orders.
-    bids: Vec<BinanceOrder>,
-    asks: Vec<BinanceOrder>,
-}
-
-#[derive(Deserialize, Debug)]
-pub struct BinanceOrder {
-    price: String,
-    quantity: String,
-}
-
-impl TryFrom<BinanceOrder> for ExchangeOrder {
-    type Error = Box<dyn std::error::Error>;
-
-    fn try_from(order: BinanceOrder) -> Result<Self, Self::Error> {
-        let exchange = Exchange::Binance;
-        let price = order.price.parse::<f64>()?;
-        let amount = order.quantity.parse::<f64>()?;
-        Ok(ExchangeOrder {
-            exchange,
-            price,
-            amount,
-        })
+    LastUpdateId,
+    #[serde(rename = "bids")]
+    Bids,
+    #[serde(rename = "asks")]
+    Asks,
+    #[serde(other)]
+    Other,
+}
+
+/// Partial book depth payload:
+/// `{"lastUpdateId": 1, "bids": [["price", "qty"], ...], "asks": [...]}`.
+struct BinanceOrderbookSeed {
+    max_orders: usize,
+}
+
+impl BinanceOrderbookSeed {
+    fn side(&self) -> BookSideSeed {
+        BookSideSeed {
+            exchange: Exchange::BINANCE,
+            max_orders: self.max_orders,
+        }
     }
 }
 
-impl TryFrom<BinanceOrderbook> for Orderbook {
-    type Error = Box<dyn std::error::Error>;
+impl<'de> DeserializeSeed<'de> for BinanceOrderbookSeed {
+    type Value = Orderbook;
 
-    fn try_from(msg: BinanceOrderbook) -> Result<Self, Self::Error> {
-        let exchange_ts = msg.last_update_id;
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
 
-        let bids = msg
-            .bids
-            .into_iter()
-            .take(10)
-            .map(|order| order.try_into())
-            .collect::<Result<Vec<_>, _>>()?;
+impl<'de> Visitor<'de> for BinanceOrderbookSeed {
+    type Value = Orderbook;
 
-        let asks = msg
-            .asks
-            .into_iter()
-            .take(10)
-            .map(|order| order.try_into())
-            .collect::<Result<Vec<_>, _>>()?;
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a Binance partial book depth message")
+    }
 
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let (mut last_update_id, mut bids, mut asks) = (None, None, None);
+        while let Some(field) = map.next_key()? {
+            match field {
+                Field::LastUpdateId => last_update_id = Some(map.next_value()?),
+                Field::Bids => bids = Some(map.next_value_seed(self.side())?),
+                Field::Asks => asks = Some(map.next_value_seed(self.side())?),
+                Field::Other => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
         Ok(Orderbook {
-            exchange_ts,
-            bids,
-            asks,
+            sequence: Some(last_update_id.ok_or_else(|| de::Error::missing_field("lastUpdateId"))?),
+            // Partial depth payloads carry no event time.
+            event_time_us: None,
+            received_time_us: 0,
+            sequence_gap: None,
+            repeated: false,
+            bids: bids.ok_or_else(|| de::Error::missing_field("bids"))?,
+            asks: asks.ok_or_else(|| de::Error::missing_field("asks"))?,
         })
     }
 }
-pub struct BinanceWebSocket {
-    venue: Exchange,
-    url: String,
-    channel: String,
+
+/// Parses a depth message, keeping the best `max_orders` levels per side.
+pub fn parse_orderbook(text: &str, max_orders: usize) -> Result<Orderbook, ExchangeError> {
+    let seed = BinanceOrderbookSeed { max_orders };
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    let orderbook = seed.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(orderbook)
+}
+
+/// A reply to a request, e.g. `{"result": null, "id": 1}`, or an error,
+/// e.g. `{"code": 2, "msg": "Invalid request"}`.
+#[derive(Deserialize)]
+struct Reply {
+    #[serde(default)]
+    id: Option<serde_json::Value>,
+    #[serde(default)]
+    msg: Option<String>,
+}
+
+impl Reply {
+    /// Classifies a payload that failed to parse as data, returning
+    /// `err` if it isn't a reply either.
+    fn classify<T>(text: &str, err: ExchangeError) -> Result<VenueMessage<T>, ExchangeError> {
+        match serde_json::from_str::<Reply>(text) {
+            Ok(Reply { msg: Some(msg), .. }) => Ok(VenueMessage::Error(msg)),
+            Ok(Reply { id: Some(_), .. }) => Ok(VenueMessage::Control),
+            _ => Err(err),
+        }
+    }
+}
+
+/// Binance pings every 20 seconds and the depth stream pushes every second,
+/// so silence for longer than this means the connection is gone.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Partial book depth stream. Binance sends the pings, so no heartbeat.
+pub struct BinanceProtocol {
+    trading_pair: String,
     max_orders: usize,
-    write: Option<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>>,
-    read: Option<SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>>,
 }
 
-impl BinanceWebSocket {
+impl BinanceProtocol {
     pub fn new(trading_pair: &str, max_orders: usize) -> Self {
         Self {
-            venue: Exchange::Binance,
-            url: "wss://stream.binance.com:9443/ws/".to_string(),
-            channel: format!("{}@depth{}@1000ms", trading_pair, max_orders),
+            trading_pair: trading_pair.to_string(),
             max_orders,
-            write: None,
-            read: None,
         }
     }
+}
 
-    fn parse_orderbook_message(&self, msg: Message) -> Result<Orderbook, ExchangeError> {
-        msg.to_text()
-            .map_err(|_| ExchangeError::WebSocketError(WsError::Utf8))
-            .and_then(|text| {
-                serde_json::from_str::<BinanceOrderbook>(text).map_err(ExchangeError::ParsingError)
-            })
-            .and_then(|parsed| {
-                let exchange_ts = parsed.last_update_id;
+impl VenueProtocol for BinanceProtocol {
+    type Item = Orderbook;
 
-                let bids = parsed
-                    .bids
-                    .into_iter()
-                    .take(self.max_orders)
-                    .map(|order| order.try_into())
-                    .collect::<Result<Vec<_>, _>>()
-                    .map_err(|_| ExchangeError::ConversionError)?;
+    fn exchange(&self) -> Exchange {
+        Exchange::BINANCE
+    }
 
-                let asks = parsed
-                    .asks
-                    .into_iter()
-                    .take(self.max_orders)
-                    .map(|order| order.try_into())
-                    .collect::<Result<Vec<_>, _>>()
-                    .map_err(|_| ExchangeError::ConversionError)?;
+    fn url(&self) -> String {
+        format!(
+            "wss://stream.binance.com:9443/ws/{}@depth{}@1000ms",
+            self.trading_pair, self.max_orders
+        )
+    }
 
-                Ok(Orderbook {
-                    exchange_ts,
-                    bids,
-                    asks,
-                })
-            })
+    fn idle_timeout(&self) -> Duration {
+        IDLE_TIMEOUT
+    }
+
+    fn classify(&self, text: &str) -> Result<VenueMessage<Orderbook>, ExchangeError> {
+        // Books are by far the most common, so only look closer on failure.
+        match parse_orderbook(text, self.max_orders) {
+            Ok(orderbook) => Ok(VenueMessage::Data(orderbook)),
+            Err(err) => Reply::classify(text, err),
+        }
     }
-}
 
-#[async_trait]
-impl ExchangeWebSocket for BinanceWebSocket {
-    fn get_exchange(&self) -> Exchange {
-        self.venue.clone()
+    fn sequence_id(&self, orderbook: &Orderbook) -> Option<u64> {
+        orderbook.sequence
     }
+}
+
+pub type BinanceWebSocket = WsExchange<BinanceProtocol>;
+
+/// `trade` and `aggTrade` payloads, e.g.
+/// `{"e":"trade","E":1,"s":"ETHBTC","t":12,"p":"0.05","q":"1.5","T":1,"m":true}`.
+/// Aggregated trades carry their id in `a` instead of `t`.
+#[derive(Deserialize)]
+struct BinanceTrade<'a> {
+    #[serde(rename = "e")]
+    event: &'a str,
+    #[serde(rename = "t", default)]
+    trade_id: Option<u64>,
+    #[serde(rename = "a", default)]
+    aggregate_id: Option<u64>,
+    #[serde(rename = "p")]
+    price: &'a str,
+    #[serde(rename = "q")]
+    quantity: &'a str,
+    #[serde(rename = "T")]
+    trade_time_ms: u64,
+    #[serde(rename = "m")]
+    buyer_is_maker: bool,
+}
+
+pub fn parse_trade(text: &str) -> Result<Trade, ExchangeError> {
+    let trade: BinanceTrade = serde_json::from_str(text)?;
+    let trade_id = match trade.event {
+        "aggTrade" => trade.aggregate_id,
+        _ => trade.trade_id,
+    };
+    Ok(Trade {
+        exchange: Exchange::BINANCE,
+        price: trade
+            .price
+            .parse()
+            .map_err(|_| ExchangeError::ConversionError)?,
+        amount: trade
+            .quantity
+            .parse()
+            .map_err(|_| ExchangeError::ConversionError)?,
+        aggressor: if trade.buyer_is_maker {
+            TradeSide::Sell
+        } else {
+            TradeSide::Buy
+        },
+        trade_id: trade_id.ok_or(ExchangeError::ConversionError)?,
+        event_time_us: trade.trade_time_ms * 1_000,
+        received_time_us: 0,
+    })
+}
+
+/// Trade stream, one message per trade or, if `aggregated`, per taker
+/// order and price.
+pub struct BinanceTradeProtocol {
+    trading_pair: String,
+    aggregated: bool,
+}
 
-    async fn initialise(&mut self) -> Result<(), ExchangeError> {
-        let (ws_stream, _) = connect_async(format!("{}{}", self.url, self.channel)).await?;
-        let (write, read) = ws_stream.split();
-        self.write = Some(write);
-        self.read = Some(read);
-        Ok(())
+impl BinanceTradeProtocol {
+    pub fn new(trading_pair: &str, aggregated: bool) -> Self {
+        Self {
+            trading_pair: trading_pair.to_string(),
+            aggregated,
+        }
     }
 }
 
-impl Stream for BinanceWebSocket {
-    type Item = Result<Orderbook, ExchangeError>;
+impl VenueProtocol for BinanceTradeProtocol {
+    type Item = Trade;
 
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let this = self.get_mut();
+    fn exchange(&self) -> Exchange {
+        Exchange::BINANCE
+    }
 
-        let reader = match this.read.as_mut() {
-            Some(reader) => reader,
-            None => return Poll::Ready(None),
-        };
+    fn url(&self) -> String {
+        let stream = if self.aggregated { "aggTrade" } else { "trade" };
+        format!(
+            "wss://stream.binance.com:9443/ws/{}@{}",
+            self.trading_pair, stream
+        )
+    }
 
-        match Pin::new(reader).poll_next(cx) {
-            Poll::Ready(Some(Ok(msg))) => {
-                let orderbook_result = this.parse_orderbook_message(msg);
-                Poll::Ready(Some(orderbook_result))
-            }
-            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(ExchangeError::WebSocketError(e)))),
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Pending => Poll::Pending,
+    /// Quiet markets can go minutes without a trade; Binance's pings still
+    /// keep the connection from looking idle.
+    fn idle_timeout(&self) -> Duration {
+        IDLE_TIMEOUT
+    }
+
+    fn classify(&self, text: &str) -> Result<VenueMessage<Trade>, ExchangeError> {
+        match parse_trade(text) {
+            Ok(trade) => Ok(VenueMessage::Data(trade)),
+            Err(err) => Reply::classify(text, err),
         }
     }
+
+    fn sequence_id(&self, trade: &Trade) -> Option<u64> {
+        Some(trade.trade_id)
+    }
+}
+
+pub type BinanceTradeWebSocket = WsExchange<BinanceTradeProtocol>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEPTH_MESSAGE: &str = r#"{"lastUpdateId":160,"bids":[["0.0024","10"],["0.0023","5.5"],["0.0022","1"]],"asks":[["0.0026","100"],["0.0027","20"],["0.0028","1"]]}"#;
+
+    #[test]
+    fn test_parse_orderbook() {
+        let orderbook = parse_orderbook(DEPTH_MESSAGE, 2).unwrap();
+        assert_eq!(orderbook.sequence, Some(160));
+        assert_eq!(orderbook.event_time_us, None);
+        assert_eq!(orderbook.bids.len(), 2);
+        assert_eq!(orderbook.asks.len(), 2);
+        assert_eq!(orderbook.bids[1].price, 0.0023);
+        assert_eq!(orderbook.bids[1].amount, 5.5);
+        assert_eq!(orderbook.asks[0].price, 0.0026);
+        assert_eq!(orderbook.asks[0].exchange, Exchange::BINANCE);
+    }
+
+    #[test]
+    fn test_classify_replies() {
+        let protocol = BinanceProtocol::new("ethbtc", 10);
+        assert!(matches!(
+            protocol.classify(DEPTH_MESSAGE),
+            Ok(VenueMessage::Data(_))
+        ));
+        assert!(matches!(
+            protocol.classify(r#"{"result":null,"id":1}"#),
+            Ok(VenueMessage::Control)
+        ));
+        assert!(matches!(
+            protocol.classify(r#"{"code":2,"msg":"Invalid request"}"#),
+            Ok(VenueMessage::Error(msg)) if msg == "Invalid request"
+        ));
+        assert!(protocol.classify("{}").is_err());
+    }
+
+    #[test]
+    fn test_parse_trades() {
+        let trade = parse_trade(
+            r#"{"e":"trade","E":1700000000001,"s":"ETHBTC","t":12345,"p":"0.05123","q":"1.5","T":1700000000000,"m":true,"M":true}"#,
+        )
+        .unwrap();
+        assert_eq!(trade.trade_id, 12345);
+        assert_eq!(trade.price, 0.05123);
+        assert_eq!(trade.amount, 1.5);
+        assert_eq!(trade.aggressor, TradeSide::Sell);
+        assert_eq!(trade.event_time_us, 1700000000000000);
+
+        let trade = parse_trade(
+            r#"{"e":"aggTrade","E":1700000000001,"s":"ETHBTC","a":26129,"p":"0.05124","q":"2","f":100,"l":105,"T":1700000000000,"m":false,"M":true}"#,
+        )
+        .unwrap();
+        assert_eq!(trade.trade_id, 26129);
+        assert_eq!(trade.aggressor, TradeSide::Buy);
+        assert!(parse_trade(DEPTH_MESSAGE).is_err());
+    }
+
+    #[test]
+    fn test_parse_orderbook_rejects_malformed_messages() {
+        assert!(parse_orderbook(r#"{"result":null,"id":1}"#, 10).is_err());
+        assert!(parse_orderbook(&DEPTH_MESSAGE[..40], 10).is_err());
+        assert!(parse_orderbook(&format!("{} {{}}", DEPTH_MESSAGE), 10).is_err());
+    }
 }
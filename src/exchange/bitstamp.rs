@@ -1,85 +1,185 @@
-use crate::exchange::{Exchange, ExchangeError, ExchangeOrder, ExchangeWebSocket, Orderbook};
-use async_trait::async_trait;
-use futures_util::stream::SplitSink;
-use futures_util::stream::Stream;
-use futures_util::{stream::SplitStream, SinkExt, StreamExt};
-use serde::Deserialize;
-use serde::Serialize;
-use std::pin::Pin;
-use std::task::{Context, Poll};
-use tokio::net::TcpStream;
-use tokio_tungstenite::tungstenite::Error as WsError;
-use tokio_tungstenite::MaybeTlsStream;
-use tokio_tungstenite::{connect_async, tungstenite::Message, WebSocketStream};
-
-#[derive(Deserialize, Debug)]
-pub struct BitstampOrderbook {
-    pub data: OrderbookData,
-    pub channel: String,
-    pub event: String,
-}
-
-#[derive(Deserialize, Debug)]
-pub struct OrderbookData {
-    #[serde(skip)]
-    pub timestamp: String,
-    pub microtimestamp: String,
-    // TODO: potential optimization:
-    // only deserialize first 10 orders.
-    pub bids: Vec<BitstampOrder>,
-    pub asks: Vec<BitstampOrder>,
-}
-
-#[derive(Deserialize, Debug)]
-pub struct BitstampOrder {
-    pub price: String,
-    pub quantity: String,
-}
-
-impl TryFrom<BitstampOrder> for ExchangeOrder {
-    type Error = Box<dyn std::error::Error>;
-
-    fn try_from(order: BitstampOrder) -> Result<Self, Self::Error> {
-        let exchange = Exchange::Bitstamp;
-        let price = order.price.parse::<f64>()?;
-        let amount = order.quantity.parse::<f64>()?;
-        Ok(ExchangeOrder {
-            exchange,
-            price,
-            amount,
+use crate::exchange::levels::BookSideSeed;
+use crate::exchange::ws_exchange::{VenueMessage, VenueProtocol, WsExchange};
+use crate::exchange::{Exchange, ExchangeError, Orderbook, Trade, TradeSide};
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum MessageField {
+    Data,
+    Event,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum DataField {
+    Microtimestamp,
+    Bids,
+    Asks,
+    Message,
+    #[serde(other)]
+    Other,
+}
+
+/// Channel message: `{"data": {...}, "channel": ..., "event": ...}`. Book
+/// updates have event `data` and carry
+/// `{"microtimestamp": "...", "bids": [...], "asks": [...]}`.
+struct BitstampMessageSeed {
+    max_orders: usize,
+}
+
+/// The `data` of a message: an order book, an error message, or neither.
+struct MessageDataSeed {
+    max_orders: usize,
+}
+
+#[derive(Default)]
+struct MessageData {
+    book: Option<Orderbook>,
+    message: Option<String>,
+}
+
+impl<'de> DeserializeSeed<'de> for BitstampMessageSeed {
+    type Value = VenueMessage<Orderbook>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for BitstampMessageSeed {
+    type Value = VenueMessage<Orderbook>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a Bitstamp channel message")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let (mut data, mut event) = (MessageData::default(), None);
+        while let Some(field) = map.next_key()? {
+            match field {
+                MessageField::Data => {
+                    data = map.next_value_seed(MessageDataSeed {
+                        max_orders: self.max_orders,
+                    })?
+                }
+                MessageField::Event => event = Some(map.next_value::<&str>()?),
+                MessageField::Other => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(match event {
+            Some("data") | None => {
+                VenueMessage::Data(data.book.ok_or_else(|| de::Error::missing_field("data"))?)
+            }
+            Some(event) => channel_event(event, data.message),
         })
     }
 }
 
-impl TryFrom<BitstampOrderbook> for Orderbook {
-    type Error = Box<dyn std::error::Error>;
+/// Classifies the events every channel shares; `message` is the error
+/// text `bts:error` carries.
+fn channel_event<T>(event: &str, message: Option<String>) -> VenueMessage<T> {
+    match event {
+        "bts:heartbeat" => VenueMessage::Heartbeat,
+        "bts:request_reconnect" => VenueMessage::Reconnect,
+        "bts:error" => {
+            VenueMessage::Error(message.unwrap_or_else(|| "unspecified error".to_string()))
+        }
+        _ => VenueMessage::Control,
+    }
+}
+
+impl MessageDataSeed {
+    fn side(&self) -> BookSideSeed {
+        BookSideSeed {
+            exchange: Exchange::BITSTAMP,
+            max_orders: self.max_orders,
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for MessageDataSeed {
+    type Value = MessageData;
 
-    fn try_from(msg: BitstampOrderbook) -> Result<Self, Self::Error> {
-        // Parse the microtimestamp as u64
-        let exchange_ts = msg.data.microtimestamp.parse::<u64>()?;
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for MessageDataSeed {
+    type Value = MessageData;
 
-        let bids = msg
-            .data
-            .bids
-            .into_iter()
-            .map(|order| order.try_into())
-            .collect::<Result<Vec<_>, _>>()?;
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Bitstamp message data")
+    }
 
-        let asks = msg
-            .data
-            .asks
-            .into_iter()
-            .map(|order| order.try_into())
-            .collect::<Result<Vec<_>, _>>()?;
+    /// `bts:request_reconnect` sends an empty string.
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<Self::Value, E> {
+        Ok(MessageData::default())
+    }
 
-        Ok(Orderbook {
-            exchange_ts,
-            bids,
-            asks,
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let (mut microtimestamp, mut bids, mut asks) = (None, None, None);
+        let mut message = None;
+        while let Some(field) = map.next_key()? {
+            match field {
+                DataField::Microtimestamp => {
+                    let value: &str = map.next_value()?;
+                    microtimestamp = Some(value.parse().map_err(de::Error::custom)?);
+                }
+                DataField::Bids => bids = Some(map.next_value_seed(self.side())?),
+                DataField::Asks => asks = Some(map.next_value_seed(self.side())?),
+                DataField::Message => message = map.next_value()?,
+                DataField::Other => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        if microtimestamp.is_none() && bids.is_none() && asks.is_none() {
+            return Ok(MessageData {
+                book: None,
+                message,
+            });
+        }
+        let book = Orderbook {
+            sequence: None,
+            event_time_us: Some(
+                microtimestamp.ok_or_else(|| de::Error::missing_field("microtimestamp"))?,
+            ),
+            received_time_us: 0,
+            sequence_gap: None,
+            repeated: false,
+            bids: bids.ok_or_else(|| de::Error::missing_field("bids"))?,
+            asks: asks.ok_or_else(|| de::Error::missing_field("asks"))?,
+        };
+        Ok(MessageData {
+            book: Some(book),
+            message,
         })
     }
 }
 
+/// Classifies a channel message, parsing books down to the best
+/// `max_orders` levels per side.
+pub fn parse_message(
+    text: &str,
+    max_orders: usize,
+) -> Result<VenueMessage<Orderbook>, ExchangeError> {
+    let seed = BitstampMessageSeed { max_orders };
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    let message = seed.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(message)
+}
+
 #[derive(Serialize)]
 struct Subscription {
     event: String,
@@ -91,116 +191,270 @@ struct Channel {
     channel: String,
 }
 
-pub struct BitstampWebSocket {
-    venue: Exchange,
-    url: String,
-    channel: String,
+/// The book channel only pushes on change, so a quiet market can be silent;
+/// heartbeats keep replies flowing well within the idle timeout.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn heartbeat() -> Message {
+    Message::Text(r#"{"event":"bts:heartbeat"}"#.into())
+}
+
+/// Full order book channel, ordered by `microtimestamp`.
+pub struct BitstampProtocol {
+    trading_pair: String,
     max_orders: usize,
-    write: Option<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>>,
-    read: Option<SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>>,
 }
 
-impl BitstampWebSocket {
+impl BitstampProtocol {
     pub fn new(trading_pair: &str, max_orders: usize) -> Self {
         Self {
-            venue: Exchange::Bitstamp,
-            url: "wss://ws.bitstamp.net/".to_string(),
-            channel: "order_book_".to_string() + trading_pair,
+            trading_pair: trading_pair.to_string(),
             max_orders,
-            write: None,
-            read: None,
         }
     }
+}
+
+impl VenueProtocol for BitstampProtocol {
+    type Item = Orderbook;
+
+    fn exchange(&self) -> Exchange {
+        Exchange::BITSTAMP
+    }
+
+    fn url(&self) -> String {
+        "wss://ws.bitstamp.net/".to_string()
+    }
 
-    fn parse_orderbook_message(&self, msg: Message) -> Result<Orderbook, ExchangeError> {
-        msg.to_text()
-            .map_err(|_| ExchangeError::WebSocketError(WsError::Utf8))
-            .and_then(|text| {
-                serde_json::from_str::<BitstampOrderbook>(text).map_err(ExchangeError::ParsingError)
-            })
-            .and_then(|parsed| {
-                let exchange_ts = parsed
-                    .data
-                    .microtimestamp
-                    .parse::<u64>()
-                    .map_err(|_| ExchangeError::ConversionError)?;
-
-                let bids = parsed
-                    .data
-                    .bids
-                    .into_iter()
-                    .take(self.max_orders)
-                    .map(|order| order.try_into())
-                    .collect::<Result<Vec<_>, _>>()
-                    .map_err(|_| ExchangeError::ConversionError)?;
-
-                let asks = parsed
-                    .data
-                    .asks
-                    .into_iter()
-                    .take(self.max_orders)
-                    .map(|order| order.try_into())
-                    .collect::<Result<Vec<_>, _>>()
-                    .map_err(|_| ExchangeError::ConversionError)?;
-
-                Ok(Orderbook {
-                    exchange_ts,
-                    bids,
-                    asks,
-                })
-            })
-    }
-}
-
-#[async_trait]
-impl ExchangeWebSocket for BitstampWebSocket {
-    fn get_exchange(&self) -> Exchange {
-        self.venue.clone()
-    }
-
-    async fn initialise(&mut self) -> Result<(), ExchangeError> {
+    fn subscription(&self) -> Option<String> {
         let subscription = Subscription {
             event: "bts:subscribe".to_string(),
             data: Channel {
-                channel: self.channel.clone(),
+                channel: "order_book_".to_string() + &self.trading_pair,
             },
         };
-        let json_subscription =
-            serde_json::to_string(&subscription).map_err(|e| ExchangeError::ParsingError(e))?;
-        let (ws_stream, _) = connect_async(&self.url).await?;
-        let (mut write, read) = ws_stream.split();
+        serde_json::to_string(&subscription).ok()
+    }
+
+    fn idle_timeout(&self) -> Duration {
+        IDLE_TIMEOUT
+    }
 
-        write
-            .send(Message::Text(json_subscription.into()))
-            .await
-            .map_err(|e| ExchangeError::WebSocketError(e))?;
+    fn heartbeat(&self) -> Option<(Duration, fn() -> Message)> {
+        Some((HEARTBEAT_INTERVAL, heartbeat))
+    }
+
+    fn classify(&self, text: &str) -> Result<VenueMessage<Orderbook>, ExchangeError> {
+        parse_message(text, self.max_orders)
+    }
+
+    fn sequence_id(&self, orderbook: &Orderbook) -> Option<u64> {
+        orderbook.event_time_us
+    }
+}
+
+pub type BitstampWebSocket = WsExchange<BitstampProtocol>;
+
+/// `live_trades` message: `{"data": {...}, "channel": ..., "event": "trade"}`.
+#[derive(Deserialize)]
+struct TradeMessage {
+    event: String,
+    #[serde(default)]
+    data: TradeMessageData,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TradeMessageData {
+    Trade(BitstampTrade),
+    Error { message: String },
+    Other(IgnoredAny),
+}
+
+impl Default for TradeMessageData {
+    fn default() -> Self {
+        TradeMessageData::Other(IgnoredAny)
+    }
+}
+
+/// `type` is the aggressor: 0 for a buy, 1 for a sell.
+#[derive(Deserialize)]
+struct BitstampTrade {
+    id: u64,
+    price: f64,
+    amount: f64,
+    #[serde(rename = "type")]
+    side: u8,
+    microtimestamp: String,
+}
+
+/// Classifies a `live_trades` channel message.
+pub fn parse_trade_message(text: &str) -> Result<VenueMessage<Trade>, ExchangeError> {
+    let message: TradeMessage = serde_json::from_str(text)?;
+    match (message.event.as_str(), message.data) {
+        ("trade", TradeMessageData::Trade(trade)) => Ok(VenueMessage::Data(Trade {
+            exchange: Exchange::BITSTAMP,
+            price: trade.price,
+            amount: trade.amount,
+            aggressor: match trade.side {
+                0 => TradeSide::Buy,
+                1 => TradeSide::Sell,
+                _ => return Err(ExchangeError::ConversionError),
+            },
+            trade_id: trade.id,
+            event_time_us: trade
+                .microtimestamp
+                .parse()
+                .map_err(|_| ExchangeError::ConversionError)?,
+            received_time_us: 0,
+        })),
+        ("trade", _) => Err(ExchangeError::ConversionError),
+        (event, TradeMessageData::Error { message }) => Ok(channel_event(event, Some(message))),
+        (event, _) => Ok(channel_event(event, None)),
+    }
+}
 
-        self.write = Some(write);
-        self.read = Some(read);
+/// Live trades channel, ordered by trade id.
+pub struct BitstampTradeProtocol {
+    trading_pair: String,
+}
 
-        Ok(())
+impl BitstampTradeProtocol {
+    pub fn new(trading_pair: &str) -> Self {
+        Self {
+            trading_pair: trading_pair.to_string(),
+        }
     }
 }
 
-impl Stream for BitstampWebSocket {
-    type Item = Result<Orderbook, ExchangeError>;
+impl VenueProtocol for BitstampTradeProtocol {
+    type Item = Trade;
 
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let this = self.get_mut();
+    fn exchange(&self) -> Exchange {
+        Exchange::BITSTAMP
+    }
 
-        let reader = match this.read.as_mut() {
-            Some(reader) => reader,
-            None => return Poll::Ready(None),
+    fn url(&self) -> String {
+        "wss://ws.bitstamp.net/".to_string()
+    }
+
+    fn subscription(&self) -> Option<String> {
+        let subscription = Subscription {
+            event: "bts:subscribe".to_string(),
+            data: Channel {
+                channel: "live_trades_".to_string() + &self.trading_pair,
+            },
         };
+        serde_json::to_string(&subscription).ok()
+    }
 
-        match Pin::new(reader).poll_next(cx) {
-            Poll::Ready(Some(Ok(msg))) => {
-                let orderbook_result = this.parse_orderbook_message(msg);
-                Poll::Ready(Some(orderbook_result))
-            }
-            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(ExchangeError::WebSocketError(e)))),
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Pending => Poll::Pending,
+    fn idle_timeout(&self) -> Duration {
+        IDLE_TIMEOUT
+    }
+
+    fn heartbeat(&self) -> Option<(Duration, fn() -> Message)> {
+        Some((HEARTBEAT_INTERVAL, heartbeat))
+    }
+
+    fn classify(&self, text: &str) -> Result<VenueMessage<Trade>, ExchangeError> {
+        parse_trade_message(text)
+    }
+
+    fn sequence_id(&self, trade: &Trade) -> Option<u64> {
+        Some(trade.trade_id)
+    }
+}
+
+pub type BitstampTradeWebSocket = WsExchange<BitstampTradeProtocol>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORDER_BOOK_MESSAGE: &str = r#"{"data":{"timestamp":"1700000000","microtimestamp":"1700000000123456","bids":[["0.05123","1.2"],["0.05122","0.4"],["0.05120","3"]],"asks":[["0.05125","2"],["0.05127","0.1"]]},"channel":"order_book_ethbtc","event":"data"}"#;
+
+    fn parse_book(text: &str, max_orders: usize) -> Orderbook {
+        match parse_message(text, max_orders) {
+            Ok(VenueMessage::Data(orderbook)) => orderbook,
+            other => panic!("expected a book, got {:?}", other),
         }
     }
+
+    #[test]
+    fn test_parse_orderbook() {
+        let orderbook = parse_book(ORDER_BOOK_MESSAGE, 2);
+        assert_eq!(orderbook.event_time_us, Some(1700000000123456));
+        assert_eq!(orderbook.sequence, None);
+        assert_eq!(orderbook.bids.len(), 2);
+        assert_eq!(orderbook.asks.len(), 2);
+        assert_eq!(orderbook.bids[0].price, 0.05123);
+        assert_eq!(orderbook.bids[1].amount, 0.4);
+        assert_eq!(orderbook.asks[1].price, 0.05127);
+        assert_eq!(orderbook.asks[1].exchange, Exchange::BITSTAMP);
+    }
+
+    #[test]
+    fn test_classifies_other_events() {
+        let subscribed =
+            r#"{"event":"bts:subscription_succeeded","channel":"order_book_ethbtc","data":{}}"#;
+        let heartbeat = r#"{"event":"bts:heartbeat","channel":"","data":{"status":"success"}}"#;
+        let reconnect = r#"{"event":"bts:request_reconnect","channel":"","data":""}"#;
+        let error = r#"{"event":"bts:error","channel":"","data":{"code":null,"message":"Bad subscription string."}}"#;
+
+        assert!(matches!(
+            parse_message(subscribed, 10),
+            Ok(VenueMessage::Control)
+        ));
+        assert!(matches!(
+            parse_message(heartbeat, 10),
+            Ok(VenueMessage::Heartbeat)
+        ));
+        assert!(matches!(
+            parse_message(reconnect, 10),
+            Ok(VenueMessage::Reconnect)
+        ));
+        assert!(matches!(
+            parse_message(error, 10),
+            Ok(VenueMessage::Error(message)) if message == "Bad subscription string."
+        ));
+    }
+
+    #[test]
+    fn test_parse_trade_messages() {
+        let trade = r#"{"data":{"id":325432342,"timestamp":"1700000000","amount":0.25,"amount_str":"0.25","price":0.05123,"price_str":"0.05123","type":1,"microtimestamp":"1700000000123456","buy_order_id":1,"sell_order_id":2},"channel":"live_trades_ethbtc","event":"trade"}"#;
+        let Ok(VenueMessage::Data(trade)) = parse_trade_message(trade) else {
+            panic!("expected a trade");
+        };
+        assert_eq!(trade.trade_id, 325432342);
+        assert_eq!(trade.price, 0.05123);
+        assert_eq!(trade.amount, 0.25);
+        assert_eq!(trade.aggressor, TradeSide::Sell);
+        assert_eq!(trade.event_time_us, 1700000000123456);
+
+        let subscribed =
+            r#"{"event":"bts:subscription_succeeded","channel":"live_trades_ethbtc","data":{}}"#;
+        let error = r#"{"event":"bts:error","channel":"","data":{"code":null,"message":"Bad subscription string."}}"#;
+        let reconnect = r#"{"event":"bts:request_reconnect","channel":"","data":""}"#;
+        assert!(matches!(
+            parse_trade_message(subscribed),
+            Ok(VenueMessage::Control)
+        ));
+        assert!(matches!(
+            parse_trade_message(error),
+            Ok(VenueMessage::Error(_))
+        ));
+        assert!(matches!(
+            parse_trade_message(reconnect),
+            Ok(VenueMessage::Reconnect)
+        ));
+        assert!(parse_trade_message(r#"{"event":"trade","data":{}}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_orderbook_rejects_malformed_books() {
+        let missing_asks = ORDER_BOOK_MESSAGE.replace(r#""asks""#, r#""offers""#);
+        assert!(parse_message(&missing_asks, 10).is_err());
+        let bad_timestamp = ORDER_BOOK_MESSAGE.replace("1700000000123456", "soon");
+        assert!(parse_message(&bad_timestamp, 10).is_err());
+    }
 }
@@ -0,0 +1,369 @@
+use crate::config::HeartbeatConfig;
+use crate::exchange::{Exchange, ExchangeError};
+use futures_util::stream::Stream;
+use futures_util::{Sink, SinkExt, StreamExt};
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::{
+    interval_at, sleep, sleep_until, timeout, Instant, Interval, MissedTickBehavior, Sleep,
+};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tracing::{info, warn};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type ConnectFuture = Pin<Box<dyn Future<Output = Result<WsStream, ExchangeError>> + Send>>;
+
+/// Ceiling on the first reconnect's delay, doubled for each attempt since
+/// the connection was last healthy.
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(100);
+
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// A connection up for this long resets the backoff.
+const HEALTHY_PERIOD: Duration = Duration::from_secs(30);
+
+/// Exponential backoff with jitter, so a venue outage doesn't have every
+/// feed reconnecting in lockstep.
+#[derive(Debug, Default)]
+struct Backoff {
+    attempts: u32,
+    connected_at: Option<Instant>,
+}
+
+impl Backoff {
+    fn on_connected(&mut self, now: Instant) {
+        self.connected_at = Some(now);
+    }
+
+    /// The delay before the next attempt: between half and all of the
+    /// current ceiling.
+    fn next_delay(&mut self, now: Instant) -> Duration {
+        if self
+            .connected_at
+            .take()
+            .is_some_and(|connected_at| now.duration_since(connected_at) >= HEALTHY_PERIOD)
+        {
+            self.attempts = 0;
+        }
+        let ceiling = INITIAL_RECONNECT_DELAY
+            .saturating_mul(1 << self.attempts.min(16))
+            .min(MAX_RECONNECT_DELAY);
+        self.attempts += 1;
+        let unit = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        ceiling.mul_f64(0.5 + unit / 2.0)
+    }
+}
+
+enum State {
+    Disconnected,
+    Connecting(ConnectFuture),
+    Connected(Box<WsStream>),
+}
+
+/// A venue WebSocket that answers pings, sends the venue's heartbeat and
+/// reconnects (resubscribing) when the socket closes or has been silent
+/// for longer than the idle timeout, so a half-open connection isn't
+/// mistaken for a quiet market. Yields text payloads.
+pub struct WsConnection {
+    exchange: Exchange,
+    url: String,
+    subscribe: Option<String>,
+    idle_timeout: Duration,
+    heartbeat_interval: Option<Duration>,
+    heartbeat: fn() -> Message,
+    state: State,
+    idle: Option<Pin<Box<Sleep>>>,
+    heartbeat_timer: Option<Interval>,
+    outgoing: VecDeque<Message>,
+    backoff: Backoff,
+}
+
+impl WsConnection {
+    pub fn new(exchange: Exchange, url: String, idle_timeout: Duration) -> Self {
+        Self {
+            exchange,
+            url,
+            subscribe: None,
+            idle_timeout,
+            heartbeat_interval: None,
+            heartbeat: || Message::Ping(Default::default()),
+            state: State::Disconnected,
+            idle: None,
+            heartbeat_timer: None,
+            outgoing: VecDeque::new(),
+            backoff: Backoff::default(),
+        }
+    }
+
+    /// Sent after every (re)connect.
+    pub fn with_subscription(mut self, subscribe: String) -> Self {
+        self.subscribe = Some(subscribe);
+        self
+    }
+
+    /// Sends `heartbeat()` every `interval`. Defaults to a WebSocket ping.
+    pub fn with_heartbeat(mut self, interval: Duration, heartbeat: fn() -> Message) -> Self {
+        self.heartbeat_interval = Some(interval);
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// Overrides the venue's defaults with any values set in `config`.
+    pub fn with_config(mut self, config: &HeartbeatConfig) -> Self {
+        if let Some(idle_timeout_ms) = config.idle_timeout_ms {
+            self.idle_timeout = Duration::from_millis(idle_timeout_ms);
+        }
+        if let Some(interval_ms) = config.interval_ms {
+            // Zero turns the heartbeat off.
+            self.heartbeat_interval =
+                Some(Duration::from_millis(interval_ms)).filter(|interval| !interval.is_zero());
+        }
+        self
+    }
+
+    /// Connects and subscribes, returning once the subscription is sent.
+    pub async fn connect(&mut self) -> Result<(), ExchangeError> {
+        let ws_stream = connect(self.url.clone(), self.subscribe.clone()).await?;
+        self.on_connected(ws_stream);
+        Ok(())
+    }
+
+    fn on_connected(&mut self, ws_stream: WsStream) {
+        self.state = State::Connected(Box::new(ws_stream));
+        self.backoff.on_connected(Instant::now());
+        self.outgoing.clear();
+        self.reset_idle();
+        self.heartbeat_timer = self.heartbeat_interval.map(|period| {
+            let mut timer = interval_at(Instant::now() + period, period);
+            timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            timer
+        });
+    }
+
+    fn reset_idle(&mut self) {
+        let deadline = Instant::now() + self.idle_timeout;
+        match self.idle.as_mut() {
+            Some(idle) => idle.as_mut().reset(deadline),
+            None => self.idle = Some(Box::pin(sleep_until(deadline))),
+        }
+    }
+
+    /// Drops the current connection and reconnects.
+    pub fn reconnect_now(&mut self) {
+        info!(exchange = %self.exchange, event = "reconnect_requested", "Reconnecting to exchange");
+        self.reconnect();
+    }
+
+    /// Reconnects after the backoff delay. An attempt that takes longer than
+    /// the idle timeout fails like a silent connection would.
+    fn reconnect(&mut self) {
+        let delay = self.backoff.next_delay(Instant::now());
+        let (url, subscribe) = (self.url.clone(), self.subscribe.clone());
+        let (exchange, idle) = (self.exchange, self.idle_timeout);
+        self.heartbeat_timer = None;
+        self.state = State::Connecting(Box::pin(async move {
+            sleep(delay).await;
+            timeout(idle, connect(url, subscribe))
+                .await
+                .map_err(|_| ExchangeError::IdleTimeout { exchange, idle })?
+        }));
+    }
+
+    /// Queues due heartbeats and writes queued frames without blocking reads.
+    fn poll_send(&mut self, cx: &mut Context<'_>) -> Result<(), ExchangeError> {
+        if let Some(timer) = self.heartbeat_timer.as_mut() {
+            while timer.poll_tick(cx).is_ready() {
+                self.outgoing.push_back((self.heartbeat)());
+            }
+        }
+        let State::Connected(ws_stream) = &mut self.state else {
+            return Ok(());
+        };
+        while !self.outgoing.is_empty() {
+            match Pin::new(&mut **ws_stream).poll_ready(cx) {
+                Poll::Ready(Ok(())) => {
+                    let message = self.outgoing.pop_front().expect("queue is not empty");
+                    Pin::new(&mut **ws_stream).start_send(message)?;
+                }
+                Poll::Ready(Err(err)) => return Err(err.into()),
+                Poll::Pending => return Ok(()),
+            }
+        }
+        match Pin::new(&mut **ws_stream).poll_flush(cx) {
+            Poll::Ready(Err(err)) => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+async fn connect(url: String, subscribe: Option<String>) -> Result<WsStream, ExchangeError> {
+    let (mut ws_stream, _) = connect_async(url).await?;
+    if let Some(subscribe) = subscribe {
+        ws_stream.send(Message::Text(subscribe.into())).await?;
+    }
+    Ok(ws_stream)
+}
+
+impl Stream for WsConnection {
+    type Item = Result<String, ExchangeError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let State::Connecting(future) = &mut this.state {
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(Ok(ws_stream)) => {
+                        info!(exchange = %this.exchange, event = "reconnected", "Reconnected to exchange");
+                        this.on_connected(ws_stream);
+                    }
+                    Poll::Ready(Err(err)) => {
+                        warn!(
+                            exchange = %this.exchange,
+                            event = "reconnect_failed",
+                            error = %err,
+                            "Failed to reconnect to exchange"
+                        );
+                        this.reconnect();
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+
+            if let Err(err) = this.poll_send(cx) {
+                warn!(
+                    exchange = %this.exchange,
+                    event = "write_error",
+                    error = %err,
+                    "Failed to write to exchange, reconnecting"
+                );
+                this.reconnect();
+                return Poll::Ready(Some(Err(err)));
+            }
+
+            let State::Connected(ws_stream) = &mut this.state else {
+                return Poll::Ready(None);
+            };
+            match ws_stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(message))) => {
+                    this.reset_idle();
+                    match message {
+                        Message::Text(text) => return Poll::Ready(Some(Ok(text.to_string()))),
+                        Message::Ping(payload) => this.outgoing.push_back(Message::Pong(payload)),
+                        Message::Close(frame) => {
+                            info!(
+                                exchange = %this.exchange,
+                                event = "closed",
+                                frame = ?frame,
+                                "Exchange closed the connection, reconnecting"
+                            );
+                            this.reconnect();
+                        }
+                        Message::Binary(_) | Message::Pong(_) | Message::Frame(_) => {}
+                    }
+                }
+                Poll::Ready(Some(Err(err))) => {
+                    warn!(
+                        exchange = %this.exchange,
+                        event = "read_error",
+                        error = %err,
+                        "Exchange connection failed, reconnecting"
+                    );
+                    this.reconnect();
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+                Poll::Ready(None) => {
+                    info!(exchange = %this.exchange, event = "closed", "Exchange connection ended, reconnecting");
+                    this.reconnect();
+                }
+                Poll::Pending => {
+                    let idle = this.idle.as_mut().expect("set when connected");
+                    if idle.as_mut().poll(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                    warn!(
+                        exchange = %this.exchange,
+                        event = "idle_timeout",
+                        idle_timeout_ms = this.idle_timeout.as_millis() as u64,
+                        "No data from exchange, reconnecting"
+                    );
+                    this.reconnect();
+                    return Poll::Ready(Some(Err(ExchangeError::IdleTimeout {
+                        exchange: this.exchange,
+                        idle: this.idle_timeout,
+                    })));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    #[test]
+    fn test_backoff_grows_and_resets_once_healthy() {
+        let mut backoff = Backoff::default();
+        let start = Instant::now();
+        for ceiling in [100, 200, 400, 800] {
+            let delay = backoff.next_delay(start).as_millis() as u64;
+            assert!((ceiling / 2..=ceiling).contains(&delay), "{delay}ms");
+        }
+        for _ in 0..20 {
+            assert!(backoff.next_delay(start) <= MAX_RECONNECT_DELAY);
+        }
+
+        // A connection that drops quickly keeps backing off.
+        backoff.on_connected(start);
+        assert!(backoff.next_delay(start + Duration::from_secs(1)) >= MAX_RECONNECT_DELAY / 2);
+        backoff.on_connected(start);
+        assert!(backoff.next_delay(start + HEALTHY_PERIOD) <= INITIAL_RECONNECT_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_answers_pings_and_reconnects_when_idle() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut subscriptions = Vec::new();
+            // The first connection pings, publishes once and goes silent.
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut first = accept_async(stream).await.unwrap();
+            subscriptions.push(first.next().await.unwrap().unwrap());
+            first.send(Message::Ping("hb".into())).await.unwrap();
+            let pong = first.next().await.unwrap().unwrap();
+            first.send(Message::Text("first".into())).await.unwrap();
+
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut second = accept_async(stream).await.unwrap();
+            subscriptions.push(second.next().await.unwrap().unwrap());
+            second.send(Message::Text("second".into())).await.unwrap();
+            (subscriptions, pong, first, second)
+        });
+
+        let mut connection = WsConnection::new(Exchange::BITSTAMP, url, Duration::from_millis(200))
+            .with_subscription("subscribe".to_string());
+        connection.connect().await.unwrap();
+
+        assert_eq!(connection.next().await.unwrap().unwrap(), "first");
+        assert!(matches!(
+            connection.next().await,
+            Some(Err(ExchangeError::IdleTimeout { .. }))
+        ));
+        assert_eq!(connection.next().await.unwrap().unwrap(), "second");
+
+        let (subscriptions, pong, _, _) = server.await.unwrap();
+        assert_eq!(pong, Message::Pong("hb".into()));
+        assert_eq!(subscriptions, vec![Message::Text("subscribe".into()); 2]);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::exchange::{
+    Exchange, ExchangeError, ExchangeOrder, ExchangeStream, ExchangeWebSocket, Orderbook,
+};
+use async_trait::async_trait;
+use futures_util::stream::Stream;
+use futures_util::StreamExt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A venue's book with prices converted into the common quote currency at
+/// the mid of a reference-rate book, e.g. an ETH/USD venue merged into an
+/// ETH/USDT book via a USDT/USD rate with `invert` set. Emits nothing until
+/// both books have arrived, then re-emits on either update; a book sent
+/// again only for a new rate is marked `repeated`. Converted levels keep
+/// the venue's price in `original_price`.
+pub struct QuoteConversion {
+    venue: Box<dyn ExchangeStream>,
+    rate: Box<dyn ExchangeStream>,
+    invert: bool,
+    venue_book: Option<Orderbook>,
+    rate_mid: Option<f64>,
+}
+
+impl QuoteConversion {
+    pub fn new(
+        venue: Box<dyn ExchangeStream>,
+        rate: Box<dyn ExchangeStream>,
+        invert: bool,
+    ) -> Self {
+        Self {
+            venue,
+            rate,
+            invert,
+            venue_book: None,
+            rate_mid: None,
+        }
+    }
+
+    /// Common quote per unit of the venue's quote, from the reference
+    /// book's mid. `None` while either side of that book is empty.
+    fn conversion_rate(&self, book: &Orderbook) -> Option<f64> {
+        let (bid, ask) = (book.bids.first()?, book.asks.first()?);
+        let mid = (bid.price + ask.price) / 2.0;
+        if mid <= 0.0 {
+            return None;
+        }
+        Some(if self.invert { 1.0 / mid } else { mid })
+    }
+
+    /// The venue's last book at the current rate. A `repeated` book is the
+    /// venue's message sent again, so it carries no new sequence gap.
+    fn converted(&self, repeated: bool) -> Option<Orderbook> {
+        let (book, rate) = (self.venue_book.as_ref()?, self.rate_mid?);
+        Some(Orderbook {
+            bids: convert_levels(&book.bids, rate),
+            asks: convert_levels(&book.asks, rate),
+            sequence_gap: book.sequence_gap.filter(|_| !repeated),
+            repeated,
+            ..book.clone()
+        })
+    }
+}
+
+/// Multiplies each level's price by `rate`, keeping the venue's price.
+pub fn convert_levels(levels: &[ExchangeOrder], rate: f64) -> Vec<ExchangeOrder> {
+    levels
+        .iter()
+        .map(|level| ExchangeOrder {
+            price: level.price * rate,
+            original_price: Some(level.original_price.unwrap_or(level.price)),
+            ..level.clone()
+        })
+        .collect()
+}
+
+#[async_trait]
+impl ExchangeWebSocket for QuoteConversion {
+    fn get_exchange(&self) -> Exchange {
+        self.venue.get_exchange()
+    }
+
+    async fn initialise(&mut self) -> Result<(), ExchangeError> {
+        self.venue.initialise().await?;
+        self.rate.initialise().await
+    }
+}
+
+impl Stream for QuoteConversion {
+    type Item = Result<Orderbook, ExchangeError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let (mut venue_updated, mut rate_updated) = (false, false);
+            match this.venue.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(book))) => {
+                    this.venue_book = Some(book);
+                    venue_updated = true;
+                }
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => {}
+            }
+            match this.rate.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(book))) => {
+                    // Keep the last good rate through a one-sided book.
+                    if let Some(rate) = this.conversion_rate(&book) {
+                        rate_updated = this.rate_mid != Some(rate);
+                        this.rate_mid = Some(rate);
+                    }
+                }
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                // Without a rate the venue's prices can't be merged.
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => {}
+            }
+            if !venue_updated && !rate_updated {
+                return Poll::Pending;
+            }
+            if let Some(book) = this.converted(!venue_updated) {
+                return Poll::Ready(Some(Ok(book)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::fake_feed::FakeFeed;
+    use crate::feed_stats::FeedStats;
+
+    const VENUE: Exchange = Exchange::new("TestUsdVenue");
+
+    fn book(exchange: Exchange, bid: f64, ask: f64) -> Orderbook {
+        let order = |price| ExchangeOrder::new(exchange, price, 1.0);
+        Orderbook {
+            bids: vec![order(bid)],
+            asks: vec![order(ask)],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_converts_venue_prices_at_reference_mid() {
+        let venue = FakeFeed::open(VENUE, vec![Ok(book(VENUE, 2000.0, 2002.0))]);
+        // USDT priced in USD, so USD converts to USDT at the inverse.
+        let rate = FakeFeed::open(
+            Exchange::BINANCE,
+            vec![
+                Ok(book(Exchange::BINANCE, 0.99, 1.01)),
+                Ok(book(Exchange::BINANCE, 0.79, 0.81)),
+            ],
+        );
+        let mut converted = QuoteConversion::new(Box::new(venue), Box::new(rate), true);
+
+        let first = converted.next().await.unwrap().unwrap();
+        assert_eq!(first.bids[0].exchange, VENUE);
+        assert_eq!(first.bids[0].price, 2000.0);
+        assert_eq!(first.bids[0].original_price, Some(2000.0));
+        assert_eq!(first.asks[0].price, 2002.0);
+
+        let second = converted.next().await.unwrap().unwrap();
+        assert!((second.bids[0].price - 2500.0).abs() < 1e-9);
+        assert_eq!(second.asks[0].original_price, Some(2002.0));
+        assert_eq!(converted.get_exchange(), VENUE);
+    }
+
+    #[tokio::test]
+    async fn test_rate_ticks_do_not_count_as_venue_messages() {
+        let venue = FakeFeed::open(
+            VENUE,
+            vec![Ok(Orderbook {
+                sequence_gap: Some(500),
+                ..book(VENUE, 2000.0, 2002.0)
+            })],
+        );
+        let rate = FakeFeed::open(
+            Exchange::BINANCE,
+            vec![
+                Ok(book(Exchange::BINANCE, 0.99, 1.01)),
+                Ok(book(Exchange::BINANCE, 0.79, 0.81)),
+            ],
+        );
+        let mut converted = QuoteConversion::new(Box::new(venue), Box::new(rate), true);
+        let feed_stats = FeedStats::default();
+
+        let first = converted.next().await.unwrap().unwrap();
+        assert!(!first.repeated);
+        feed_stats.record(&VENUE, &first);
+        let second = converted.next().await.unwrap().unwrap();
+        assert!(second.repeated);
+        assert_eq!(second.sequence_gap, None);
+        feed_stats.record(&VENUE, &second);
+
+        let stats = &feed_stats.venues()[0];
+        assert_eq!((stats.messages, stats.sequence_gaps), (1, 1));
+    }
+}
@@ -0,0 +1,49 @@
+use crate::exchange::{Exchange, ExchangeError, ExchangeWebSocket};
+use async_trait::async_trait;
+use futures_util::stream::{self, BoxStream, Stream};
+use futures_util::StreamExt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A venue feed for tests that yields scripted books or trades.
+pub struct FakeFeed<T> {
+    exchange: Exchange,
+    items: BoxStream<'static, Result<T, ExchangeError>>,
+}
+
+impl<T: Send + 'static> FakeFeed<T> {
+    /// Yields `items`, then ends as a dropped connection would.
+    pub fn new(exchange: Exchange, items: Vec<Result<T, ExchangeError>>) -> Self {
+        Self {
+            exchange,
+            items: stream::iter(items).boxed(),
+        }
+    }
+
+    /// Yields `items`, then stays open without sending anything more.
+    pub fn open(exchange: Exchange, items: Vec<Result<T, ExchangeError>>) -> Self {
+        Self {
+            exchange,
+            items: stream::iter(items).chain(stream::pending()).boxed(),
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Send> ExchangeWebSocket for FakeFeed<T> {
+    fn get_exchange(&self) -> Exchange {
+        self.exchange
+    }
+
+    async fn initialise(&mut self) -> Result<(), ExchangeError> {
+        Ok(())
+    }
+}
+
+impl<T> Stream for FakeFeed<T> {
+    type Item = Result<T, ExchangeError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.items.poll_next_unpin(cx)
+    }
+}
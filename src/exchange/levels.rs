@@ -0,0 +1,83 @@
+use crate::exchange::{Exchange, ExchangeOrder};
+use serde::de::{self, DeserializeSeed, IgnoredAny, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+
+/// A `["price", "amount"]` pair borrowed straight from the payload.
+#[derive(Deserialize)]
+struct RawLevel<'a>(&'a str, &'a str);
+
+/// Deserializes one side of a book, `[["price", "amount"], ...]`, keeping
+/// only the first `max_orders` levels. The remaining levels are skipped
+/// without being parsed or allocated.
+pub struct BookSideSeed {
+    pub exchange: Exchange,
+    pub max_orders: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for BookSideSeed {
+    type Value = Vec<ExchangeOrder>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for BookSideSeed {
+    type Value = Vec<ExchangeOrder>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an array of [price, amount] levels")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut orders = Vec::with_capacity(self.max_orders.min(seq.size_hint().unwrap_or(0)));
+        while orders.len() < self.max_orders {
+            let Some(RawLevel(price, amount)) = seq.next_element()? else {
+                return Ok(orders);
+            };
+            orders.push(ExchangeOrder {
+                exchange: self.exchange,
+                price: price.parse().map_err(de::Error::custom)?,
+                amount: amount.parse().map_err(de::Error::custom)?,
+                original_price: None,
+            });
+        }
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str, max_orders: usize) -> Result<Vec<ExchangeOrder>, serde_json::Error> {
+        let seed = BookSideSeed {
+            exchange: Exchange::BINANCE,
+            max_orders,
+        };
+        seed.deserialize(&mut serde_json::Deserializer::from_str(json))
+    }
+
+    #[test]
+    fn test_keeps_only_top_levels() {
+        let orders = parse(r#"[["1.5","2"],["1.4","3"],["1.3","4"]]"#, 2).unwrap();
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].price, 1.5);
+        assert_eq!(orders[1].amount, 3.0);
+    }
+
+    #[test]
+    fn test_shorter_than_max() {
+        assert_eq!(parse(r#"[["1.5","2"]]"#, 10).unwrap().len(), 1);
+        assert!(parse("[]", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_invalid_levels() {
+        assert!(parse(r#"[["abc","2"]]"#, 10).is_err());
+        // Levels past the cut-off are skipped but must still be valid JSON.
+        assert!(parse(r#"[["1.5","2"],["1.4""#, 1).is_err());
+    }
+}
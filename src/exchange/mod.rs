@@ -2,28 +2,59 @@ use async_trait::async_trait;
 use futures_util::stream::Stream;
 use serde_json::Error as SerdeError;
 use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 pub mod binance;
 pub mod bitstamp;
-use binance::BinanceWebSocket;
-use bitstamp::BitstampWebSocket;
+pub mod connection;
+pub mod conversion;
+#[cfg(test)]
+pub(crate) mod fake_feed;
+mod levels;
+pub mod registry;
+pub mod sequence;
+pub mod synthetic;
+pub mod ws_exchange;
+pub use registry::{
+    instantiate_exchange_websocket, instantiate_trade_websocket, register_exchange,
+    register_trade_feed, ExchangeParams, TradeFeedParams,
+};
 
-const BINANCE_STR: &str = "Binance";
-const BITSTAMP_STR: &str = "Bitstamp";
+/// Identifies a venue by name. Any venue can define its own, e.g.
+/// `const MY_VENUE: Exchange = Exchange::new("MyVenue")`; parsing a name
+/// only succeeds for venues in the registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Exchange(&'static str);
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum Exchange {
-    Binance,
-    Bitstamp,
+impl Exchange {
+    pub const BINANCE: Exchange = Exchange::new("Binance");
+    pub const BITSTAMP: Exchange = Exchange::new("Bitstamp");
+
+    pub const fn new(name: &'static str) -> Self {
+        Exchange(name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
 }
 
 impl fmt::Display for Exchange {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Exchange::Binance => write!(f, "{}", BINANCE_STR),
-            Exchange::Bitstamp => write!(f, "{}", BITSTAMP_STR),
-        }
+        f.write_str(self.0)
+    }
+}
+
+impl FromStr for Exchange {
+    type Err = ExchangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        registry::registered_exchanges()
+            .into_iter()
+            .find(|exchange| exchange.name() == s)
+            .ok_or_else(|| ExchangeError::Unsupported(s.to_string()))
     }
 }
 
@@ -36,43 +67,118 @@ impl<T> ExchangeStream for T where
 {
 }
 
-pub fn instantiate_exchange_websocket(
-    exchange: &str,
-    trading_pair: &str,
-    max_orders: usize,
-) -> Result<Box<dyn ExchangeStream>, ExchangeError> {
-    match exchange {
-        BINANCE_STR => Ok(Box::new(BinanceWebSocket::new(trading_pair, max_orders))),
-        BITSTAMP_STR => Ok(Box::new(BitstampWebSocket::new(trading_pair, max_orders))),
-        _ => Err(ExchangeError::Unsupported(exchange.to_string())),
-    }
+pub trait TradeStream:
+    Stream<Item = Result<Trade, ExchangeError>> + Unpin + ExchangeWebSocket
+{
+}
+impl<T> TradeStream for T where
+    T: Stream<Item = Result<Trade, ExchangeError>> + Unpin + ExchangeWebSocket
+{
 }
 
 #[derive(Error, Debug)]
 pub enum ExchangeError {
+    /// Boxed: the tungstenite error is large enough to bloat every
+    /// `Result` on the parsing path.
     #[error("WebSocket read error")]
-    WebSocketError(#[from] tokio_tungstenite::tungstenite::Error),
+    WebSocketError(Box<tokio_tungstenite::tungstenite::Error>),
     #[error("Invalid message format")]
     ParsingError(#[from] SerdeError),
     #[error("Conversion to orderbook failed")]
     ConversionError,
+    #[error("{exchange} sent sequence {received} after {last}")]
+    OutOfSequence {
+        exchange: Exchange,
+        last: u64,
+        received: u64,
+    },
+    #[error("No data from {exchange} for {idle:?}")]
+    IdleTimeout { exchange: Exchange, idle: Duration },
+    #[error("{exchange} reported an error: {message}")]
+    Venue { exchange: Exchange, message: String },
     #[error("Unsupported Exchange: {0}")]
     Unsupported(String),
     #[error("Uknown error: {0}")]
     Unknown(String),
 }
 
+impl From<tokio_tungstenite::tungstenite::Error> for ExchangeError {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        ExchangeError::WebSocketError(Box::new(err))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Orderbook {
-    pub exchange_ts: u64,
+    /// The venue's book sequence number, e.g. Binance's `lastUpdateId`.
+    pub sequence: Option<u64>,
+    /// When the venue generated the update, in microseconds since the Unix
+    /// epoch, e.g. Bitstamp's `microtimestamp`.
+    pub event_time_us: Option<u64>,
+    /// When the update was read off the socket, in microseconds since the
+    /// Unix epoch. Stamped by the exchange stream rather than the parser.
+    pub received_time_us: u64,
+    /// Set by the exchange stream when the sequence skipped further ahead
+    /// than the venue allows.
+    pub sequence_gap: Option<u64>,
+    /// The venue's last book sent again unchanged, e.g. converted at a new
+    /// reference rate, rather than a new message from the venue.
+    pub repeated: bool,
     pub bids: Vec<ExchangeOrder>,
     pub asks: Vec<ExchangeOrder>,
 }
 
-#[derive(Debug, Clone)]
+/// The side that took liquidity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+/// A print on one venue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trade {
+    pub exchange: Exchange,
+    pub price: f64,
+    pub amount: f64,
+    pub aggressor: TradeSide,
+    /// The venue's trade id, e.g. Binance's `t`, or `a` for aggregated
+    /// trades.
+    pub trade_id: u64,
+    /// When the venue matched the trade, in microseconds since the Unix
+    /// epoch.
+    pub event_time_us: u64,
+    /// When the trade was read off the socket. Stamped by the exchange
+    /// stream rather than the parser.
+    pub received_time_us: u64,
+}
+
+/// The current wall-clock time in microseconds since the Unix epoch.
+pub fn unix_time_us() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_micros() as u64)
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct ExchangeOrder {
     pub exchange: Exchange,
     pub price: f64,
     pub amount: f64,
+    /// The venue's own price when `price` was converted into the common
+    /// quote currency.
+    pub original_price: Option<f64>,
+}
+
+impl ExchangeOrder {
+    pub fn new(exchange: Exchange, price: f64, amount: f64) -> Self {
+        Self {
+            exchange,
+            price,
+            amount,
+            original_price: None,
+        }
+    }
 }
 
 #[async_trait]
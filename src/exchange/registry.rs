@@ -0,0 +1,199 @@
+use crate::config::HeartbeatConfig;
+use crate::exchange::binance::{BinanceProtocol, BinanceTradeProtocol};
+use crate::exchange::bitstamp::{BitstampProtocol, BitstampTradeProtocol};
+use crate::exchange::ws_exchange::WsExchange;
+use crate::exchange::{Exchange, ExchangeError, ExchangeStream, TradeStream};
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// What a factory needs to build a venue feed.
+pub struct ExchangeParams<'a> {
+    pub trading_pair: &'a str,
+    pub max_orders: usize,
+    pub heartbeat: &'a HeartbeatConfig,
+}
+
+type ExchangeFactory =
+    Arc<dyn Fn(&ExchangeParams) -> Result<Box<dyn ExchangeStream>, ExchangeError> + Send + Sync>;
+
+/// Process-wide factories keyed by venue, seeded with the built-in venues.
+static REGISTRY: LazyLock<RwLock<Vec<(Exchange, ExchangeFactory)>>> = LazyLock::new(|| {
+    let builtins: Vec<(Exchange, ExchangeFactory)> = vec![
+        (
+            Exchange::BINANCE,
+            Arc::new(|params: &ExchangeParams| {
+                Ok(Box::new(
+                    WsExchange::new(BinanceProtocol::new(params.trading_pair, params.max_orders))
+                        .with_heartbeat(params.heartbeat),
+                ) as Box<dyn ExchangeStream>)
+            }),
+        ),
+        (
+            Exchange::BITSTAMP,
+            Arc::new(|params: &ExchangeParams| {
+                Ok(Box::new(
+                    WsExchange::new(BitstampProtocol::new(
+                        params.trading_pair,
+                        params.max_orders,
+                    ))
+                    .with_heartbeat(params.heartbeat),
+                ) as Box<dyn ExchangeStream>)
+            }),
+        ),
+    ];
+    RwLock::new(builtins)
+});
+
+/// What a factory needs to build a venue's trade feed.
+pub struct TradeFeedParams<'a> {
+    pub trading_pair: &'a str,
+    /// Ask for the venue's aggregated trades where it has them, e.g.
+    /// Binance's `aggTrade`.
+    pub aggregated: bool,
+    pub heartbeat: &'a HeartbeatConfig,
+}
+
+type TradeFactory =
+    Arc<dyn Fn(&TradeFeedParams) -> Result<Box<dyn TradeStream>, ExchangeError> + Send + Sync>;
+
+static TRADE_REGISTRY: LazyLock<RwLock<Vec<(Exchange, TradeFactory)>>> = LazyLock::new(|| {
+    let builtins: Vec<(Exchange, TradeFactory)> = vec![
+        (
+            Exchange::BINANCE,
+            Arc::new(|params: &TradeFeedParams| {
+                Ok(Box::new(
+                    WsExchange::new(BinanceTradeProtocol::new(
+                        params.trading_pair,
+                        params.aggregated,
+                    ))
+                    .with_heartbeat(params.heartbeat),
+                ) as Box<dyn TradeStream>)
+            }),
+        ),
+        (
+            Exchange::BITSTAMP,
+            Arc::new(|params: &TradeFeedParams| {
+                Ok(Box::new(
+                    WsExchange::new(BitstampTradeProtocol::new(params.trading_pair))
+                        .with_heartbeat(params.heartbeat),
+                ) as Box<dyn TradeStream>)
+            }),
+        ),
+    ];
+    RwLock::new(builtins)
+});
+
+/// Registers a venue so it can be named in the config, replacing any
+/// factory already registered for it. Call at startup, before the
+/// processor is built.
+pub fn register_exchange<F>(exchange: Exchange, factory: F)
+where
+    F: Fn(&ExchangeParams) -> Result<Box<dyn ExchangeStream>, ExchangeError>
+        + Send
+        + Sync
+        + 'static,
+{
+    let mut registry = REGISTRY.write().expect("exchange registry lock poisoned");
+    registry.retain(|(registered, _)| *registered != exchange);
+    registry.push((exchange, Arc::new(factory)));
+}
+
+/// Registers a venue's trade feed, replacing any factory already
+/// registered for it.
+pub fn register_trade_feed<F>(exchange: Exchange, factory: F)
+where
+    F: Fn(&TradeFeedParams) -> Result<Box<dyn TradeStream>, ExchangeError> + Send + Sync + 'static,
+{
+    let mut registry = TRADE_REGISTRY
+        .write()
+        .expect("trade registry lock poisoned");
+    registry.retain(|(registered, _)| *registered != exchange);
+    registry.push((exchange, Arc::new(factory)));
+}
+
+/// Venues with a book feed, a trade feed, or both.
+pub fn registered_exchanges() -> Vec<Exchange> {
+    let mut exchanges: Vec<Exchange> = {
+        let registry = REGISTRY.read().expect("exchange registry lock poisoned");
+        registry.iter().map(|(exchange, _)| *exchange).collect()
+    };
+    let trades = TRADE_REGISTRY.read().expect("trade registry lock poisoned");
+    for (exchange, _) in trades.iter() {
+        if !exchanges.contains(exchange) {
+            exchanges.push(*exchange);
+        }
+    }
+    exchanges
+}
+
+pub fn instantiate_exchange_websocket(
+    exchange: &str,
+    trading_pair: &str,
+    max_orders: usize,
+    heartbeat: &HeartbeatConfig,
+) -> Result<Box<dyn ExchangeStream>, ExchangeError> {
+    // Released before building, so a factory can build other venues.
+    let factory = {
+        let registry = REGISTRY.read().expect("exchange registry lock poisoned");
+        let (_, factory) = registry
+            .iter()
+            .find(|(registered, _)| registered.name() == exchange)
+            .ok_or_else(|| ExchangeError::Unsupported(exchange.to_string()))?;
+        factory.clone()
+    };
+    factory(&ExchangeParams {
+        trading_pair,
+        max_orders,
+        heartbeat,
+    })
+}
+
+pub fn instantiate_trade_websocket(
+    exchange: &str,
+    trading_pair: &str,
+    aggregated: bool,
+    heartbeat: &HeartbeatConfig,
+) -> Result<Box<dyn TradeStream>, ExchangeError> {
+    let factory = {
+        let registry = TRADE_REGISTRY.read().expect("trade registry lock poisoned");
+        let (_, factory) = registry
+            .iter()
+            .find(|(registered, _)| registered.name() == exchange)
+            .ok_or_else(|| ExchangeError::Unsupported(exchange.to_string()))?;
+        factory.clone()
+    };
+    factory(&TradeFeedParams {
+        trading_pair,
+        aggregated,
+        heartbeat,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::fake_feed::FakeFeed;
+    use crate::exchange::Orderbook;
+    use std::str::FromStr;
+
+    const OTC: Exchange = Exchange::new("TestOtc");
+
+    #[test]
+    fn test_registers_custom_exchange() {
+        assert!(Exchange::from_str("TestOtc").is_err());
+        assert!(matches!(
+            instantiate_exchange_websocket("TestOtc", "ethbtc", 10, &HeartbeatConfig::default()),
+            Err(ExchangeError::Unsupported(_))
+        ));
+
+        register_exchange(OTC, |_| {
+            Ok(Box::new(FakeFeed::<Orderbook>::open(OTC, vec![])))
+        });
+
+        assert_eq!(Exchange::from_str("TestOtc").unwrap(), OTC);
+        assert_eq!(Exchange::from_str("Binance").unwrap(), Exchange::BINANCE);
+        let feed =
+            instantiate_exchange_websocket("TestOtc", "ethbtc", 10, &HeartbeatConfig::default())
+                .unwrap();
+        assert_eq!(feed.get_exchange(), OTC);
+    }
+}
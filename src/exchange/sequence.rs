@@ -0,0 +1,97 @@
+use crate::exchange::{Exchange, ExchangeError};
+
+/// What [`SequenceTracker::check`] made of an id.
+#[derive(Debug, PartialEq)]
+pub enum Sequenced {
+    /// Moved forward; `gap` is set when it skipped past `max_gap`.
+    Next { gap: Option<u64> },
+    /// The last id again, as snapshot feeds resend while the book is quiet.
+    Duplicate,
+}
+
+/// Tracks the last ordering id a venue sent, e.g. Binance's `lastUpdateId`
+/// or Bitstamp's `microtimestamp`. It is deliberately kept across
+/// reconnects so a stale book replayed by a new connection is caught.
+pub struct SequenceTracker {
+    exchange: Exchange,
+    last: Option<u64>,
+    max_gap: Option<u64>,
+}
+
+impl SequenceTracker {
+    pub fn new(exchange: Exchange) -> Self {
+        Self {
+            exchange,
+            last: None,
+            max_gap: None,
+        }
+    }
+
+    /// Flags ids that skip ahead by more than `max_gap`. Unset by default,
+    /// since snapshot feeds legitimately skip ids between messages.
+    pub fn with_max_gap(mut self, max_gap: u64) -> Self {
+        self.max_gap = Some(max_gap);
+        self
+    }
+
+    /// Accepts `id` if it moved forward, noting how far it skipped past
+    /// `max_gap`, if at all. An earlier id is rejected and not recorded.
+    pub fn check(&mut self, id: u64) -> Result<Sequenced, ExchangeError> {
+        let gap = match self.last {
+            Some(last) if id == last => return Ok(Sequenced::Duplicate),
+            Some(last) if id < last => {
+                return Err(ExchangeError::OutOfSequence {
+                    exchange: self.exchange,
+                    last,
+                    received: id,
+                })
+            }
+            Some(last) => Some(id - last).filter(|gap| self.max_gap.is_some_and(|max| *gap > max)),
+            None => None,
+        };
+        self.last = Some(id);
+        Ok(Sequenced::Next { gap })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_regressions() {
+        let mut tracker = SequenceTracker::new(Exchange::BINANCE);
+        assert_eq!(tracker.check(10).unwrap(), Sequenced::Next { gap: None });
+        assert_eq!(tracker.check(12).unwrap(), Sequenced::Next { gap: None });
+        assert!(matches!(
+            tracker.check(5),
+            Err(ExchangeError::OutOfSequence {
+                last: 12,
+                received: 5,
+                ..
+            })
+        ));
+        // Rejected ids don't move the tracker back.
+        assert_eq!(tracker.check(13).unwrap(), Sequenced::Next { gap: None });
+    }
+
+    #[test]
+    fn test_drops_repeated_ids_as_duplicates() {
+        let mut tracker = SequenceTracker::new(Exchange::BINANCE);
+        assert_eq!(tracker.check(10).unwrap(), Sequenced::Next { gap: None });
+        assert_eq!(tracker.check(10).unwrap(), Sequenced::Duplicate);
+        assert_eq!(tracker.check(10).unwrap(), Sequenced::Duplicate);
+        assert_eq!(tracker.check(11).unwrap(), Sequenced::Next { gap: None });
+    }
+
+    #[test]
+    fn test_flags_gaps_over_max() {
+        let mut tracker = SequenceTracker::new(Exchange::BITSTAMP).with_max_gap(100);
+        tracker.check(1_000).unwrap();
+        assert_eq!(tracker.check(1_100).unwrap(), Sequenced::Next { gap: None });
+        assert_eq!(
+            tracker.check(1_500).unwrap(),
+            Sequenced::Next { gap: Some(400) }
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use crate::config::SyntheticConfig;
+use crate::exchange::{
+    instantiate_exchange_websocket, register_exchange, Exchange, ExchangeError, ExchangeOrder,
+    ExchangeStream, ExchangeWebSocket, Orderbook,
+};
+use async_trait::async_trait;
+use futures_util::stream::Stream;
+use futures_util::StreamExt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// An implied book for a cross pair from two legs sharing a quote
+/// currency, e.g. ETH/BTC from ETH/USDT (`base`) and BTC/USDT (`quote`).
+/// Emits a new book whenever either leg updates once both have arrived.
+pub struct SyntheticExchange {
+    exchange: Exchange,
+    base: Box<dyn ExchangeStream>,
+    quote: Box<dyn ExchangeStream>,
+    base_book: Option<Orderbook>,
+    quote_book: Option<Orderbook>,
+    max_orders: usize,
+}
+
+impl SyntheticExchange {
+    pub fn new(
+        exchange: Exchange,
+        base: Box<dyn ExchangeStream>,
+        quote: Box<dyn ExchangeStream>,
+        max_orders: usize,
+    ) -> Self {
+        Self {
+            exchange,
+            base,
+            quote,
+            base_book: None,
+            quote_book: None,
+            max_orders,
+        }
+    }
+
+    /// Timestamps come from the leg update that triggered the book.
+    fn implied(&self, event_time_us: Option<u64>, received_time_us: u64) -> Option<Orderbook> {
+        let (base, quote) = (self.base_book.as_ref()?, self.quote_book.as_ref()?);
+        Some(Orderbook {
+            sequence: None,
+            event_time_us,
+            received_time_us,
+            sequence_gap: None,
+            repeated: false,
+            // Selling the cross sells base for the common quote, then buys
+            // the quote leg with it; buying does the reverse.
+            bids: implied_side(self.exchange, &base.bids, &quote.asks, self.max_orders),
+            asks: implied_side(self.exchange, &base.asks, &quote.bids, self.max_orders),
+        })
+    }
+}
+
+/// Walks both legs' levels best first, matching them by notional in the
+/// common currency. Each step fills as much as the thinner of the two
+/// current levels allows, at the ratio of their prices.
+pub fn implied_side(
+    exchange: Exchange,
+    base: &[ExchangeOrder],
+    quote: &[ExchangeOrder],
+    max_orders: usize,
+) -> Vec<ExchangeOrder> {
+    let mut levels = Vec::new();
+    let (mut base_levels, mut quote_levels) = (base.iter(), quote.iter());
+    let (mut base_level, mut quote_level) = (base_levels.next(), quote_levels.next());
+    let (mut base_notional, mut quote_notional) = (
+        base_level.map_or(0.0, |l| l.price * l.amount),
+        quote_level.map_or(0.0, |l| l.price * l.amount),
+    );
+    while let (Some(b), Some(q)) = (base_level, quote_level) {
+        if levels.len() == max_orders || b.price <= 0.0 || q.price <= 0.0 {
+            break;
+        }
+        let notional = base_notional.min(quote_notional);
+        if notional > 0.0 {
+            levels.push(ExchangeOrder {
+                exchange,
+                price: b.price / q.price,
+                amount: notional / b.price,
+                original_price: None,
+            });
+        }
+        base_notional -= notional;
+        quote_notional -= notional;
+        if base_notional <= 0.0 {
+            base_level = base_levels.next();
+            base_notional = base_level.map_or(0.0, |l| l.price * l.amount);
+        }
+        if quote_notional <= 0.0 {
+            quote_level = quote_levels.next();
+            quote_notional = quote_level.map_or(0.0, |l| l.price * l.amount);
+        }
+    }
+    levels
+}
+
+#[async_trait]
+impl ExchangeWebSocket for SyntheticExchange {
+    fn get_exchange(&self) -> Exchange {
+        self.exchange
+    }
+
+    async fn initialise(&mut self) -> Result<(), ExchangeError> {
+        self.base.initialise().await?;
+        self.quote.initialise().await
+    }
+}
+
+impl Stream for SyntheticExchange {
+    type Item = Result<Orderbook, ExchangeError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let mut trigger = None;
+            for leg in 0..2 {
+                let stream = if leg == 0 {
+                    &mut this.base
+                } else {
+                    &mut this.quote
+                };
+                match stream.poll_next_unpin(cx) {
+                    Poll::Ready(Some(Ok(book))) => {
+                        trigger = Some((book.event_time_us, book.received_time_us));
+                        if leg == 0 {
+                            this.base_book = Some(book);
+                        } else {
+                            this.quote_book = Some(book);
+                        }
+                    }
+                    Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                    // The cross is meaningless without either leg.
+                    Poll::Ready(None) => return Poll::Ready(None),
+                    Poll::Pending => {}
+                }
+            }
+            let Some((event_time_us, received_time_us)) = trigger else {
+                return Poll::Pending;
+            };
+            if let Some(book) = this.implied(event_time_us, received_time_us) {
+                return Poll::Ready(Some(Ok(book)));
+            }
+        }
+    }
+}
+
+/// Registers a configured synthetic as a venue named `config.name`, built
+/// from two feeds on `config.exchange`, replacing any earlier registration
+/// under that name. Returns the venue to add to the processor.
+pub fn register_synthetic(config: &SyntheticConfig) -> Exchange {
+    // Names live for the process, like the built-in venues' names, so each
+    // is only leaked the first time it is registered.
+    let exchange = config
+        .name
+        .parse()
+        .unwrap_or_else(|_| Exchange::new(Box::leak(config.name.clone().into_boxed_str())));
+    let config = config.clone();
+    register_exchange(exchange, move |params| {
+        let leg = |trading_pair: &str| {
+            instantiate_exchange_websocket(
+                &config.exchange,
+                trading_pair,
+                params.max_orders,
+                params.heartbeat,
+            )
+        };
+        Ok(Box::new(SyntheticExchange::new(
+            exchange,
+            leg(&config.base_leg)?,
+            leg(&config.quote_leg)?,
+            params.max_orders,
+        )))
+    });
+    exchange
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::fake_feed::FakeFeed;
+
+    const SYNTHETIC: Exchange = Exchange::new("TestSynthetic");
+
+    fn order(price: f64, amount: f64) -> ExchangeOrder {
+        ExchangeOrder::new(Exchange::BINANCE, price, amount)
+    }
+
+    #[test]
+    fn test_implied_side_matches_legs_by_notional() {
+        // Sell 1 + 2 ETH at 2000 and 1990 USDT and buy BTC with the
+        // proceeds: the first BTC level takes 3000 USDT, so the second ETH
+        // level spills into the second BTC level.
+        let eth_bids = [order(2000.0, 1.0), order(1990.0, 2.0)];
+        let btc_asks = [order(40_000.0, 0.075), order(40_100.0, 1.0)];
+        let bids = implied_side(SYNTHETIC, &eth_bids, &btc_asks, 10);
+
+        let expected = [
+            (2000.0 / 40_000.0, 1.0),
+            (1990.0 / 40_000.0, 1000.0 / 1990.0),
+            (1990.0 / 40_100.0, 2980.0 / 1990.0),
+        ];
+        assert_eq!(bids.len(), expected.len());
+        for (level, (price, amount)) in bids.iter().zip(expected) {
+            assert_eq!(level.exchange, SYNTHETIC);
+            assert!((level.price - price).abs() < 1e-12);
+            assert!((level.amount - amount).abs() < 1e-9);
+        }
+
+        assert_eq!(implied_side(SYNTHETIC, &eth_bids, &btc_asks, 1).len(), 1);
+        assert!(implied_side(SYNTHETIC, &eth_bids, &[], 10).is_empty());
+    }
+
+    fn book(bid: f64, ask: f64) -> Orderbook {
+        Orderbook {
+            bids: vec![order(bid, 10.0)],
+            asks: vec![order(ask, 10.0)],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_reregistering_reuses_the_name() {
+        let config = SyntheticConfig {
+            name: "TestReregistered".to_string(),
+            exchange: "Binance".to_string(),
+            base_leg: "ethusdt".to_string(),
+            quote_leg: "btcusdt".to_string(),
+        };
+        let first = register_synthetic(&config);
+        let second = register_synthetic(&config);
+        assert_eq!(first, second);
+        assert!(std::ptr::eq(first.name(), second.name()));
+    }
+
+    #[tokio::test]
+    async fn test_emits_once_both_legs_arrive() {
+        let base = FakeFeed::new(
+            Exchange::BINANCE,
+            vec![Ok(book(2000.0, 2001.0)), Ok(book(2002.0, 2003.0))],
+        );
+        let quote = FakeFeed::open(Exchange::BINANCE, vec![Ok(book(40_000.0, 40_010.0))]);
+        let mut synthetic = SyntheticExchange::new(SYNTHETIC, Box::new(base), Box::new(quote), 10);
+
+        let first = synthetic.next().await.unwrap().unwrap();
+        assert_eq!(first.bids[0].price, 2000.0 / 40_010.0);
+        assert_eq!(first.asks[0].price, 2001.0 / 40_000.0);
+        let second = synthetic.next().await.unwrap().unwrap();
+        assert_eq!(second.bids[0].price, 2002.0 / 40_010.0);
+        assert!(synthetic.next().await.is_none());
+    }
+}
@@ -0,0 +1,349 @@
+use crate::config::HeartbeatConfig;
+use crate::exchange::connection::WsConnection;
+use crate::exchange::sequence::{SequenceTracker, Sequenced};
+use crate::exchange::{unix_time_us, Exchange, ExchangeError, ExchangeWebSocket, Orderbook, Trade};
+use async_trait::async_trait;
+use futures_util::stream::Stream;
+use futures_util::StreamExt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, warn};
+
+/// What a venue payload turned out to be.
+#[derive(Debug)]
+pub enum VenueMessage<T> {
+    /// A book, trade or whatever else the feed carries.
+    Data(T),
+    /// A reply to our heartbeat; only proves the connection is alive.
+    Heartbeat,
+    /// Subscription acks and other replies that need no action.
+    Control,
+    /// The venue asked us to reconnect, e.g. before maintenance.
+    Reconnect,
+    /// The venue reported an error.
+    Error(String),
+}
+
+/// Data a venue feed yields, stamped by `WsExchange` as it is read.
+pub trait FeedItem: Send + 'static {
+    fn stamp(&mut self, received_time_us: u64, sequence_gap: Option<u64>);
+}
+
+impl FeedItem for Orderbook {
+    fn stamp(&mut self, received_time_us: u64, sequence_gap: Option<u64>) {
+        self.received_time_us = received_time_us;
+        self.sequence_gap = sequence_gap;
+    }
+}
+
+/// Trade ids may skip, e.g. Bitstamp's are shared across pairs, so gaps
+/// are only logged.
+impl FeedItem for Trade {
+    fn stamp(&mut self, received_time_us: u64, _sequence_gap: Option<u64>) {
+        self.received_time_us = received_time_us;
+    }
+}
+
+/// Everything venue specific about a feed. Connection handling,
+/// heartbeats, reconnection, sequence checks and receive timestamps are
+/// shared by `WsExchange`.
+pub trait VenueProtocol: Send + Unpin + 'static {
+    type Item: FeedItem;
+
+    fn exchange(&self) -> Exchange;
+
+    fn url(&self) -> String;
+
+    /// Sent after every (re)connect.
+    fn subscription(&self) -> Option<String> {
+        None
+    }
+
+    /// Reconnect when nothing has been read for this long.
+    fn idle_timeout(&self) -> Duration;
+
+    /// How often to send which heartbeat, for venues that expect one.
+    fn heartbeat(&self) -> Option<(Duration, fn() -> Message)> {
+        None
+    }
+
+    fn classify(&self, text: &str) -> Result<VenueMessage<Self::Item>, ExchangeError>;
+
+    /// The id items must strictly increase in: a sequence number where the
+    /// venue has one, otherwise its event time.
+    fn sequence_id(&self, item: &Self::Item) -> Option<u64>;
+
+    /// How far `sequence_id` may skip before it counts as a gap. `None`
+    /// for feeds that legitimately skip ids, e.g. periodic snapshots.
+    fn max_sequence_gap(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// A venue feed over a WebSocket, driven by its `VenueProtocol`.
+pub struct WsExchange<P> {
+    protocol: P,
+    connection: WsConnection,
+    sequence: SequenceTracker,
+}
+
+impl<P: VenueProtocol> WsExchange<P> {
+    pub fn new(protocol: P) -> Self {
+        let mut connection =
+            WsConnection::new(protocol.exchange(), protocol.url(), protocol.idle_timeout());
+        if let Some(subscription) = protocol.subscription() {
+            connection = connection.with_subscription(subscription);
+        }
+        if let Some((interval, heartbeat)) = protocol.heartbeat() {
+            connection = connection.with_heartbeat(interval, heartbeat);
+        }
+        let mut sequence = SequenceTracker::new(protocol.exchange());
+        if let Some(max_gap) = protocol.max_sequence_gap() {
+            sequence = sequence.with_max_gap(max_gap);
+        }
+        Self {
+            sequence,
+            protocol,
+            connection,
+        }
+    }
+
+    /// Overrides the venue's heartbeat and sequence gap defaults with any
+    /// values set.
+    pub fn with_heartbeat(mut self, config: &HeartbeatConfig) -> Self {
+        self.connection = self.connection.with_config(config);
+        if let Some(max_gap) = config.max_sequence_gap {
+            self.sequence = self.sequence.with_max_gap(max_gap);
+        }
+        self
+    }
+
+    /// Rejects items older than the last one accepted, e.g. replayed after
+    /// a reconnect, drops resends of the last one and stamps the rest.
+    fn accept(
+        &mut self,
+        mut item: P::Item,
+        received_time_us: u64,
+    ) -> Result<Option<P::Item>, ExchangeError> {
+        let sequence_gap = match self.protocol.sequence_id(&item) {
+            Some(id) => match self.sequence.check(id)? {
+                Sequenced::Next { gap } => gap,
+                Sequenced::Duplicate => return Ok(None),
+            },
+            None => None,
+        };
+        if let Some(gap) = sequence_gap {
+            warn!(
+                "{} sequence skipped ahead by {}",
+                self.protocol.exchange(),
+                gap
+            );
+        }
+        item.stamp(received_time_us, sequence_gap);
+        Ok(Some(item))
+    }
+}
+
+#[async_trait]
+impl<P: VenueProtocol> ExchangeWebSocket for WsExchange<P> {
+    fn get_exchange(&self) -> Exchange {
+        self.protocol.exchange()
+    }
+
+    async fn initialise(&mut self) -> Result<(), ExchangeError> {
+        self.connection.connect().await
+    }
+}
+
+impl<P: VenueProtocol> Stream for WsExchange<P> {
+    type Item = Result<P::Item, ExchangeError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            let text = match this.connection.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(text))) => text,
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            let received_time_us = unix_time_us();
+            let item = match this.protocol.classify(&text) {
+                Ok(VenueMessage::Data(item)) => item,
+                Ok(VenueMessage::Heartbeat) | Ok(VenueMessage::Control) => {
+                    debug!(exchange = %this.protocol.exchange(), message = %text, "Venue control message");
+                    continue;
+                }
+                Ok(VenueMessage::Reconnect) => {
+                    this.connection.reconnect_now();
+                    continue;
+                }
+                Ok(VenueMessage::Error(message)) => {
+                    return Poll::Ready(Some(Err(ExchangeError::Venue {
+                        exchange: this.protocol.exchange(),
+                        message,
+                    })))
+                }
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            };
+            match this.accept(item, received_time_us) {
+                Ok(Some(item)) => return Poll::Ready(Some(Ok(item))),
+                Ok(None) => {
+                    debug!(exchange = %this.protocol.exchange(), "Dropping repeated sequence id");
+                    continue;
+                }
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::ExchangeOrder;
+    use futures_util::SinkExt;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    /// Payloads are `book <id> <price>`, `hb`, `ack`, `reconnect` or
+    /// `error <message>`.
+    struct TestProtocol {
+        url: String,
+    }
+
+    impl VenueProtocol for TestProtocol {
+        type Item = Orderbook;
+
+        fn exchange(&self) -> Exchange {
+            Exchange::BINANCE
+        }
+
+        fn url(&self) -> String {
+            self.url.clone()
+        }
+
+        fn subscription(&self) -> Option<String> {
+            Some("subscribe".to_string())
+        }
+
+        fn idle_timeout(&self) -> Duration {
+            Duration::from_secs(5)
+        }
+
+        fn classify(&self, text: &str) -> Result<VenueMessage<Orderbook>, ExchangeError> {
+            let mut words = text.split(' ');
+            Ok(match words.next() {
+                Some("book") => {
+                    let mut number = || words.next().unwrap().parse::<u64>().unwrap();
+                    let (sequence, price) = (number(), number());
+                    VenueMessage::Data(Orderbook {
+                        sequence: Some(sequence),
+                        bids: vec![ExchangeOrder {
+                            exchange: Exchange::BINANCE,
+                            price: price as f64,
+                            amount: 1.0,
+                            original_price: None,
+                        }],
+                        ..Default::default()
+                    })
+                }
+                Some("hb") => VenueMessage::Heartbeat,
+                Some("ack") => VenueMessage::Control,
+                Some("reconnect") => VenueMessage::Reconnect,
+                Some("error") => VenueMessage::Error(words.collect::<Vec<_>>().join(" ")),
+                _ => return Err(ExchangeError::ConversionError),
+            })
+        }
+
+        fn sequence_id(&self, orderbook: &Orderbook) -> Option<u64> {
+            orderbook.sequence
+        }
+    }
+
+    #[tokio::test]
+    async fn test_routes_venue_messages() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut first = accept_async(stream).await.unwrap();
+            first.next().await.unwrap().unwrap();
+            for text in [
+                "ack",
+                "book 1 100",
+                "hb",
+                "book 1 100",
+                "book 0 99",
+                "error rate limited",
+                "reconnect",
+            ] {
+                first.send(Message::Text(text.into())).await.unwrap();
+            }
+
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut second = accept_async(stream).await.unwrap();
+            let subscription = second.next().await.unwrap().unwrap();
+            second
+                .send(Message::Text("book 2 101".into()))
+                .await
+                .unwrap();
+            (subscription, first, second)
+        });
+
+        let mut exchange = WsExchange::new(TestProtocol { url });
+        exchange.initialise().await.unwrap();
+
+        let first = exchange.next().await.unwrap().unwrap();
+        assert_eq!(first.sequence, Some(1));
+        assert_eq!(first.bids[0].price, 100.0);
+        assert!(first.received_time_us > 0);
+        // A resent sequence id is dropped and an earlier one is rejected.
+        assert!(matches!(
+            exchange.next().await,
+            Some(Err(ExchangeError::OutOfSequence { .. }))
+        ));
+        assert!(matches!(
+            exchange.next().await,
+            Some(Err(ExchangeError::Venue { message, .. })) if message == "rate limited"
+        ));
+        // `reconnect` moves to a new, resubscribed connection.
+        let second = exchange.next().await.unwrap().unwrap();
+        assert_eq!(second.sequence, Some(2));
+
+        let (subscription, _, _) = server.await.unwrap();
+        assert_eq!(subscription, Message::Text("subscribe".into()));
+    }
+
+    #[tokio::test]
+    async fn test_flags_configured_sequence_gaps() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            ws.next().await.unwrap().unwrap();
+            for text in ["book 1 100", "book 6 100", "book 20 100"] {
+                ws.send(Message::Text(text.into())).await.unwrap();
+            }
+            ws
+        });
+
+        let mut exchange = WsExchange::new(TestProtocol { url }).with_heartbeat(&HeartbeatConfig {
+            max_sequence_gap: Some(5),
+            ..Default::default()
+        });
+        exchange.initialise().await.unwrap();
+
+        assert_eq!(exchange.next().await.unwrap().unwrap().sequence_gap, None);
+        assert_eq!(exchange.next().await.unwrap().unwrap().sequence_gap, None);
+        assert_eq!(
+            exchange.next().await.unwrap().unwrap().sequence_gap,
+            Some(14)
+        );
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,247 @@
+use crate::exchange::{Exchange, Orderbook};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Latency samples kept per venue by default.
+pub const DEFAULT_WINDOW: usize = 1000;
+
+/// Percentiles of receive time minus venue event time over the rolling
+/// window. Both clocks feed into these, so they include any skew.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencySummary {
+    pub samples: usize,
+    pub p50_us: i64,
+    pub p90_us: i64,
+    pub p99_us: i64,
+    pub max_us: i64,
+    /// Estimated offset of our clock from the venue's: the smallest latency
+    /// in the window, which still includes the minimum network delay. A
+    /// negative value means the venue's clock is ahead of ours.
+    pub clock_skew_us: i64,
+}
+
+impl LatencySummary {
+    fn from_samples(samples: &VecDeque<i64>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<i64> = samples.iter().copied().collect();
+        sorted.sort_unstable();
+        // Nearest-rank percentile.
+        let percentile = |p: f64| {
+            let rank = (p * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        Some(LatencySummary {
+            samples: sorted.len(),
+            p50_us: percentile(0.5),
+            p90_us: percentile(0.9),
+            p99_us: percentile(0.99),
+            max_us: sorted[sorted.len() - 1],
+            clock_skew_us: sorted[0],
+        })
+    }
+}
+
+/// Feed health for one venue. Sequence ids and event times are reported
+/// separately since venues publish one, the other or both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VenueFeedStats {
+    pub exchange: Exchange,
+    pub messages: u64,
+    pub last_sequence: Option<u64>,
+    pub last_event_time_us: Option<u64>,
+    pub last_received_time_us: u64,
+    /// Books rejected for going back on the venue's sequence.
+    pub out_of_order: u64,
+    /// Books whose sequence skipped further ahead than the venue allows.
+    pub sequence_gaps: u64,
+    /// `None` for venues whose payloads carry no event time.
+    pub latency: Option<LatencySummary>,
+}
+
+struct VenueWindow {
+    stats: VenueFeedStats,
+    latencies_us: VecDeque<i64>,
+}
+
+struct FeedStatsInner {
+    window: usize,
+    venues: Vec<VenueWindow>,
+}
+
+impl FeedStatsInner {
+    fn venue_mut(&mut self, exchange: &Exchange) -> &mut VenueWindow {
+        match self
+            .venues
+            .iter()
+            .position(|v| v.stats.exchange == *exchange)
+        {
+            Some(index) => &mut self.venues[index],
+            None => {
+                self.venues.push(VenueWindow {
+                    stats: VenueFeedStats {
+                        exchange: *exchange,
+                        messages: 0,
+                        last_sequence: None,
+                        last_event_time_us: None,
+                        last_received_time_us: 0,
+                        out_of_order: 0,
+                        sequence_gaps: 0,
+                        latency: None,
+                    },
+                    latencies_us: VecDeque::with_capacity(self.window),
+                });
+                self.venues.last_mut().expect("venue was just added")
+            }
+        }
+    }
+}
+
+/// Rolling per-venue feed statistics, recorded by the processor and read by
+/// the gRPC service. Cheap to clone.
+#[derive(Clone)]
+pub struct FeedStats {
+    inner: Arc<Mutex<FeedStatsInner>>,
+}
+
+impl Default for FeedStats {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+impl FeedStats {
+    pub fn new(window: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(FeedStatsInner {
+                window: window.max(1),
+                venues: Vec::new(),
+            })),
+        }
+    }
+
+    /// Records a book read from the venue; repeated books are skipped.
+    pub fn record(&self, exchange: &Exchange, orderbook: &Orderbook) {
+        if orderbook.repeated {
+            return;
+        }
+        let mut inner = self.inner.lock().expect("feed stats lock poisoned");
+        let window = inner.window;
+        let venue = inner.venue_mut(exchange);
+
+        let stats = &mut venue.stats;
+        stats.messages += 1;
+        stats.last_sequence = orderbook.sequence.or(stats.last_sequence);
+        stats.last_event_time_us = orderbook.event_time_us.or(stats.last_event_time_us);
+        stats.last_received_time_us = orderbook.received_time_us;
+        if orderbook.sequence_gap.is_some() {
+            stats.sequence_gaps += 1;
+        }
+
+        if let Some(event_time_us) = orderbook.event_time_us {
+            if venue.latencies_us.len() == window {
+                venue.latencies_us.pop_front();
+            }
+            venue
+                .latencies_us
+                .push_back(orderbook.received_time_us as i64 - event_time_us as i64);
+        }
+    }
+
+    /// Counts a book the venue's stream rejected as out of sequence.
+    pub fn record_out_of_order(&self, exchange: &Exchange) {
+        let mut inner = self.inner.lock().expect("feed stats lock poisoned");
+        inner.venue_mut(exchange).stats.out_of_order += 1;
+    }
+
+    pub fn venues(&self) -> Vec<VenueFeedStats> {
+        let inner = self.inner.lock().expect("feed stats lock poisoned");
+        inner
+            .venues
+            .iter()
+            .map(|venue| VenueFeedStats {
+                latency: LatencySummary::from_samples(&venue.latencies_us),
+                ..venue.stats.clone()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orderbook(sequence: Option<u64>, event_time_us: Option<u64>, received: u64) -> Orderbook {
+        Orderbook {
+            sequence,
+            event_time_us,
+            received_time_us: received,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_latency_percentiles_over_window() {
+        let stats = FeedStats::new(100);
+        // Latencies of 1..=200us; only the last 100 stay in the window.
+        for latency in 1..=200 {
+            stats.record(
+                &Exchange::BITSTAMP,
+                &orderbook(None, Some(1_000), 1_000 + latency),
+            );
+        }
+
+        let venues = stats.venues();
+        assert_eq!(venues.len(), 1);
+        assert_eq!(venues[0].messages, 200);
+        assert_eq!(venues[0].last_event_time_us, Some(1_000));
+        assert_eq!(venues[0].last_sequence, None);
+        let latency = venues[0].latency.as_ref().unwrap();
+        assert_eq!(latency.samples, 100);
+        assert_eq!(latency.p50_us, 150);
+        assert_eq!(latency.p90_us, 190);
+        assert_eq!(latency.p99_us, 199);
+        assert_eq!(latency.max_us, 200);
+        assert_eq!(latency.clock_skew_us, 101);
+    }
+
+    #[test]
+    fn test_sequence_only_venue_has_no_latency() {
+        let stats = FeedStats::default();
+        stats.record(&Exchange::BINANCE, &orderbook(Some(7), None, 5));
+        stats.record(&Exchange::BINANCE, &orderbook(Some(8), None, 6));
+
+        let venues = stats.venues();
+        assert_eq!(venues[0].last_sequence, Some(8));
+        assert_eq!(venues[0].last_received_time_us, 6);
+        assert_eq!(venues[0].latency, None);
+    }
+
+    #[test]
+    fn test_counts_sequence_problems() {
+        let stats = FeedStats::default();
+        stats.record(
+            &Exchange::BINANCE,
+            &Orderbook {
+                sequence_gap: Some(500),
+                ..orderbook(Some(1_000), None, 1)
+            },
+        );
+        stats.record_out_of_order(&Exchange::BINANCE);
+        stats.record_out_of_order(&Exchange::BINANCE);
+
+        let venues = stats.venues();
+        assert_eq!(venues[0].messages, 1);
+        assert_eq!(venues[0].sequence_gaps, 1);
+        assert_eq!(venues[0].out_of_order, 2);
+    }
+
+    #[test]
+    fn test_venue_clock_ahead_gives_negative_skew() {
+        let stats = FeedStats::default();
+        stats.record(&Exchange::BITSTAMP, &orderbook(None, Some(2_000), 1_500));
+        let latency = stats.venues()[0].latency.clone().unwrap();
+        assert_eq!(latency.clock_skew_us, -500);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::config::AuthConfig;
+use crate::subscription::Entitlements;
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::sync::Arc;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+use tracing::warn;
+
+const ANONYMOUS_CLIENT: &str = "anonymous";
+
+/// Attached to each request's extensions by [`Authenticator`].
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    pub client: String,
+    pub entitlements: Entitlements,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+    #[serde(flatten)]
+    entitlements: Entitlements,
+}
+
+struct JwtVerifier {
+    key: DecodingKey,
+    validation: Validation,
+}
+
+/// Validates `authorization: Bearer <token>` against the static tokens from
+/// config, falling back to HS256 JWTs signed with the configured secret.
+/// Without an auth config every request is let through unrestricted.
+#[derive(Clone)]
+pub struct Authenticator {
+    inner: Option<Arc<AuthenticatorInner>>,
+}
+
+struct AuthenticatorInner {
+    static_tokens: Vec<(String, AuthContext)>,
+    jwt: Option<JwtVerifier>,
+}
+
+impl Authenticator {
+    pub fn new(config: Option<&AuthConfig>) -> Self {
+        let inner = config.map(|config| {
+            let static_tokens = config
+                .tokens
+                .iter()
+                .map(|token| {
+                    let context = AuthContext {
+                        client: token.client.clone(),
+                        entitlements: token.entitlements.clone(),
+                    };
+                    (token.token.clone(), context)
+                })
+                .collect();
+
+            let jwt = config.jwt_hmac_secret.as_ref().map(|secret| {
+                let mut validation = Validation::new(Algorithm::HS256);
+                if let Some(issuer) = &config.jwt_issuer {
+                    validation.set_issuer(&[issuer]);
+                }
+                JwtVerifier {
+                    key: DecodingKey::from_secret(secret.as_bytes()),
+                    validation,
+                }
+            });
+
+            Arc::new(AuthenticatorInner { static_tokens, jwt })
+        });
+
+        Self { inner }
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn authenticate(&self, token: Option<&str>) -> Result<AuthContext, Status> {
+        let Some(inner) = &self.inner else {
+            return Ok(AuthContext {
+                client: ANONYMOUS_CLIENT.to_string(),
+                entitlements: Entitlements::default(),
+            });
+        };
+
+        let token = token.ok_or_else(|| Status::unauthenticated("Missing bearer token"))?;
+
+        // Every token is checked so the time taken doesn't reveal which
+        // one, or how much of it, matched.
+        let matched = inner
+            .static_tokens
+            .iter()
+            .fold(None, |matched, (candidate, context)| {
+                let equal = constant_time_eq(candidate.as_bytes(), token.as_bytes());
+                matched.or(equal.then_some(context))
+            });
+        if let Some(context) = matched {
+            return Ok(context.clone());
+        }
+
+        let jwt = inner
+            .jwt
+            .as_ref()
+            .ok_or_else(|| Status::unauthenticated("Invalid bearer token"))?;
+        let claims = decode::<Claims>(token, &jwt.key, &jwt.validation)
+            .map_err(|err| {
+                warn!("Rejected JWT: {}", err);
+                Status::unauthenticated("Invalid bearer token")
+            })?
+            .claims;
+
+        Ok(AuthContext {
+            client: claims.sub,
+            entitlements: claims.entitlements,
+        })
+    }
+}
+
+/// Compares every byte of the longer input whatever the contents, unlike
+/// `==` which stops at the first difference.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let len = a.len().max(b.len());
+    let diff = (0..len).fold(a.len() ^ b.len(), |diff, i| {
+        let (x, y) = (
+            a.get(i).copied().unwrap_or(0),
+            b.get(i).copied().unwrap_or(0),
+        );
+        diff | usize::from(x ^ y)
+    });
+    diff == 0
+}
+
+impl Interceptor for Authenticator {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        let context = self.authenticate(token)?;
+        request.extensions_mut().insert(context);
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StaticToken;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct TestClaims<'a> {
+        sub: &'a str,
+        exp: u64,
+        venues: Vec<&'a str>,
+        max_depth: usize,
+    }
+
+    fn auth_config() -> AuthConfig {
+        AuthConfig {
+            tokens: vec![StaticToken {
+                client: "dashboard".to_string(),
+                token: "static-token".to_string(),
+                entitlements: Entitlements {
+                    instruments: None,
+                    venues: Some(vec!["Binance".to_string()]),
+                    max_depth: Some(5),
+                },
+            }],
+            jwt_hmac_secret: Some("secret".to_string()),
+            jwt_issuer: None,
+        }
+    }
+
+    fn bearer(token: &str) -> Request<()> {
+        let mut request = Request::new(());
+        request.metadata_mut().insert(
+            "authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        request
+    }
+
+    #[test]
+    fn test_without_config_allows_anonymous() {
+        let mut authenticator = Authenticator::new(None);
+        let request = authenticator.call(Request::new(())).unwrap();
+        let context = request.extensions().get::<AuthContext>().unwrap();
+        assert_eq!(context.client, ANONYMOUS_CLIENT);
+        assert_eq!(context.entitlements, Entitlements::default());
+    }
+
+    #[test]
+    fn test_static_token() {
+        let mut authenticator = Authenticator::new(Some(&auth_config()));
+        let request = authenticator.call(bearer("static-token")).unwrap();
+        let context = request.extensions().get::<AuthContext>().unwrap();
+        assert_eq!(context.client, "dashboard");
+        assert_eq!(context.entitlements.max_depth, Some(5));
+    }
+
+    #[test]
+    fn test_missing_and_unknown_token() {
+        let mut authenticator = Authenticator::new(Some(&auth_config()));
+        let missing = authenticator.call(Request::new(())).unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::Unauthenticated);
+        for token in ["nope", "static-toke", "static-token2", ""] {
+            let unknown = authenticator.call(bearer(token)).unwrap_err();
+            assert_eq!(unknown.code(), tonic::Code::Unauthenticated);
+        }
+    }
+
+    #[test]
+    fn test_hmac_signed_jwt() {
+        let claims = TestClaims {
+            sub: "quoting-engine",
+            exp: u64::MAX / 2,
+            venues: vec!["Bitstamp"],
+            max_depth: 3,
+        };
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+        let forged = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(b"other"),
+        )
+        .unwrap();
+
+        let mut authenticator = Authenticator::new(Some(&auth_config()));
+        let request = authenticator.call(bearer(&token)).unwrap();
+        let context = request.extensions().get::<AuthContext>().unwrap();
+        assert_eq!(context.client, "quoting-engine");
+        assert_eq!(
+            context.entitlements.venues,
+            Some(vec!["Bitstamp".to_string()])
+        );
+        assert_eq!(context.entitlements.max_depth, Some(3));
+
+        assert!(authenticator.call(bearer(&forged)).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use crate::orderbook::orderbook_aggregator_client::OrderbookAggregatorClient;
+use crate::orderbook::{
+    BookSummaryRequest, FeedStatsRequest, FeedStatsResponse, Summary, Trade, TradesRequest,
+};
+use futures_util::stream::{self, Stream, StreamExt};
+use std::time::Duration;
+use thiserror::Error;
+use tonic::metadata::MetadataValue;
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tonic::{Code, Request, Status, Streaming};
+use tracing::{info, warn};
+
+/// Delay before resubscribing after the stream fails.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("Invalid server address: {0}")]
+    InvalidAddress(String),
+    #[error("Connection failed: {0}")]
+    Transport(#[from] tonic::transport::Error),
+    /// Boxed, as a `Status` is several times the size of the other variants.
+    #[error("Server returned {}: {}", .0.code(), .0.message())]
+    Status(Box<Status>),
+    #[error("Subscription ended by the server")]
+    Ended,
+}
+
+impl From<Status> for ClientError {
+    fn from(status: Status) -> Self {
+        ClientError::Status(Box::new(status))
+    }
+}
+
+impl ClientError {
+    /// Whether resubscribing could help. Rejections such as a bad token or
+    /// an unknown instrument will not change by retrying.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClientError::InvalidAddress(_) => false,
+            ClientError::Transport(_) | ClientError::Ended => true,
+            ClientError::Status(status) => !matches!(
+                status.code(),
+                Code::Unauthenticated
+                    | Code::PermissionDenied
+                    | Code::InvalidArgument
+                    | Code::NotFound
+                    | Code::Unimplemented
+            ),
+        }
+    }
+}
+
+/// Client for the `OrderbookAggregator` service. Cheap to clone.
+#[derive(Clone)]
+pub struct OrderbookClient {
+    endpoint: Endpoint,
+    token: Option<String>,
+    reconnect_delay: Duration,
+}
+
+impl OrderbookClient {
+    /// `addr` is a URI such as `http://127.0.0.1:50051`.
+    pub fn new(addr: &str) -> Result<Self, ClientError> {
+        let endpoint = Endpoint::from_shared(addr.to_string())
+            .map_err(|_| ClientError::InvalidAddress(addr.to_string()))?;
+        Ok(Self {
+            endpoint,
+            token: None,
+            reconnect_delay: RECONNECT_DELAY,
+        })
+    }
+
+    /// Sent as `authorization: Bearer <token>` on every request.
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    pub fn with_tls(mut self, tls: ClientTlsConfig) -> Result<Self, ClientError> {
+        self.endpoint = self.endpoint.tls_config(tls)?;
+        Ok(self)
+    }
+
+    pub fn with_reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    async fn connect(&self) -> Result<OrderbookAggregatorClient<Channel>, ClientError> {
+        Ok(OrderbookAggregatorClient::new(
+            self.endpoint.connect().await?,
+        ))
+    }
+
+    fn request<T>(&self, message: T) -> Result<Request<T>, ClientError> {
+        let mut request = Request::new(message);
+        if let Some(token) = &self.token {
+            let value = MetadataValue::try_from(format!("Bearer {}", token))
+                .map_err(|_| Status::invalid_argument("Token is not valid metadata"))?;
+            request.metadata_mut().insert("authorization", value);
+        }
+        Ok(request)
+    }
+
+    /// Opens a single `BookSummary` stream.
+    pub async fn book_summary(
+        &self,
+        request: BookSummaryRequest,
+    ) -> Result<Streaming<Summary>, ClientError> {
+        let mut client = self.connect().await?;
+        let response = client.book_summary(self.request(request)?).await?;
+        Ok(response.into_inner())
+    }
+
+    pub async fn feed_stats(&self) -> Result<FeedStatsResponse, ClientError> {
+        let mut client = self.connect().await?;
+        let response = client
+            .get_feed_stats(self.request(FeedStatsRequest {})?)
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    /// Opens a single `StreamTrades` stream.
+    pub async fn stream_trades(
+        &self,
+        request: TradesRequest,
+    ) -> Result<Streaming<Trade>, ClientError> {
+        let mut client = self.connect().await?;
+        let response = client.stream_trades(self.request(request)?).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Streams summaries, resubscribing after the reconnect delay whenever
+    /// the connection fails or the server ends the stream. Each failure is
+    /// yielded as an error; the stream ends after one that isn't retryable.
+    pub fn subscribe(
+        &self,
+        request: BookSummaryRequest,
+    ) -> impl Stream<Item = Result<Summary, ClientError>> + Send + 'static {
+        enum State {
+            Connect { delay: Duration },
+            Streaming(Box<Streaming<Summary>>),
+            Done,
+        }
+
+        let client = self.clone();
+        stream::unfold(
+            State::Connect {
+                delay: Duration::ZERO,
+            },
+            move |state| {
+                let (client, request) = (client.clone(), request.clone());
+                async move {
+                    let result = match state {
+                        State::Done => return None,
+                        State::Connect { delay } => {
+                            tokio::time::sleep(delay).await;
+                            match client.book_summary(request).await {
+                                Ok(mut summaries) => {
+                                    info!("Subscribed to book summaries");
+                                    next_summary(&mut summaries).await.map(|summary| {
+                                        (summary, State::Streaming(Box::new(summaries)))
+                                    })
+                                }
+                                Err(err) => Err(err),
+                            }
+                        }
+                        State::Streaming(mut summaries) => next_summary(&mut summaries)
+                            .await
+                            .map(|summary| (summary, State::Streaming(summaries))),
+                    };
+                    Some(match result {
+                        Ok((summary, state)) => (Ok(summary), state),
+                        Err(err) if err.is_retryable() => {
+                            warn!("Book summary stream failed, resubscribing: {}", err);
+                            let delay = client.reconnect_delay;
+                            (Err(err), State::Connect { delay })
+                        }
+                        Err(err) => (Err(err), State::Done),
+                    })
+                }
+            },
+        )
+    }
+}
+
+async fn next_summary(summaries: &mut Streaming<Summary>) -> Result<Summary, ClientError> {
+    match summaries.next().await {
+        Some(result) => Ok(result?),
+        None => Err(ClientError::Ended),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combined_book::CombinedBookSnapshot;
+    use crate::config::SubscriptionConfig;
+    use crate::exchange::{Exchange, ExchangeOrder};
+    use crate::grpc::auth::Authenticator;
+    use crate::grpc::orderbook_service::OrderbookService;
+    use crate::orderbook::orderbook_aggregator_server::OrderbookAggregatorServer;
+    use crate::subscription::Subscriptions;
+    use tokio::net::TcpListener;
+    use tokio::sync::watch;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::transport::Server;
+
+    #[tokio::test]
+    async fn test_subscribe_retries_until_server_is_up() {
+        // Reserve a port, then start serving on it only after the first
+        // attempt has failed.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let client = OrderbookClient::new(&format!("http://{}", addr))
+            .unwrap()
+            .with_reconnect_delay(Duration::from_millis(50));
+        let mut summaries = Box::pin(client.subscribe(BookSummaryRequest::default()));
+        let first = summaries.next().await.unwrap();
+        assert!(matches!(first, Err(ClientError::Transport(_))));
+
+        let (sender, receiver) = watch::channel(CombinedBookSnapshot::default());
+        let subscriptions = Subscriptions::new(receiver, "ethbtc", &SubscriptionConfig::default());
+        let listener = TcpListener::bind(addr).await.unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(OrderbookAggregatorServer::with_interceptor(
+                    OrderbookService::new(subscriptions),
+                    Authenticator::new(None),
+                ))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        sender.send_modify(|snapshot| {
+            snapshot.bids = vec![ExchangeOrder {
+                exchange: Exchange::BINANCE,
+                price: 100.0,
+                amount: 1.0,
+                original_price: None,
+            }]
+        });
+
+        let summary = loop {
+            match summaries.next().await.unwrap() {
+                Ok(summary) => break summary,
+                Err(err) => assert!(err.is_retryable()),
+            }
+        };
+        assert_eq!(summary.bids[0].price, 100.0);
+        assert_eq!(summary.bids[0].exchange, "Binance");
+    }
+
+    #[test]
+    fn test_rejections_are_not_retried() {
+        assert!(!ClientError::from(Status::unauthenticated("bad token")).is_retryable());
+        assert!(!ClientError::from(Status::not_found("unknown instrument")).is_retryable());
+        assert!(ClientError::from(Status::unavailable("restarting")).is_retryable());
+        assert!(ClientError::Ended.is_retryable());
+    }
+}
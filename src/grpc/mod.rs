@@ -1 +1,4 @@
+pub mod auth;
+pub mod client;
 pub mod orderbook_service;
+pub mod tls;
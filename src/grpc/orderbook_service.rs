@@ -1,11 +1,32 @@
+use crate::analytics;
+use crate::candles::{self, Candles, SeriesKey};
 use crate::combined_book::CombinedBookSnapshot;
-use crate::exchange::ExchangeOrder;
-use crate::orderbook::{orderbook_aggregator_server::OrderbookAggregator, Empty, Level, Summary};
+use crate::config::AnalyticsConfig;
+use crate::exchange::{self, unix_time_us, ExchangeOrder, TradeSide};
+use crate::feed_stats::{FeedStats, LatencySummary, VenueFeedStats};
+use crate::grpc::auth::AuthContext;
+use crate::grpc::tls::client_subject;
+use crate::liquidity_stats::{LiquidityStats, LiquiditySummary};
+use crate::orderbook::{
+    orderbook_aggregator_server::OrderbookAggregator, Aggressor, AnalyticsRequest, BookAnalytics,
+    BookSummaryRequest, CancelPaperOrderRequest, Candle, CandleSource, CandlesRequest,
+    CandlesResponse, ChildOrder, DepthBand, FeedStatsRequest, FeedStatsResponse, Imbalance,
+    LatencyStats, Level, Liquidity, LiquidityStatsRequest, LiquidityStatsResponse, PaperFill,
+    PaperFillsRequest, PaperOrder, PaperOrderRequest, PaperOrderStatus, PaperPosition,
+    PaperPositionsRequest, PaperPositionsResponse, RouteOrderRequest, RouteOrderResponse, Side,
+    Summary, Trade, TradesRequest, VenueStats,
+};
+use crate::paper_trading::{self, PaperTrading, PaperTradingError};
+use crate::routing::{self, RouteRequest, Router};
+use crate::subscription::{SubscriptionError, SubscriptionRequest, Subscriptions};
 use futures_util::{Stream, StreamExt};
 use std::pin::Pin;
-use tokio::sync::watch;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
 use tonic::{Request, Response, Status};
-use tracing::{debug, info, instrument};
+use tracing::{debug, info, instrument, warn};
 
 impl From<ExchangeOrder> for Level {
     fn from(order: ExchangeOrder) -> Self {
@@ -13,6 +34,7 @@ impl From<ExchangeOrder> for Level {
             exchange: order.exchange.to_string(),
             price: order.price,
             amount: order.amount,
+            original_price: order.original_price,
         }
     }
 }
@@ -27,48 +49,1283 @@ impl From<CombinedBookSnapshot> for Summary {
     }
 }
 
+impl From<exchange::Trade> for Trade {
+    fn from(trade: exchange::Trade) -> Self {
+        let aggressor = match trade.aggressor {
+            TradeSide::Buy => Aggressor::Buy,
+            TradeSide::Sell => Aggressor::Sell,
+        };
+        Trade {
+            exchange: trade.exchange.to_string(),
+            price: trade.price,
+            amount: trade.amount,
+            aggressor: aggressor.into(),
+            trade_id: trade.trade_id,
+            event_time_us: trade.event_time_us,
+            received_time_us: trade.received_time_us,
+        }
+    }
+}
+
+impl From<TradeSide> for Side {
+    fn from(side: TradeSide) -> Self {
+        match side {
+            TradeSide::Buy => Side::Buy,
+            TradeSide::Sell => Side::Sell,
+        }
+    }
+}
+
+impl From<paper_trading::PaperOrder> for PaperOrder {
+    fn from(order: paper_trading::PaperOrder) -> Self {
+        let status = match order.status {
+            paper_trading::PaperOrderStatus::Pending => PaperOrderStatus::Pending,
+            paper_trading::PaperOrderStatus::Open => PaperOrderStatus::Open,
+            paper_trading::PaperOrderStatus::Filled => PaperOrderStatus::Filled,
+            paper_trading::PaperOrderStatus::Cancelled => PaperOrderStatus::Cancelled,
+        };
+        PaperOrder {
+            order_id: order.id,
+            side: Side::from(order.request.side).into(),
+            quantity: order.request.amount,
+            limit_price: order.request.limit_price,
+            exchanges: order
+                .request
+                .venues
+                .iter()
+                .flatten()
+                .map(|exchange| exchange.to_string())
+                .collect(),
+            status: status.into(),
+            filled: order.filled,
+            average_price: order.average_price(),
+            queue_ahead: order.queue_ahead,
+        }
+    }
+}
+
+impl From<paper_trading::PaperFill> for PaperFill {
+    fn from(fill: paper_trading::PaperFill) -> Self {
+        let liquidity = match fill.liquidity {
+            paper_trading::Liquidity::Maker => Liquidity::Maker,
+            paper_trading::Liquidity::Taker => Liquidity::Taker,
+        };
+        PaperFill {
+            order_id: fill.order_id,
+            exchange: fill.exchange.to_string(),
+            side: Side::from(fill.side).into(),
+            price: fill.price,
+            quantity: fill.amount,
+            fee: fill.fee,
+            liquidity: liquidity.into(),
+            time_us: fill.time_us,
+        }
+    }
+}
+
+impl From<paper_trading::PaperPosition> for PaperPosition {
+    fn from(position: paper_trading::PaperPosition) -> Self {
+        PaperPosition {
+            exchange: position.exchange.to_string(),
+            base: position.base,
+            quote: position.quote,
+            fees: position.fees,
+        }
+    }
+}
+
+impl From<routing::Route> for RouteOrderResponse {
+    fn from(route: routing::Route) -> Self {
+        RouteOrderResponse {
+            filled: route.filled(),
+            unfilled: route.unfilled,
+            average_price: route.average_price(),
+            expected_fees: route.expected_fees(),
+            child_orders: route
+                .children
+                .into_iter()
+                .map(|child| ChildOrder {
+                    exchange: child.exchange.to_string(),
+                    quantity: child.amount,
+                    limit_price: child.limit_price,
+                    expected_price: child.expected_price,
+                    expected_fee: child.expected_fee,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<PaperTradingError> for Status {
+    fn from(err: PaperTradingError) -> Self {
+        let message = err.to_string();
+        match err {
+            PaperTradingError::InvalidOrder(_) => Status::invalid_argument(message),
+            PaperTradingError::UnknownOrder(_) => Status::not_found(message),
+        }
+    }
+}
+
+impl From<candles::Candle> for Candle {
+    fn from(candle: candles::Candle) -> Self {
+        let source = match candle.key.source {
+            candles::CandleSource::Mid => CandleSource::Mid,
+            candles::CandleSource::Trades => CandleSource::Trades,
+        };
+        Candle {
+            exchange: candle
+                .key
+                .exchange
+                .map_or(String::new(), |exchange| exchange.to_string()),
+            interval_ms: candle.key.interval.as_millis() as u32,
+            source: source.into(),
+            open_time_us: candle.open_time_us,
+            open: candle.open,
+            high: candle.high,
+            low: candle.low,
+            close: candle.close,
+            volume: candle.volume,
+            trades: candle.trades,
+        }
+    }
+}
+
+impl From<analytics::BookAnalytics> for BookAnalytics {
+    fn from(analytics: analytics::BookAnalytics) -> Self {
+        BookAnalytics {
+            mid: analytics.mid,
+            microprice: analytics.microprice,
+            imbalances: analytics
+                .imbalances
+                .into_iter()
+                .map(|imbalance| Imbalance {
+                    depth: imbalance.depth as u32,
+                    imbalance: imbalance.imbalance,
+                })
+                .collect(),
+            depth_bands: analytics
+                .depth_bands
+                .into_iter()
+                .map(|band| DepthBand {
+                    bps: band.bps,
+                    exchange: band
+                        .exchange
+                        .map_or(String::new(), |exchange| exchange.to_string()),
+                    bid_amount: band.bid_amount,
+                    ask_amount: band.ask_amount,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<LiquiditySummary> for crate::orderbook::LiquidityStats {
+    fn from(summary: LiquiditySummary) -> Self {
+        crate::orderbook::LiquidityStats {
+            exchange: summary
+                .exchange
+                .map_or(String::new(), |exchange| exchange.to_string()),
+            window_ms: summary.window.as_millis() as u64,
+            observed_ms: summary.observed.as_millis() as u64,
+            mean_spread: summary.mean_spread,
+            min_spread: summary.min_spread,
+            max_spread: summary.max_spread,
+            mean_bid_size: summary.mean_bid_size,
+            mean_ask_size: summary.mean_ask_size,
+            best_bid_share: summary.best_bid_share,
+            best_ask_share: summary.best_ask_share,
+        }
+    }
+}
+
+impl From<AnalyticsRequest> for SubscriptionRequest {
+    fn from(request: AnalyticsRequest) -> Self {
+        SubscriptionRequest {
+            instrument: request.instrument,
+            exchanges: request.exchanges,
+            min_interval: Duration::from_millis(request.min_interval_ms.into()),
+            conflate: request.conflate,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<TradesRequest> for SubscriptionRequest {
+    fn from(request: TradesRequest) -> Self {
+        SubscriptionRequest {
+            instrument: request.instrument,
+            exchanges: request.exchanges,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<BookSummaryRequest> for SubscriptionRequest {
+    fn from(request: BookSummaryRequest) -> Self {
+        SubscriptionRequest {
+            instrument: request.instrument,
+            exchanges: request.exchanges,
+            depth: request.depth as usize,
+            min_interval: Duration::from_millis(request.min_interval_ms.into()),
+            conflate: request.conflate,
+            top_of_book_only: request.top_of_book_only,
+        }
+    }
+}
+
+impl From<LatencySummary> for LatencyStats {
+    fn from(latency: LatencySummary) -> Self {
+        LatencyStats {
+            samples: latency.samples as u32,
+            p50_us: latency.p50_us,
+            p90_us: latency.p90_us,
+            p99_us: latency.p99_us,
+            max_us: latency.max_us,
+            clock_skew_us: latency.clock_skew_us,
+        }
+    }
+}
+
+impl From<VenueFeedStats> for VenueStats {
+    fn from(stats: VenueFeedStats) -> Self {
+        VenueStats {
+            exchange: stats.exchange.to_string(),
+            messages: stats.messages,
+            last_sequence: stats.last_sequence,
+            last_event_time_us: stats.last_event_time_us,
+            last_received_time_us: stats.last_received_time_us,
+            latency: stats.latency.map(LatencyStats::from),
+            out_of_order: stats.out_of_order,
+            sequence_gaps: stats.sequence_gaps,
+        }
+    }
+}
+
+impl From<SubscriptionError> for Status {
+    fn from(err: SubscriptionError) -> Self {
+        let message = err.to_string();
+        match err {
+            SubscriptionError::UnknownInstrument(_) => Status::not_found(message),
+            SubscriptionError::UnknownExchange(_) => Status::invalid_argument(message),
+            SubscriptionError::NotEntitled(_) => Status::permission_denied(message),
+            SubscriptionError::TooManySubscribers => Status::resource_exhausted(message),
+        }
+    }
+}
+
 pub struct OrderbookService {
-    receiver: watch::Receiver<CombinedBookSnapshot>,
+    subscriptions: Subscriptions,
+    feed_stats: FeedStats,
+    trades: Option<broadcast::Sender<exchange::Trade>>,
+    candles: Option<Candles>,
+    analytics: AnalyticsConfig,
+    liquidity_stats: LiquidityStats,
+    paper_trading: Option<PaperTrading>,
+    router: Router,
+    allowed_client_subjects: Vec<String>,
 }
 
 impl OrderbookService {
-    pub fn new(receiver: watch::Receiver<CombinedBookSnapshot>) -> Self {
-        Self { receiver }
+    pub fn new(subscriptions: Subscriptions) -> Self {
+        Self {
+            subscriptions,
+            feed_stats: FeedStats::default(),
+            trades: None,
+            candles: None,
+            analytics: AnalyticsConfig::default(),
+            liquidity_stats: LiquidityStats::default(),
+            paper_trading: None,
+            router: Router::default(),
+            allowed_client_subjects: Vec::new(),
+        }
+    }
+
+    pub fn with_feed_stats(mut self, feed_stats: FeedStats) -> Self {
+        self.feed_stats = feed_stats;
+        self
+    }
+
+    /// Serves `StreamTrades` from this channel; without it the RPC fails
+    /// with `FAILED_PRECONDITION`.
+    pub fn with_trades(mut self, trades: broadcast::Sender<exchange::Trade>) -> Self {
+        self.trades = Some(trades);
+        self
+    }
+
+    /// Serves `StreamCandles` and `GetCandles`; without it they fail with
+    /// `FAILED_PRECONDITION`.
+    pub fn with_candles(mut self, candles: Candles) -> Self {
+        self.candles = Some(candles);
+        self
+    }
+
+    pub fn with_analytics(mut self, analytics: AnalyticsConfig) -> Self {
+        self.analytics = analytics;
+        self
+    }
+
+    pub fn with_liquidity_stats(mut self, liquidity_stats: LiquidityStats) -> Self {
+        self.liquidity_stats = liquidity_stats;
+        self
+    }
+
+    /// Serves the paper trading RPCs; without it they fail with
+    /// `FAILED_PRECONDITION`.
+    pub fn with_paper_trading(mut self, paper_trading: PaperTrading) -> Self {
+        self.paper_trading = Some(paper_trading);
+        self
+    }
+
+    pub fn with_router(mut self, router: Router) -> Self {
+        self.router = router;
+        self
+    }
+
+    pub fn with_allowed_client_subjects(mut self, subjects: Vec<String>) -> Self {
+        self.allowed_client_subjects = subjects;
+        self
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn authorise_client(&self, client_subject: Option<&str>) -> Result<(), Status> {
+        if self.allowed_client_subjects.is_empty() {
+            return Ok(());
+        }
+        match client_subject {
+            Some(subject) if self.allowed_client_subjects.iter().any(|s| s == subject) => Ok(()),
+            Some(subject) => Err(Status::permission_denied(format!(
+                "Client certificate '{}' is not authorised",
+                subject
+            ))),
+            None => Err(Status::unauthenticated("Client certificate required")),
+        }
+    }
+
+    /// Checks the client certificate and returns the context the
+    /// authenticator attached to `request`.
+    #[allow(clippy::result_large_err)]
+    fn authenticate<T>(&self, request: &Request<T>) -> Result<AuthContext, Status> {
+        self.authorise_client(client_subject(request).as_deref())?;
+        request
+            .extensions()
+            .get::<AuthContext>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Request was not authenticated"))
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn paper_trading(&self) -> Result<&PaperTrading, Status> {
+        self.paper_trading
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("Paper trading is not enabled"))
+    }
+
+    /// Resolves a candles request to a configured series the client may
+    /// read. The consolidated series mixes every venue, so it needs an
+    /// unrestricted venue entitlement.
+    #[allow(clippy::result_large_err)]
+    fn candle_series(
+        &self,
+        request: &CandlesRequest,
+        auth: &AuthContext,
+    ) -> Result<(Candles, SeriesKey), Status> {
+        let candles = self
+            .candles
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("Candles are not enabled"))?;
+        let interval = Duration::from_millis(request.interval_ms.into());
+        if !candles.intervals().contains(&interval) {
+            return Err(Status::invalid_argument(format!(
+                "Interval {}ms is not configured",
+                request.interval_ms
+            )));
+        }
+
+        let subscription_request = SubscriptionRequest {
+            instrument: request.instrument.clone(),
+            exchanges: if request.exchange.is_empty() {
+                Vec::new()
+            } else {
+                vec![request.exchange.clone()]
+            },
+            ..Default::default()
+        };
+        let filter = self
+            .subscriptions
+            .resolve_filter(&subscription_request, &auth.entitlements)?;
+        let exchange = match filter.venues {
+            _ if request.exchange.is_empty() && auth.entitlements.venues.is_some() => {
+                return Err(Status::permission_denied(
+                    "Not entitled to consolidated candles",
+                ))
+            }
+            Some(venues) => venues.first().copied(),
+            None => None,
+        };
+
+        let source = match request.source() {
+            CandleSource::Mid => candles::CandleSource::Mid,
+            CandleSource::Trades => candles::CandleSource::Trades,
+        };
+        let key = SeriesKey {
+            source,
+            exchange,
+            interval,
+        };
+        Ok((candles.clone(), key))
     }
 }
 
 #[tonic::async_trait]
 impl OrderbookAggregator for OrderbookService {
     type BookSummaryStream = Pin<Box<dyn Stream<Item = Result<Summary, Status>> + Send>>;
+    type StreamTradesStream = Pin<Box<dyn Stream<Item = Result<Trade, Status>> + Send>>;
+    type StreamCandlesStream = Pin<Box<dyn Stream<Item = Result<Candle, Status>> + Send>>;
+    type AnalyticsStream = Pin<Box<dyn Stream<Item = Result<BookAnalytics, Status>> + Send>>;
+    type StreamPaperFillsStream = Pin<Box<dyn Stream<Item = Result<PaperFill, Status>> + Send>>;
 
-    #[instrument(skip(self, _request))]
+    #[instrument(skip(self, request))]
+    #[allow(clippy::result_large_err)]
     async fn book_summary(
         &self,
-        _request: Request<Empty>,
+        request: Request<BookSummaryRequest>,
     ) -> Result<Response<Self::BookSummaryStream>, Status> {
-        let client_addr = _request.remote_addr();
+        let client_addr = request.remote_addr();
+        let client_subject = client_subject(&request);
         let request_id = uuid::Uuid::new_v4();
 
+        let auth = self.authenticate(&request).inspect_err(|status| {
+            warn!(
+                request_id = %request_id,
+                client_subject = ?client_subject,
+                "Rejecting subscribe request: {}",
+                status.message()
+            );
+        })?;
         info!(
             request_id = %request_id,
             client_addr = ?client_addr,
+            client_subject = ?client_subject,
+            client = %auth.client,
             "New subscribe request received"
         );
 
-        let receiver = self.receiver.clone();
+        let subscription_request = SubscriptionRequest::from(request.into_inner());
+        let snapshots = self
+            .subscriptions
+            .subscribe(&subscription_request, &auth.entitlements)
+            .inspect_err(|err| {
+                warn!(
+                    request_id = %request_id,
+                    client = %auth.client,
+                    "Rejecting subscribe request: {}",
+                    err
+                );
+            })?;
+        debug!(request_id = %request_id, request = ?subscription_request, "Subscription accepted");
+
+        let stream = snapshots.map(move |snapshot| {
+            debug!(
+                request_id = %request_id,
+                snapshot = ?snapshot,
+                "Sending data to subscriber"
+            );
+            Ok(snapshot.into())
+        });
+
+        Ok(Response::new(Box::pin(stream) as Self::BookSummaryStream))
+    }
+
+    #[instrument(skip(self, request))]
+    async fn get_feed_stats(
+        &self,
+        request: Request<FeedStatsRequest>,
+    ) -> Result<Response<FeedStatsResponse>, Status> {
+        let auth = self.authenticate(&request)?;
+        debug!(client = %auth.client, "Feed stats requested");
+
+        let venues = self
+            .feed_stats
+            .venues()
+            .into_iter()
+            .filter(|stats| {
+                auth.entitlements
+                    .venues
+                    .as_ref()
+                    .is_none_or(|entitled| entitled.contains(&stats.exchange.to_string()))
+            })
+            .map(VenueStats::from)
+            .collect();
+        Ok(Response::new(FeedStatsResponse { venues }))
+    }
+
+    #[instrument(skip(self, request))]
+    #[allow(clippy::result_large_err)]
+    async fn stream_trades(
+        &self,
+        request: Request<TradesRequest>,
+    ) -> Result<Response<Self::StreamTradesStream>, Status> {
+        let auth = self.authenticate(&request)?;
+        let trades = self
+            .trades
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("Trade feeds are not enabled"))?;
+
+        // Same instrument and venue checks and subscriber cap as the book;
+        // depth does not apply.
+        let (filter, permit) = self
+            .subscriptions
+            .resolve_filter(&request.into_inner().into(), &auth.entitlements)
+            .and_then(|filter| Ok((filter, self.subscriptions.try_acquire()?)))
+            .inspect_err(|err| {
+                warn!(client = %auth.client, "Rejecting trades request: {}", err);
+            })?;
+        info!(client = %auth.client, venues = ?filter.venues, "Streaming trades");
+
+        let client = auth.client;
+        let stream = BroadcastStream::new(trades.subscribe()).filter_map(move |result| {
+            let _permit = &permit;
+            let trade = match result {
+                Ok(trade) => filter
+                    .venues
+                    .as_ref()
+                    .is_none_or(|venues| venues.contains(&trade.exchange))
+                    .then(|| Ok(trade.into())),
+                Err(BroadcastStreamRecvError::Lagged(missed)) => {
+                    warn!(client = %client, missed, "Trade subscriber lagged, skipping trades");
+                    None
+                }
+            };
+            async move { trade }
+        });
+        Ok(Response::new(Box::pin(stream) as Self::StreamTradesStream))
+    }
+
+    #[instrument(skip(self, request))]
+    #[allow(clippy::result_large_err)]
+    async fn stream_candles(
+        &self,
+        request: Request<CandlesRequest>,
+    ) -> Result<Response<Self::StreamCandlesStream>, Status> {
+        let auth = self.authenticate(&request)?;
+        let (candles, key) = self.candle_series(request.get_ref(), &auth)?;
+        let permit = self.subscriptions.try_acquire().inspect_err(|err| {
+            warn!(client = %auth.client, "Rejecting candles request: {}", err);
+        })?;
+        info!(client = %auth.client, series = ?key, "Streaming candles");
+
+        let client = auth.client;
+        let stream = BroadcastStream::new(candles.subscribe()).filter_map(move |result| {
+            let _permit = &permit;
+            let candle = match result {
+                Ok(candle) => (candle.key == key).then(|| Ok(candle.into())),
+                Err(BroadcastStreamRecvError::Lagged(missed)) => {
+                    warn!(client = %client, missed, "Candle subscriber lagged, skipping bars");
+                    None
+                }
+            };
+            async move { candle }
+        });
+        Ok(Response::new(Box::pin(stream) as Self::StreamCandlesStream))
+    }
+
+    #[instrument(skip(self, request))]
+    async fn get_candles(
+        &self,
+        request: Request<CandlesRequest>,
+    ) -> Result<Response<CandlesResponse>, Status> {
+        let auth = self.authenticate(&request)?;
+        let (candles, key) = self.candle_series(request.get_ref(), &auth)?;
+        let candles = candles
+            .history(&key, request.get_ref().count as usize)
+            .into_iter()
+            .map(Candle::from)
+            .collect();
+        Ok(Response::new(CandlesResponse { candles }))
+    }
+
+    #[instrument(skip(self, request))]
+    #[allow(clippy::result_large_err)]
+    async fn analytics(
+        &self,
+        request: Request<AnalyticsRequest>,
+    ) -> Result<Response<Self::AnalyticsStream>, Status> {
+        let auth = self.authenticate(&request)?;
+
+        let subscription_request = SubscriptionRequest::from(request.into_inner());
+        let snapshots = self
+            .subscriptions
+            .subscribe(&subscription_request, &auth.entitlements)
+            .inspect_err(|err| {
+                warn!(client = %auth.client, "Rejecting analytics request: {}", err);
+            })?;
+        info!(client = %auth.client, request = ?subscription_request, "Streaming analytics");
+
+        let config = self.analytics.clone();
         let stream =
-            tokio_stream::wrappers::WatchStream::new(receiver).filter_map(move |snapshot| {
-                let request_id = request_id;
-                async move {
-                    debug!(
-                        request_id = %request_id,
-                        snapshot = ?snapshot,
-                        "Sending data to subscriber"
-                    );
-                    Some(Ok(snapshot.into()))
+            snapshots.map(move |snapshot| Ok(analytics::compute(&snapshot, &config).into()));
+        Ok(Response::new(Box::pin(stream) as Self::AnalyticsStream))
+    }
+
+    /// The consolidated figures are only sent to clients entitled to every
+    /// venue that did not ask for specific ones.
+    #[instrument(skip(self, request))]
+    async fn get_liquidity_stats(
+        &self,
+        request: Request<LiquidityStatsRequest>,
+    ) -> Result<Response<LiquidityStatsResponse>, Status> {
+        let auth = self.authenticate(&request)?;
+        let subscription_request = SubscriptionRequest {
+            exchanges: request.get_ref().exchanges.clone(),
+            ..Default::default()
+        };
+        let filter = self
+            .subscriptions
+            .resolve_filter(&subscription_request, &auth.entitlements)?;
+        debug!(client = %auth.client, venues = ?filter.venues, "Liquidity stats requested");
+
+        let stats = self
+            .liquidity_stats
+            .summaries(unix_time_us())
+            .into_iter()
+            .filter(|summary| match (&filter.venues, summary.exchange) {
+                (None, _) => true,
+                (Some(venues), Some(exchange)) => venues.contains(&exchange),
+                (Some(_), None) => false,
+            })
+            .map(crate::orderbook::LiquidityStats::from)
+            .collect();
+        Ok(Response::new(LiquidityStatsResponse { stats }))
+    }
+
+    /// Orders only fill on venues the client is entitled to.
+    #[instrument(skip(self, request))]
+    async fn submit_paper_order(
+        &self,
+        request: Request<PaperOrderRequest>,
+    ) -> Result<Response<PaperOrder>, Status> {
+        let auth = self.authenticate(&request)?;
+        let paper_trading = self.paper_trading()?;
+        let request = request.get_ref();
+        let side = match request.side() {
+            Side::Buy => TradeSide::Buy,
+            Side::Sell => TradeSide::Sell,
+            Side::Unspecified => return Err(Status::invalid_argument("Order side is required")),
+        };
+        let subscription_request = SubscriptionRequest {
+            exchanges: request.exchanges.clone(),
+            ..Default::default()
+        };
+        let filter = self
+            .subscriptions
+            .resolve_filter(&subscription_request, &auth.entitlements)?;
+
+        let order = paper_trading.submit(
+            &auth.client,
+            paper_trading::PaperOrderRequest {
+                side,
+                amount: request.quantity,
+                limit_price: request.limit_price,
+                venues: filter.venues,
+            },
+            unix_time_us(),
+        )?;
+        info!(client = %auth.client, order = ?order, "Paper order submitted");
+        Ok(Response::new(order.into()))
+    }
+
+    #[instrument(skip(self, request))]
+    async fn cancel_paper_order(
+        &self,
+        request: Request<CancelPaperOrderRequest>,
+    ) -> Result<Response<PaperOrder>, Status> {
+        let auth = self.authenticate(&request)?;
+        let order = self
+            .paper_trading()?
+            .cancel(&auth.client, request.get_ref().order_id)?;
+        info!(client = %auth.client, order_id = order.id, "Paper order cancelled");
+        Ok(Response::new(order.into()))
+    }
+
+    #[instrument(skip(self, request))]
+    #[allow(clippy::result_large_err)]
+    async fn stream_paper_fills(
+        &self,
+        request: Request<PaperFillsRequest>,
+    ) -> Result<Response<Self::StreamPaperFillsStream>, Status> {
+        let auth = self.authenticate(&request)?;
+        let fills = self.paper_trading()?.subscribe();
+        let permit = self.subscriptions.try_acquire().inspect_err(|err| {
+            warn!(client = %auth.client, "Rejecting paper fills request: {}", err);
+        })?;
+        info!(client = %auth.client, "Streaming paper fills");
+
+        let client = auth.client;
+        let stream = BroadcastStream::new(fills).filter_map(move |result| {
+            let _permit = &permit;
+            let fill = match result {
+                Ok(fill) => (fill.client == client).then(|| Ok(fill.into())),
+                Err(BroadcastStreamRecvError::Lagged(missed)) => {
+                    warn!(client = %client, missed, "Paper fill subscriber lagged, skipping fills");
+                    None
                 }
+            };
+            async move { fill }
+        });
+        Ok(Response::new(
+            Box::pin(stream) as Self::StreamPaperFillsStream
+        ))
+    }
+
+    #[instrument(skip(self, request))]
+    async fn get_paper_positions(
+        &self,
+        request: Request<PaperPositionsRequest>,
+    ) -> Result<Response<PaperPositionsResponse>, Status> {
+        let auth = self.authenticate(&request)?;
+        let paper_trading = self.paper_trading()?;
+        Ok(Response::new(PaperPositionsResponse {
+            positions: paper_trading
+                .positions(&auth.client)
+                .into_iter()
+                .map(PaperPosition::from)
+                .collect(),
+            open_orders: paper_trading
+                .open_orders(&auth.client)
+                .into_iter()
+                .map(PaperOrder::from)
+                .collect(),
+        }))
+    }
+
+    /// Routes against the book the client may see: entitled venues, to its
+    /// entitled depth.
+    #[instrument(skip(self, request))]
+    async fn route_order(
+        &self,
+        request: Request<RouteOrderRequest>,
+    ) -> Result<Response<RouteOrderResponse>, Status> {
+        let auth = self.authenticate(&request)?;
+        let request = request.get_ref();
+        let side = match request.side() {
+            Side::Buy => TradeSide::Buy,
+            Side::Sell => TradeSide::Sell,
+            Side::Unspecified => return Err(Status::invalid_argument("Order side is required")),
+        };
+        if !(request.quantity.is_finite() && request.quantity > 0.0) {
+            return Err(Status::invalid_argument("Quantity must be positive"));
+        }
+        if request
+            .limit_price
+            .is_some_and(|price| !(price.is_finite() && price > 0.0))
+        {
+            return Err(Status::invalid_argument("Limit price must be positive"));
+        }
+        let subscription_request = SubscriptionRequest {
+            instrument: request.instrument.clone(),
+            exchanges: request.exchanges.clone(),
+            ..Default::default()
+        };
+        let snapshot = self
+            .subscriptions
+            .current(&subscription_request, &auth.entitlements)?;
+
+        let route = self.router.route(
+            &snapshot,
+            &RouteRequest {
+                side,
+                amount: request.quantity,
+                limit_price: request.limit_price,
+            },
+        );
+        debug!(client = %auth.client, route = ?route, "Routed order");
+        Ok(Response::new(route.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SubscriptionConfig;
+    use crate::exchange::{Exchange, Orderbook};
+    use crate::subscription::Entitlements;
+    use tokio::sync::watch;
+
+    fn service(allowed: &[&str]) -> OrderbookService {
+        let (_, receiver) = watch::channel(CombinedBookSnapshot::default());
+        let subscriptions = Subscriptions::new(receiver, "ethbtc", &SubscriptionConfig::default());
+        OrderbookService::new(subscriptions)
+            .with_allowed_client_subjects(allowed.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_authorise_client_without_allow_list() {
+        let service = service(&[]);
+        assert!(service.authorise_client(None).is_ok());
+        assert!(service.authorise_client(Some("CN=anyone")).is_ok());
+    }
+
+    #[test]
+    fn test_authorise_client_with_allow_list() {
+        let service = service(&["CN=quoting-engine"]);
+        assert!(service.authorise_client(Some("CN=quoting-engine")).is_ok());
+        assert_eq!(
+            service
+                .authorise_client(Some("CN=dashboard"))
+                .unwrap_err()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+        assert_eq!(
+            service.authorise_client(None).unwrap_err().code(),
+            tonic::Code::Unauthenticated
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_feed_stats_filters_entitled_venues() {
+        let feed_stats = FeedStats::default();
+        let orderbook = |event_time_us| Orderbook {
+            event_time_us,
+            received_time_us: 1_250,
+            ..Default::default()
+        };
+        feed_stats.record(&Exchange::BINANCE, &orderbook(None));
+        feed_stats.record(&Exchange::BITSTAMP, &orderbook(Some(1_000)));
+        let service = service(&[]).with_feed_stats(feed_stats);
+
+        let mut request = Request::new(FeedStatsRequest {});
+        request.extensions_mut().insert(AuthContext {
+            client: "dashboard".to_string(),
+            entitlements: Entitlements {
+                venues: Some(vec!["Bitstamp".to_string()]),
+                ..Default::default()
+            },
+        });
+        let response = service.get_feed_stats(request).await.unwrap().into_inner();
+
+        assert_eq!(response.venues.len(), 1);
+        assert_eq!(response.venues[0].exchange, "Bitstamp");
+        assert_eq!(response.venues[0].last_event_time_us, Some(1_000));
+        assert_eq!(response.venues[0].latency.unwrap().p50_us, 250);
+    }
+
+    #[tokio::test]
+    async fn test_stream_trades_filters_venues() {
+        let (sender, _) = broadcast::channel(16);
+        let service = service(&[]).with_trades(sender.clone());
+        let trade = |exchange, trade_id| exchange::Trade {
+            exchange,
+            price: 0.05,
+            amount: 2.0,
+            aggressor: TradeSide::Sell,
+            trade_id,
+            event_time_us: 1_000,
+            received_time_us: 1_250,
+        };
+
+        let mut request = Request::new(TradesRequest {
+            instrument: String::new(),
+            exchanges: vec!["Bitstamp".to_string()],
+        });
+        request.extensions_mut().insert(AuthContext {
+            client: "dashboard".to_string(),
+            entitlements: Entitlements::default(),
+        });
+        let mut trades = service.stream_trades(request).await.unwrap().into_inner();
+        sender.send(trade(Exchange::BINANCE, 1)).unwrap();
+        sender.send(trade(Exchange::BITSTAMP, 2)).unwrap();
+
+        let received = trades.next().await.unwrap().unwrap();
+        assert_eq!(received.exchange, "Bitstamp");
+        assert_eq!(received.trade_id, 2);
+        assert_eq!(received.aggressor(), Aggressor::Sell);
+    }
+
+    #[tokio::test]
+    async fn test_stream_trades_counts_against_subscriber_cap() {
+        let (sender, _) = broadcast::channel(16);
+        let (_, receiver) = watch::channel(CombinedBookSnapshot::default());
+        let config = SubscriptionConfig {
+            max_subscribers: Some(1),
+            min_interval_ms: 0,
+        };
+        let service = OrderbookService::new(Subscriptions::new(receiver, "ethbtc", &config))
+            .with_trades(sender);
+        let request = || {
+            let mut request = Request::new(TradesRequest::default());
+            request.extensions_mut().insert(AuthContext {
+                client: "dashboard".to_string(),
+                entitlements: Entitlements::default(),
             });
+            request
+        };
 
-        Ok(Response::new(Box::pin(stream) as Self::BookSummaryStream))
+        let trades = service.stream_trades(request()).await.unwrap();
+        let status = service.stream_trades(request()).await.err().unwrap();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+        drop(trades);
+        assert!(service.stream_trades(request()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_stream_candles_counts_against_subscriber_cap() {
+        let (_, receiver) = watch::channel(CombinedBookSnapshot::default());
+        let config = SubscriptionConfig {
+            max_subscribers: Some(1),
+            min_interval_ms: 0,
+        };
+        let service = OrderbookService::new(Subscriptions::new(receiver, "ethbtc", &config))
+            .with_candles(Candles::new(vec![Duration::from_secs(1)], 10));
+        let request = || {
+            let mut request = Request::new(CandlesRequest {
+                interval_ms: 1000,
+                ..Default::default()
+            });
+            request.extensions_mut().insert(AuthContext {
+                client: "dashboard".to_string(),
+                entitlements: Entitlements::default(),
+            });
+            request
+        };
+
+        let candles = service.stream_candles(request()).await.unwrap();
+        let status = service.stream_candles(request()).await.err().unwrap();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+        drop(candles);
+        assert!(service.stream_candles(request()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_stream_paper_fills_counts_against_subscriber_cap() {
+        let (_, receiver) = watch::channel(CombinedBookSnapshot::default());
+        let config = SubscriptionConfig {
+            max_subscribers: Some(1),
+            min_interval_ms: 0,
+        };
+        let service = OrderbookService::new(Subscriptions::new(receiver, "ethbtc", &config))
+            .with_paper_trading(PaperTrading::new(Duration::ZERO));
+        let request = || {
+            let mut request = Request::new(PaperFillsRequest::default());
+            request.extensions_mut().insert(AuthContext {
+                client: "desk".to_string(),
+                entitlements: Entitlements::default(),
+            });
+            request
+        };
+
+        let fills = service.stream_paper_fills(request()).await.unwrap();
+        let status = service.stream_paper_fills(request()).await.err().unwrap();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+        drop(fills);
+        assert!(service.stream_paper_fills(request()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_stream_trades_requires_trade_feeds() {
+        let mut request = Request::new(TradesRequest::default());
+        request.extensions_mut().insert(AuthContext {
+            client: "dashboard".to_string(),
+            entitlements: Entitlements::default(),
+        });
+        let status = service(&[]).stream_trades(request).await.err().unwrap();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn test_get_candles_checks_series_and_entitlements() {
+        let candles = Candles::new(vec![Duration::from_secs(1)], 10);
+        candles.record_trade(&exchange::Trade {
+            exchange: Exchange::BINANCE,
+            price: 0.05,
+            amount: 2.0,
+            aggressor: TradeSide::Buy,
+            trade_id: 1,
+            event_time_us: 1_000_000,
+            received_time_us: 1_000_000,
+        });
+        candles.close_due(2_000_000);
+        let service = service(&[]).with_candles(candles);
+        let get = |exchange: &str, interval_ms, venues: Option<Vec<String>>| {
+            let mut request = Request::new(CandlesRequest {
+                exchange: exchange.to_string(),
+                interval_ms,
+                source: CandleSource::Trades.into(),
+                ..Default::default()
+            });
+            request.extensions_mut().insert(AuthContext {
+                client: "dashboard".to_string(),
+                entitlements: Entitlements {
+                    venues,
+                    ..Default::default()
+                },
+            });
+            service.get_candles(request)
+        };
+
+        let response = get("Binance", 1000, None).await.unwrap().into_inner();
+        assert_eq!(response.candles.len(), 1);
+        assert_eq!(response.candles[0].exchange, "Binance");
+        assert_eq!(response.candles[0].volume, 2.0);
+        let consolidated = get("", 1000, None).await.unwrap().into_inner();
+        assert_eq!(consolidated.candles[0].exchange, "");
+
+        let code = |result: Result<_, Status>| result.err().unwrap().code();
+        assert_eq!(
+            code(get("Binance", 60_000, None).await),
+            tonic::Code::InvalidArgument
+        );
+        let bitstamp_only = Some(vec!["Bitstamp".to_string()]);
+        assert_eq!(
+            code(get("Binance", 1000, bitstamp_only.clone()).await),
+            tonic::Code::PermissionDenied
+        );
+        assert_eq!(
+            code(get("", 1000, bitstamp_only).await),
+            tonic::Code::PermissionDenied
+        );
+    }
+
+    #[tokio::test]
+    async fn test_analytics_streams_figures_for_the_filtered_book() {
+        let (_sender, receiver) = watch::channel(CombinedBookSnapshot {
+            spread: 1.0,
+            bids: vec![
+                ExchangeOrder::new(Exchange::BINANCE, 100.0, 1.0),
+                ExchangeOrder::new(Exchange::BITSTAMP, 99.0, 1.0),
+            ],
+            asks: vec![
+                ExchangeOrder::new(Exchange::BITSTAMP, 101.0, 3.0),
+                ExchangeOrder::new(Exchange::BINANCE, 102.0, 1.0),
+            ],
+        });
+        let subscriptions = Subscriptions::new(receiver, "ethbtc", &SubscriptionConfig::default());
+        let service = OrderbookService::new(subscriptions).with_analytics(AnalyticsConfig {
+            imbalance_depths: vec![1],
+            depth_bands_bps: Vec::new(),
+        });
+
+        let mut request = Request::new(AnalyticsRequest {
+            exchanges: vec!["Bitstamp".to_string()],
+            ..Default::default()
+        });
+        request.extensions_mut().insert(AuthContext {
+            client: "quoting".to_string(),
+            entitlements: Entitlements::default(),
+        });
+        let mut stream = service.analytics(request).await.unwrap().into_inner();
+        let analytics = stream.next().await.unwrap().unwrap();
+
+        assert_eq!(analytics.mid, Some(100.0));
+        assert_eq!(analytics.microprice, Some(99.5));
+        assert_eq!(analytics.imbalances[0].imbalance, -0.5);
+    }
+
+    #[tokio::test]
+    async fn test_get_liquidity_stats_filters_entitled_venues() {
+        let liquidity_stats = LiquidityStats::default();
+        let order = |exchange, price| ExchangeOrder::new(exchange, price, 1.0);
+        liquidity_stats.record(
+            &CombinedBookSnapshot {
+                spread: 1.0,
+                bids: vec![
+                    order(Exchange::BINANCE, 100.0),
+                    order(Exchange::BITSTAMP, 99.0),
+                ],
+                asks: vec![
+                    order(Exchange::BINANCE, 101.0),
+                    order(Exchange::BITSTAMP, 102.0),
+                ],
+            },
+            unix_time_us() - 2_000_000,
+        );
+        let service = service(&[]).with_liquidity_stats(liquidity_stats);
+        let get = |venues: Option<Vec<String>>| {
+            let mut request = Request::new(LiquidityStatsRequest::default());
+            request.extensions_mut().insert(AuthContext {
+                client: "desk".to_string(),
+                entitlements: Entitlements {
+                    venues,
+                    ..Default::default()
+                },
+            });
+            service.get_liquidity_stats(request)
+        };
+
+        let all = get(None).await.unwrap().into_inner().stats;
+        assert_eq!(all.len(), 3 * 3);
+        assert_eq!(all[0].exchange, "");
+        assert_eq!(all[0].window_ms, 60_000);
+        assert_eq!(all[0].mean_spread, 1.0);
+
+        let bitstamp = get(Some(vec!["Bitstamp".to_string()]))
+            .await
+            .unwrap()
+            .into_inner()
+            .stats;
+        assert_eq!(bitstamp.len(), 3);
+        assert!(bitstamp.iter().all(|stats| stats.exchange == "Bitstamp"));
+        assert_eq!(bitstamp[0].mean_spread, 3.0);
+        assert_eq!(bitstamp[0].best_bid_share, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_paper_orders_fill_against_entitled_venues() {
+        let paper_trading = PaperTrading::new(Duration::ZERO);
+        let service = service(&[]).with_paper_trading(paper_trading.clone());
+        fn with_auth<T>(mut request: Request<T>) -> Request<T> {
+            request.extensions_mut().insert(AuthContext {
+                client: "desk".to_string(),
+                entitlements: Entitlements {
+                    venues: Some(vec!["Bitstamp".to_string()]),
+                    ..Default::default()
+                },
+            });
+            request
+        }
+
+        let unspecified = service
+            .submit_paper_order(with_auth(Request::new(PaperOrderRequest {
+                quantity: 1.0,
+                ..Default::default()
+            })))
+            .await;
+        assert_eq!(
+            unspecified.unwrap_err().code(),
+            tonic::Code::InvalidArgument
+        );
+
+        let order = service
+            .submit_paper_order(with_auth(Request::new(PaperOrderRequest {
+                side: Side::Buy.into(),
+                quantity: 1.5,
+                ..Default::default()
+            })))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(order.status(), PaperOrderStatus::Pending);
+        assert_eq!(order.exchanges, vec!["Bitstamp"]);
+
+        let ask = |exchange, price| ExchangeOrder::new(exchange, price, 1.0);
+        paper_trading.on_update(
+            &CombinedBookSnapshot {
+                spread: 0.0,
+                bids: Vec::new(),
+                asks: vec![
+                    ask(Exchange::BINANCE, 100.0),
+                    ask(Exchange::BITSTAMP, 101.0),
+                ],
+            },
+            unix_time_us(),
+        );
+
+        let positions = service
+            .get_paper_positions(with_auth(Request::new(PaperPositionsRequest {})))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(positions.open_orders.is_empty());
+        assert_eq!(positions.positions.len(), 1);
+        assert_eq!(positions.positions[0].exchange, "Bitstamp");
+        assert_eq!(positions.positions[0].base, 1.0);
+
+        let cancelled = service
+            .cancel_paper_order(with_auth(Request::new(CancelPaperOrderRequest {
+                order_id: order.order_id,
+            })))
+            .await;
+        assert_eq!(cancelled.unwrap_err().code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_route_order_uses_entitled_venues() {
+        let (sender, receiver) = watch::channel(CombinedBookSnapshot::default());
+        let subscriptions = Subscriptions::new(receiver, "ethbtc", &SubscriptionConfig::default());
+        let service = OrderbookService::new(subscriptions)
+            .with_router(Router::new().with_min_order_size(Exchange::BITSTAMP, 0.5));
+        let ask = |exchange, price| ExchangeOrder::new(exchange, price, 1.0);
+        sender.send_modify(|snapshot| {
+            snapshot.asks = vec![
+                ask(Exchange::BINANCE, 100.0),
+                ask(Exchange::BITSTAMP, 101.0),
+                ask(Exchange::BITSTAMP, 102.0),
+            ]
+        });
+        let route = |venues: Option<Vec<String>>, max_depth, quantity, limit_price| {
+            let mut request = Request::new(RouteOrderRequest {
+                side: Side::Buy.into(),
+                quantity,
+                limit_price,
+                ..Default::default()
+            });
+            request.extensions_mut().insert(AuthContext {
+                client: "desk".to_string(),
+                entitlements: Entitlements {
+                    venues,
+                    max_depth,
+                    ..Default::default()
+                },
+            });
+            service.route_order(request)
+        };
+
+        let all = route(None, None, 1.2, None).await.unwrap().into_inner();
+        // Bitstamp's 0.2 is under its minimum.
+        assert_eq!(all.child_orders.len(), 1);
+        assert_eq!(all.child_orders[0].exchange, "Binance");
+        assert!((all.unfilled - 0.2).abs() < 1e-9);
+
+        let bitstamp = route(Some(vec!["Bitstamp".to_string()]), None, 1.5, None)
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(bitstamp.child_orders.len(), 1);
+        assert_eq!(bitstamp.child_orders[0].limit_price, 102.0);
+        assert_eq!(bitstamp.average_price, Some((101.0 + 0.5 * 102.0) / 1.5));
+        assert_eq!(bitstamp.filled, 1.5);
+
+        // Levels past the entitled depth aren't routed to.
+        let shallow = route(Some(vec!["Bitstamp".to_string()]), Some(1), 1.5, None)
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(shallow.child_orders.len(), 1);
+        assert_eq!(shallow.child_orders[0].limit_price, 101.0);
+        assert_eq!(shallow.filled, 1.0);
+        assert!((shallow.unfilled - 0.5).abs() < 1e-9);
+
+        let invalid = route(None, None, 0.0, None).await.unwrap_err();
+        assert_eq!(invalid.code(), tonic::Code::InvalidArgument);
+        for limit_price in [f64::NAN, 0.0, -1.0] {
+            let invalid = route(None, None, 1.0, Some(limit_price)).await.unwrap_err();
+            assert_eq!(invalid.code(), tonic::Code::InvalidArgument);
+        }
+    }
+
+    #[test]
+    fn test_book_summary_request_conversion() {
+        let request = BookSummaryRequest {
+            instrument: "ethbtc".to_string(),
+            exchanges: vec!["Binance".to_string()],
+            depth: 5,
+            min_interval_ms: 250,
+            conflate: true,
+            top_of_book_only: false,
+        };
+        assert_eq!(
+            SubscriptionRequest::from(request),
+            SubscriptionRequest {
+                instrument: "ethbtc".to_string(),
+                exchanges: vec!["Binance".to_string()],
+                depth: 5,
+                min_interval: Duration::from_millis(250),
+                conflate: true,
+                top_of_book_only: false,
+            }
+        );
     }
 }
@@ -0,0 +1,58 @@
+use crate::config::TlsConfig;
+use std::fs;
+use tonic::transport::{Certificate, ClientTlsConfig, Identity, ServerTlsConfig};
+use tonic::Request;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+pub fn load_server_tls_config(
+    config: &TlsConfig,
+) -> Result<ServerTlsConfig, Box<dyn std::error::Error>> {
+    let cert = fs::read_to_string(&config.cert_path)?;
+    let key = fs::read_to_string(&config.key_path)?;
+    let mut tls_config = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+
+    if let Some(client_ca_path) = &config.client_ca_path {
+        let client_ca = fs::read_to_string(client_ca_path)?;
+        tls_config = tls_config.client_ca_root(Certificate::from_pem(client_ca));
+    }
+
+    Ok(tls_config)
+}
+
+/// TLS for `OrderbookClient`. `ca_path` replaces the system roots and a
+/// `cert_path`/`key_path` pair is presented for mutual TLS.
+pub fn load_client_tls_config(
+    ca_path: Option<&str>,
+    identity: Option<(&str, &str)>,
+    domain: Option<&str>,
+) -> Result<ClientTlsConfig, Box<dyn std::error::Error>> {
+    let mut tls_config = ClientTlsConfig::new();
+    tls_config = match ca_path {
+        Some(ca_path) => {
+            tls_config.ca_certificate(Certificate::from_pem(fs::read_to_string(ca_path)?))
+        }
+        None => tls_config.with_native_roots(),
+    };
+    if let Some((cert_path, key_path)) = identity {
+        let cert = fs::read_to_string(cert_path)?;
+        let key = fs::read_to_string(key_path)?;
+        tls_config = tls_config.identity(Identity::from_pem(cert, key));
+    }
+    if let Some(domain) = domain {
+        tls_config = tls_config.domain_name(domain);
+    }
+    Ok(tls_config)
+}
+
+/// Subject of the leaf certificate the client presented, e.g.
+/// `CN=quoting-engine, O=Desk`. `None` for plaintext or one-way TLS.
+pub fn client_subject<T>(request: &Request<T>) -> Option<String> {
+    let certs = request.peer_certs()?;
+    certificate_subject(certs.first()?.as_ref())
+}
+
+/// Subject of a DER-encoded certificate.
+pub fn certificate_subject(der: &[u8]) -> Option<String> {
+    let (_, cert) = X509Certificate::from_der(der).ok()?;
+    Some(cert.subject().to_string())
+}
@@ -0,0 +1,16 @@
+use crate::subscription::SubscriptionError;
+use axum::http::StatusCode;
+
+pub mod rest_api;
+
+// Shared by the REST API and the WebSocket publisher's upgrade handshake.
+impl From<&SubscriptionError> for StatusCode {
+    fn from(err: &SubscriptionError) -> Self {
+        match err {
+            SubscriptionError::UnknownInstrument(_) => StatusCode::NOT_FOUND,
+            SubscriptionError::UnknownExchange(_) => StatusCode::BAD_REQUEST,
+            SubscriptionError::NotEntitled(_) => StatusCode::FORBIDDEN,
+            SubscriptionError::TooManySubscribers => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
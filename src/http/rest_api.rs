@@ -0,0 +1,352 @@
+use crate::exchange::unix_time_us;
+use crate::exchange::Exchange;
+use crate::grpc::auth::{AuthContext, Authenticator};
+use crate::json::{JsonBook, JsonLevel};
+use crate::liquidity_stats::LiquidityStats;
+use crate::subscription::{SubscriptionError, SubscriptionRequest, Subscriptions};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use tokio::net::TcpListener;
+use tracing::debug;
+
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            error: self.message,
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+impl From<SubscriptionError> for ApiError {
+    fn from(err: SubscriptionError) -> Self {
+        ApiError {
+            status: (&err).into(),
+            message: err.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct BookQuery {
+    #[serde(default)]
+    depth: usize,
+    /// Comma-separated exchange names.
+    #[serde(default)]
+    exchanges: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct SpreadResponse {
+    spread: f64,
+    best_bid: Option<JsonLevel>,
+    best_ask: Option<JsonLevel>,
+}
+
+#[derive(Serialize, Debug)]
+struct VenueResponse {
+    exchange: String,
+    bid_levels: usize,
+    ask_levels: usize,
+    best_bid: Option<f64>,
+    best_ask: Option<f64>,
+}
+
+/// HTTP/JSON queries against the current combined book, for `curl` and
+/// scripts. Authenticates with the gRPC bearer tokens and applies the same
+/// entitlements.
+#[derive(Clone)]
+pub struct RestApi {
+    subscriptions: Subscriptions,
+    authenticator: Authenticator,
+    venues: Vec<Exchange>,
+    liquidity_stats: LiquidityStats,
+}
+
+impl RestApi {
+    pub fn new(
+        subscriptions: Subscriptions,
+        authenticator: Authenticator,
+        venues: Vec<Exchange>,
+    ) -> Self {
+        Self {
+            subscriptions,
+            authenticator,
+            venues,
+            liquidity_stats: LiquidityStats::default(),
+        }
+    }
+
+    /// Served as Prometheus gauges on `/metrics`.
+    pub fn with_liquidity_stats(mut self, liquidity_stats: LiquidityStats) -> Self {
+        self.liquidity_stats = liquidity_stats;
+        self
+    }
+
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/book", get(book))
+            .route("/book/:venue", get(venue_book))
+            .route("/spread", get(spread))
+            .route("/venues", get(venues))
+            .route("/metrics", get(metrics))
+            .with_state(self)
+    }
+
+    pub async fn serve(self, listener: TcpListener) -> std::io::Result<()> {
+        axum::serve(listener, self.router()).await
+    }
+
+    fn authenticate(&self, headers: &HeaderMap) -> Result<AuthContext, ApiError> {
+        let token = headers
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        self.authenticator
+            .authenticate(token)
+            .map_err(|status| ApiError {
+                status: StatusCode::UNAUTHORIZED,
+                message: status.message().to_string(),
+            })
+    }
+}
+
+async fn book(
+    State(api): State<RestApi>,
+    headers: HeaderMap,
+    Query(query): Query<BookQuery>,
+) -> Result<Json<JsonBook>, ApiError> {
+    let auth = api.authenticate(&headers)?;
+    let request = SubscriptionRequest {
+        exchanges: query
+            .exchanges
+            .map(|names| {
+                // An empty list, as in `?exchanges=`, means every venue.
+                names
+                    .split(',')
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        depth: query.depth,
+        ..Default::default()
+    };
+    debug!(client = %auth.client, request = ?request, "GET /book");
+    let snapshot = api.subscriptions.current(&request, &auth.entitlements)?;
+    Ok(Json(JsonBook::from(&snapshot)))
+}
+
+async fn venue_book(
+    State(api): State<RestApi>,
+    headers: HeaderMap,
+    Path(venue): Path<String>,
+    Query(query): Query<BookQuery>,
+) -> Result<Json<JsonBook>, ApiError> {
+    let auth = api.authenticate(&headers)?;
+    if !Exchange::from_str(&venue).is_ok_and(|exchange| api.venues.contains(&exchange)) {
+        return Err(ApiError {
+            status: StatusCode::NOT_FOUND,
+            message: format!("Exchange '{}' is not aggregated", venue),
+        });
+    }
+    let request = SubscriptionRequest {
+        exchanges: vec![venue],
+        depth: query.depth,
+        ..Default::default()
+    };
+    debug!(client = %auth.client, request = ?request, "GET /book/{{venue}}");
+    let snapshot = api.subscriptions.current(&request, &auth.entitlements)?;
+    Ok(Json(JsonBook::from(&snapshot)))
+}
+
+async fn spread(
+    State(api): State<RestApi>,
+    headers: HeaderMap,
+) -> Result<Json<SpreadResponse>, ApiError> {
+    let auth = api.authenticate(&headers)?;
+    let request = SubscriptionRequest {
+        depth: 1,
+        ..Default::default()
+    };
+    let snapshot = api.subscriptions.current(&request, &auth.entitlements)?;
+    Ok(Json(SpreadResponse {
+        spread: snapshot.spread,
+        best_bid: snapshot.bids.first().map(JsonLevel::from),
+        best_ask: snapshot.asks.first().map(JsonLevel::from),
+    }))
+}
+
+async fn venues(
+    State(api): State<RestApi>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<VenueResponse>>, ApiError> {
+    let auth = api.authenticate(&headers)?;
+    let snapshot = api
+        .subscriptions
+        .current(&SubscriptionRequest::default(), &auth.entitlements)?;
+
+    let venues = api
+        .venues
+        .iter()
+        .filter(|venue| {
+            auth.entitlements
+                .venues
+                .as_ref()
+                .is_none_or(|entitled| entitled.contains(&venue.to_string()))
+        })
+        .map(|venue| {
+            let bids = snapshot.bids.iter().filter(|o| o.exchange == *venue);
+            let asks = snapshot.asks.iter().filter(|o| o.exchange == *venue);
+            VenueResponse {
+                exchange: venue.to_string(),
+                bid_levels: bids.clone().count(),
+                ask_levels: asks.clone().count(),
+                best_bid: bids.map(|o| o.price).next(),
+                best_ask: asks.map(|o| o.price).next(),
+            }
+        })
+        .collect();
+    Ok(Json(venues))
+}
+
+/// Liquidity statistics for the venues the client is entitled to; the
+/// consolidated series needs an unrestricted entitlement.
+async fn metrics(
+    State(api): State<RestApi>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let auth = api.authenticate(&headers)?;
+    let summaries: Vec<_> = api
+        .liquidity_stats
+        .summaries(unix_time_us())
+        .into_iter()
+        .filter(
+            |summary| match (&auth.entitlements.venues, summary.exchange) {
+                (None, _) => true,
+                (Some(entitled), Some(exchange)) => entitled.contains(&exchange.to_string()),
+                (Some(_), None) => false,
+            },
+        )
+        .collect();
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        LiquidityStats::render_metrics(&summaries),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combined_book::CombinedBookSnapshot;
+    use crate::config::SubscriptionConfig;
+    use crate::exchange::ExchangeOrder;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tokio::sync::watch;
+    use tower::ServiceExt;
+
+    fn router() -> Router {
+        let snapshot = CombinedBookSnapshot {
+            spread: 0.5,
+            bids: vec![
+                ExchangeOrder::new(Exchange::BINANCE, 100.0, 1.0),
+                ExchangeOrder::new(Exchange::BITSTAMP, 99.5, 1.0),
+            ],
+            asks: vec![
+                ExchangeOrder::new(Exchange::BINANCE, 100.5, 1.0),
+                ExchangeOrder::new(Exchange::BITSTAMP, 101.0, 1.0),
+            ],
+        };
+        let (_, receiver) = watch::channel(snapshot);
+        let subscriptions = Subscriptions::new(receiver, "ethbtc", &SubscriptionConfig::default());
+        RestApi::new(
+            subscriptions,
+            Authenticator::new(None),
+            vec![Exchange::BINANCE, Exchange::BITSTAMP],
+        )
+        .router()
+    }
+
+    async fn get_json(uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = router()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_get_book() {
+        let (status, body) = get_json("/book?depth=1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["bids"].as_array().unwrap().len(), 1);
+        assert_eq!(body["bids"][0]["price"], 100.0);
+        assert_eq!(body["spread"], 0.5);
+
+        let (_, all) = get_json("/book").await;
+        let (status, body) = get_json("/book?exchanges=").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, all);
+
+        let (status, body) = get_json("/book?exchanges=Kraken").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Unknown exchange 'Kraken'");
+    }
+
+    #[tokio::test]
+    async fn test_get_venue_book() {
+        let (status, body) = get_json("/book/Bitstamp").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["bids"][0]["price"], 99.5);
+        assert_eq!(body["spread"], 1.5);
+
+        let (status, _) = get_json("/book/Kraken").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_get_metrics() {
+        let response = router()
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("# TYPE orderbook_spread_mean gauge"));
+        assert!(body.contains("orderbook_spread_mean{exchange=\"all\",window=\"60s\"} 0"));
+    }
+
+    #[tokio::test]
+    async fn test_get_spread_and_venues() {
+        let (status, body) = get_json("/spread").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["spread"], 0.5);
+        assert_eq!(body["best_bid"]["exchange"], "Binance");
+
+        let (status, body) = get_json("/venues").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[1]["exchange"], "Bitstamp");
+        assert_eq!(body[1]["bid_levels"], 1);
+        assert_eq!(body[1]["best_ask"], 101.0);
+    }
+}
@@ -0,0 +1,118 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The traded pair and how each venue lists it.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Instrument {
+    pub base: String,
+    pub quote: String,
+    /// Price increment; off-grid levels are rounded away from the touch.
+    #[serde(default)]
+    pub tick_size: Option<f64>,
+    /// Size increment; levels are rounded down and dropped below one lot.
+    #[serde(default)]
+    pub lot_size: Option<f64>,
+    /// Venue symbols keyed by exchange name, e.g. `ETH-BTC` or `XETHXXBT`.
+    #[serde(default)]
+    pub symbols: HashMap<String, String>,
+}
+
+impl Instrument {
+    pub fn new(base: &str, quote: &str) -> Self {
+        Self {
+            base: base.to_string(),
+            quote: quote.to_string(),
+            tick_size: None,
+            lot_size: None,
+            symbols: HashMap::new(),
+        }
+    }
+
+    pub fn with_tick_size(mut self, tick_size: f64) -> Self {
+        self.tick_size = Some(tick_size);
+        self
+    }
+
+    pub fn with_lot_size(mut self, lot_size: f64) -> Self {
+        self.lot_size = Some(lot_size);
+        self
+    }
+
+    pub fn with_symbol(mut self, exchange: &str, symbol: &str) -> Self {
+        self.symbols
+            .insert(exchange.to_string(), symbol.to_string());
+        self
+    }
+
+    /// The symbol to subscribe to on `exchange`: its mapping if it has
+    /// one, otherwise base and quote run together in lower case, e.g.
+    /// `ethbtc`.
+    pub fn symbol(&self, exchange: &str) -> String {
+        match self.symbols.get(exchange) {
+            Some(symbol) => symbol.clone(),
+            None => format!("{}{}", self.base, self.quote).to_lowercase(),
+        }
+    }
+}
+
+/// Reads instruments keyed by trading pair from a JSON file.
+pub fn load_instruments<P: AsRef<Path>>(
+    path: P,
+) -> Result<HashMap<String, Instrument>, Box<dyn std::error::Error>> {
+    let instruments_str = fs::read_to_string(path)?;
+    Ok(json5::from_str(&instruments_str)?)
+}
+
+/// `value` on a multiple of `increment`: unchanged if already on the grid,
+/// otherwise rounded down, or up with `round_up`.
+pub fn to_increment(value: f64, increment: f64, round_up: bool) -> f64 {
+    let steps = value / increment;
+    // Venue decimals rarely divide exactly in binary.
+    if (steps - steps.round()).abs() < 1e-9 {
+        return value;
+    }
+    if round_up {
+        steps.ceil() * increment
+    } else {
+        steps.floor() * increment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_defaults_to_pair_name() {
+        let instrument = Instrument::new("ETH", "BTC").with_symbol("Kraken", "XETHXXBT");
+        assert_eq!(instrument.symbol("Kraken"), "XETHXXBT");
+        assert_eq!(instrument.symbol("Binance"), "ethbtc");
+    }
+
+    #[test]
+    fn test_to_increment_keeps_on_grid_values() {
+        assert_eq!(to_increment(0.05617, 0.00001, false), 0.05617);
+        assert!((to_increment(0.056174, 0.00001, false) - 0.05617).abs() < 1e-12);
+        assert!((to_increment(0.056174, 0.00001, true) - 0.05618).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_load_instruments() {
+        let path = std::env::temp_dir().join("orderbooks_test_instruments.json");
+        fs::write(
+            &path,
+            r#"{"ethbtc": {"base": "ETH", "quote": "BTC", "tick_size": 0.00001,
+                "symbols": {"Coinbase": "ETH-BTC"}}}"#,
+        )
+        .unwrap();
+        let instruments = load_instruments(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let ethbtc = &instruments["ethbtc"];
+        assert_eq!(ethbtc.tick_size, Some(0.00001));
+        assert_eq!(ethbtc.lot_size, None);
+        assert_eq!(ethbtc.symbol("Coinbase"), "ETH-BTC");
+    }
+}
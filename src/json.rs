@@ -0,0 +1,62 @@
+use crate::combined_book::CombinedBookSnapshot;
+use crate::exchange::ExchangeOrder;
+use crate::orderbook::{Level, Summary};
+use serde::Serialize;
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct JsonLevel {
+    pub exchange: String,
+    pub price: f64,
+    pub amount: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_price: Option<f64>,
+}
+
+impl From<&ExchangeOrder> for JsonLevel {
+    fn from(order: &ExchangeOrder) -> Self {
+        JsonLevel {
+            exchange: order.exchange.to_string(),
+            price: order.price,
+            amount: order.amount,
+            original_price: order.original_price,
+        }
+    }
+}
+
+impl From<&Level> for JsonLevel {
+    fn from(level: &Level) -> Self {
+        JsonLevel {
+            exchange: level.exchange.clone(),
+            price: level.price,
+            amount: level.amount,
+            original_price: level.original_price,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct JsonBook {
+    pub spread: f64,
+    pub bids: Vec<JsonLevel>,
+    pub asks: Vec<JsonLevel>,
+}
+
+impl From<&CombinedBookSnapshot> for JsonBook {
+    fn from(snapshot: &CombinedBookSnapshot) -> Self {
+        JsonBook {
+            spread: snapshot.spread,
+            bids: snapshot.bids.iter().map(JsonLevel::from).collect(),
+            asks: snapshot.asks.iter().map(JsonLevel::from).collect(),
+        }
+    }
+}
+
+impl From<&Summary> for JsonBook {
+    fn from(summary: &Summary) -> Self {
+        JsonBook {
+            spread: summary.spread,
+            bids: summary.bids.iter().map(JsonLevel::from).collect(),
+            asks: summary.asks.iter().map(JsonLevel::from).collect(),
+        }
+    }
+}
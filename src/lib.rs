@@ -0,0 +1,59 @@
+//! Aggregates order books from several venues into one combined book.
+//!
+//! Embedding the aggregator takes an [`OrderbookProcessor`] and a snapshot
+//! subscription:
+//!
+//! ```no_run
+//! use orderbooks::OrderbookProcessor;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let processor = OrderbookProcessor::builder("ethbtc", 10)
+//!     .with_exchange("Binance")
+//!     .with_exchange("Bitstamp")
+//!     .build()?;
+//! let mut snapshots = processor.subscribe();
+//! tokio::spawn(processor.run());
+//!
+//! while snapshots.changed().await.is_ok() {
+//!     println!("spread: {}", snapshots.borrow().spread);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Venues are built from a registry keyed by name. Add your own with
+//! [`exchange::register_exchange`], typically as a [`exchange::ws_exchange::WsExchange`]
+//! over a [`VenueProtocol`], or pass any [`ExchangeStream`] straight to
+//! [`OrderbookProcessorBuilder::with_exchange_stream`].
+//!
+//! [`server::run`] serves the gRPC, WebSocket and REST endpoints the binary
+//! exposes.
+
+pub mod analytics;
+pub mod candles;
+pub mod combined_book;
+pub mod config;
+pub mod exchange;
+pub mod feed_stats;
+pub mod grpc;
+pub mod http;
+pub mod instrument;
+pub mod json;
+pub mod liquidity_stats;
+pub mod multiplexer;
+pub mod orderbook_processor;
+pub mod paper_trading;
+pub mod routing;
+pub mod server;
+pub mod subscription;
+pub mod trade_processor;
+pub mod websocket;
+pub mod orderbook {
+    tonic::include_proto!("orderbook");
+}
+
+pub use combined_book::CombinedBookSnapshot;
+pub use exchange::ws_exchange::VenueProtocol;
+pub use exchange::{Exchange, ExchangeError, ExchangeStream, Orderbook, Trade, TradeStream};
+pub use orderbook_processor::{OrderbookProcessor, OrderbookProcessorBuilder};
+pub use trade_processor::TradeProcessor;
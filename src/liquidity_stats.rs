@@ -0,0 +1,428 @@
+use crate::combined_book::CombinedBookSnapshot;
+use crate::exchange::{unix_time_us, Exchange};
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Windows kept by default: one minute, five minutes and an hour.
+pub const DEFAULT_WINDOWS: [Duration; 3] = [
+    Duration::from_secs(60),
+    Duration::from_secs(300),
+    Duration::from_secs(3600),
+];
+
+/// Time is accumulated in buckets of this width, so windows are accurate
+/// to the bucket.
+const BUCKET_US: u64 = 1_000_000;
+
+/// Time-weighted top-of-book figures for one venue, or all venues, over a
+/// rolling window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiquiditySummary {
+    /// `None` for the consolidated book.
+    pub exchange: Option<Exchange>,
+    pub window: Duration,
+    /// How long the venue quoted both sides within the window.
+    pub observed: Duration,
+    pub mean_spread: f64,
+    pub min_spread: f64,
+    pub max_spread: f64,
+    pub mean_bid_size: f64,
+    pub mean_ask_size: f64,
+    /// Share of the time the consolidated book was two-sided that this
+    /// venue's best bid matched the best bid. Venues tied at the best
+    /// price each get the credit, so shares can sum past one.
+    pub best_bid_share: f64,
+    pub best_ask_share: f64,
+}
+
+/// The top of one venue's book, held from `time_us` until the next update.
+#[derive(Debug, Clone, Copy)]
+struct Top {
+    time_us: u64,
+    spread: f64,
+    bid_size: f64,
+    ask_size: f64,
+    at_best_bid: bool,
+    at_best_ask: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    start_us: u64,
+    observed_us: u64,
+    spread_us: f64,
+    bid_size_us: f64,
+    ask_size_us: f64,
+    best_bid_us: u64,
+    best_ask_us: u64,
+    min_spread: f64,
+    max_spread: f64,
+}
+
+impl Bucket {
+    fn new(start_us: u64) -> Self {
+        Bucket {
+            start_us,
+            observed_us: 0,
+            spread_us: 0.0,
+            bid_size_us: 0.0,
+            ask_size_us: 0.0,
+            best_bid_us: 0,
+            best_ask_us: 0,
+            min_spread: f64::INFINITY,
+            max_spread: f64::NEG_INFINITY,
+        }
+    }
+}
+
+struct VenueWindow {
+    exchange: Option<Exchange>,
+    top: Option<Top>,
+    buckets: VecDeque<Bucket>,
+}
+
+impl VenueWindow {
+    /// Credits the current top with the time until `now_us`.
+    fn accrue(&mut self, now_us: u64, retain_us: u64) {
+        if let Some(top) = &mut self.top {
+            let mut from_us = top.time_us;
+            while from_us < now_us {
+                let start_us = from_us - from_us % BUCKET_US;
+                let to_us = now_us.min(start_us + BUCKET_US);
+                if self.buckets.back().is_none_or(|b| b.start_us != start_us) {
+                    self.buckets.push_back(Bucket::new(start_us));
+                }
+                let bucket = self.buckets.back_mut().expect("bucket was just added");
+                let elapsed_us = to_us - from_us;
+                bucket.observed_us += elapsed_us;
+                bucket.spread_us += top.spread * elapsed_us as f64;
+                bucket.bid_size_us += top.bid_size * elapsed_us as f64;
+                bucket.ask_size_us += top.ask_size * elapsed_us as f64;
+                if top.at_best_bid {
+                    bucket.best_bid_us += elapsed_us;
+                }
+                if top.at_best_ask {
+                    bucket.best_ask_us += elapsed_us;
+                }
+                bucket.min_spread = bucket.min_spread.min(top.spread);
+                bucket.max_spread = bucket.max_spread.max(top.spread);
+                from_us = to_us;
+            }
+            top.time_us = top.time_us.max(now_us);
+        }
+        while self
+            .buckets
+            .front()
+            .is_some_and(|b| b.start_us + BUCKET_US + retain_us <= now_us)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    fn totals(&self, now_us: u64, window: Duration) -> Bucket {
+        let since_us = now_us.saturating_sub(window.as_micros() as u64);
+        let mut total = Bucket::new(since_us);
+        for bucket in self
+            .buckets
+            .iter()
+            .filter(|b| b.start_us + BUCKET_US > since_us)
+        {
+            total.observed_us += bucket.observed_us;
+            total.spread_us += bucket.spread_us;
+            total.bid_size_us += bucket.bid_size_us;
+            total.ask_size_us += bucket.ask_size_us;
+            total.best_bid_us += bucket.best_bid_us;
+            total.best_ask_us += bucket.best_ask_us;
+            total.min_spread = total.min_spread.min(bucket.min_spread);
+            total.max_spread = total.max_spread.max(bucket.max_spread);
+        }
+        total
+    }
+}
+
+struct LiquidityStatsInner {
+    windows: Vec<Duration>,
+    /// The consolidated book first, then venues in order of appearance.
+    venues: Vec<VenueWindow>,
+}
+
+impl LiquidityStatsInner {
+    fn retain_us(&self) -> u64 {
+        self.windows
+            .iter()
+            .map(|w| w.as_micros() as u64)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn accrue(&mut self, now_us: u64) {
+        let retain_us = self.retain_us();
+        for venue in &mut self.venues {
+            venue.accrue(now_us, retain_us);
+        }
+    }
+}
+
+/// Rolling spread, top-of-book size and time-at-best share per venue and
+/// for the consolidated book, recorded from each venue's best levels (see
+/// [`OrderbookProcessor::subscribe_venue_tops`]). Cheap to clone.
+///
+/// [`OrderbookProcessor::subscribe_venue_tops`]: crate::orderbook_processor::OrderbookProcessor::subscribe_venue_tops
+#[derive(Clone)]
+pub struct LiquidityStats {
+    inner: Arc<Mutex<LiquidityStatsInner>>,
+}
+
+impl Default for LiquidityStats {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOWS.to_vec())
+    }
+}
+
+impl LiquidityStats {
+    pub fn new(windows: Vec<Duration>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(LiquidityStatsInner {
+                windows,
+                venues: vec![VenueWindow {
+                    exchange: None,
+                    top: None,
+                    buckets: VecDeque::new(),
+                }],
+            })),
+        }
+    }
+
+    /// Records the top of the book from `time_us` on. A venue's first level
+    /// on each side is taken as its best, so `snapshot` must not leave out
+    /// venues the way a truncated merged view can. Venues without both
+    /// sides are not counted until they quote again.
+    pub fn record(&self, snapshot: &CombinedBookSnapshot, time_us: u64) {
+        let mut inner = self.inner.lock().expect("liquidity stats lock poisoned");
+        inner.accrue(time_us);
+
+        for order in snapshot.bids.iter().chain(&snapshot.asks) {
+            if !inner
+                .venues
+                .iter()
+                .any(|v| v.exchange == Some(order.exchange))
+            {
+                inner.venues.push(VenueWindow {
+                    exchange: Some(order.exchange),
+                    top: None,
+                    buckets: VecDeque::new(),
+                });
+            }
+        }
+
+        let best_bid = snapshot.bids.first().map(|o| o.price);
+        let best_ask = snapshot.asks.first().map(|o| o.price);
+        for venue in &mut inner.venues {
+            let bid = snapshot
+                .bids
+                .iter()
+                .find(|o| venue.exchange.is_none_or(|e| o.exchange == e));
+            let ask = snapshot
+                .asks
+                .iter()
+                .find(|o| venue.exchange.is_none_or(|e| o.exchange == e));
+            venue.top = match (bid, ask) {
+                (Some(bid), Some(ask)) => Some(Top {
+                    time_us,
+                    spread: ask.price - bid.price,
+                    bid_size: bid.amount,
+                    ask_size: ask.amount,
+                    at_best_bid: best_bid == Some(bid.price),
+                    at_best_ask: best_ask == Some(ask.price),
+                }),
+                _ => None,
+            };
+        }
+    }
+
+    /// Summaries for every window, consolidated book first, up to `now_us`.
+    pub fn summaries(&self, now_us: u64) -> Vec<LiquiditySummary> {
+        let mut inner = self.inner.lock().expect("liquidity stats lock poisoned");
+        inner.accrue(now_us);
+
+        let mut summaries = Vec::new();
+        for &window in &inner.windows {
+            let consolidated_us = inner.venues[0].totals(now_us, window).observed_us;
+            for venue in &inner.venues {
+                let totals = venue.totals(now_us, window);
+                let mean = |sum: f64| {
+                    if totals.observed_us == 0 {
+                        0.0
+                    } else {
+                        sum / totals.observed_us as f64
+                    }
+                };
+                let share = |us: u64| {
+                    if consolidated_us == 0 {
+                        0.0
+                    } else {
+                        us as f64 / consolidated_us as f64
+                    }
+                };
+                let observed = totals.observed_us > 0;
+                summaries.push(LiquiditySummary {
+                    exchange: venue.exchange,
+                    window,
+                    observed: Duration::from_micros(totals.observed_us),
+                    mean_spread: mean(totals.spread_us),
+                    min_spread: if observed { totals.min_spread } else { 0.0 },
+                    max_spread: if observed { totals.max_spread } else { 0.0 },
+                    mean_bid_size: mean(totals.bid_size_us),
+                    mean_ask_size: mean(totals.ask_size_us),
+                    best_bid_share: share(totals.best_bid_us),
+                    best_ask_share: share(totals.best_ask_us),
+                });
+            }
+        }
+        summaries
+    }
+
+    /// The summaries as Prometheus text exposition, one gauge per figure
+    /// labelled by exchange (`all` for the consolidated book) and window.
+    pub fn render_metrics(summaries: &[LiquiditySummary]) -> String {
+        type Figure = fn(&LiquiditySummary) -> f64;
+        let figures: [(&str, &str, Figure); 7] = [
+            ("spread_mean", "Time-weighted mean spread", |s| {
+                s.mean_spread
+            }),
+            ("spread_min", "Narrowest spread", |s| s.min_spread),
+            ("spread_max", "Widest spread", |s| s.max_spread),
+            (
+                "top_bid_size_mean",
+                "Time-weighted mean best bid size",
+                |s| s.mean_bid_size,
+            ),
+            (
+                "top_ask_size_mean",
+                "Time-weighted mean best ask size",
+                |s| s.mean_ask_size,
+            ),
+            (
+                "best_bid_share",
+                "Share of time at the consolidated best bid",
+                |s| s.best_bid_share,
+            ),
+            (
+                "best_ask_share",
+                "Share of time at the consolidated best ask",
+                |s| s.best_ask_share,
+            ),
+        ];
+
+        let mut text = String::new();
+        for (name, help, figure) in figures {
+            let _ = writeln!(text, "# HELP orderbook_{} {}", name, help);
+            let _ = writeln!(text, "# TYPE orderbook_{} gauge", name);
+            for summary in summaries {
+                let exchange = summary.exchange.map_or("all", |e| e.name());
+                let _ = writeln!(
+                    text,
+                    "orderbook_{}{{exchange=\"{}\",window=\"{}s\"}} {}",
+                    name,
+                    exchange,
+                    summary.window.as_secs(),
+                    figure(summary)
+                );
+            }
+        }
+        text
+    }
+
+    /// Records every change to the venues' tops until the processor goes
+    /// away.
+    pub async fn run(self, mut snapshots: watch::Receiver<CombinedBookSnapshot>) {
+        while snapshots.changed().await.is_ok() {
+            let snapshot = snapshots.borrow_and_update().clone();
+            self.record(&snapshot, unix_time_us());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::ExchangeOrder;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn summary(summaries: &[LiquiditySummary], exchange: Option<Exchange>) -> &LiquiditySummary {
+        summaries.iter().find(|s| s.exchange == exchange).unwrap()
+    }
+
+    #[test]
+    fn test_time_weights_spread_size_and_best_share() {
+        let stats = LiquidityStats::new(vec![MINUTE]);
+        // Binance holds the best bid for 3s, then Bitstamp takes it for 1s.
+        stats.record(
+            &CombinedBookSnapshot {
+                spread: 1.0,
+                bids: vec![
+                    ExchangeOrder::new(Exchange::BINANCE, 100.0, 2.0),
+                    ExchangeOrder::new(Exchange::BITSTAMP, 99.0, 1.0),
+                ],
+                asks: vec![
+                    ExchangeOrder::new(Exchange::BINANCE, 101.0, 2.0),
+                    ExchangeOrder::new(Exchange::BITSTAMP, 102.0, 1.0),
+                ],
+            },
+            10_000_000,
+        );
+        stats.record(
+            &CombinedBookSnapshot {
+                spread: 0.5,
+                bids: vec![
+                    ExchangeOrder::new(Exchange::BITSTAMP, 100.5, 4.0),
+                    ExchangeOrder::new(Exchange::BINANCE, 100.0, 2.0),
+                ],
+                asks: vec![
+                    ExchangeOrder::new(Exchange::BINANCE, 101.0, 2.0),
+                    ExchangeOrder::new(Exchange::BITSTAMP, 102.0, 1.0),
+                ],
+            },
+            13_000_000,
+        );
+        let summaries = stats.summaries(14_000_000);
+
+        let all = summary(&summaries, None);
+        assert_eq!(all.observed, Duration::from_secs(4));
+        assert_eq!(all.mean_spread, (1.0 * 3.0 + 0.5) / 4.0);
+        assert_eq!((all.min_spread, all.max_spread), (0.5, 1.0));
+        assert_eq!(all.mean_bid_size, (2.0 * 3.0 + 4.0) / 4.0);
+
+        let binance = summary(&summaries, Some(Exchange::BINANCE));
+        assert_eq!(binance.best_bid_share, 0.75);
+        assert_eq!(binance.best_ask_share, 1.0);
+        let bitstamp = summary(&summaries, Some(Exchange::BITSTAMP));
+        assert_eq!(bitstamp.best_bid_share, 0.25);
+        assert_eq!(bitstamp.mean_spread, (3.0 * 3.0 + 1.5) / 4.0);
+    }
+
+    #[test]
+    fn test_old_time_leaves_the_window() {
+        let stats = LiquidityStats::new(vec![Duration::from_secs(2)]);
+        let book = |amount| CombinedBookSnapshot {
+            spread: 1.0,
+            bids: vec![ExchangeOrder::new(Exchange::BINANCE, 100.0, amount)],
+            asks: vec![ExchangeOrder::new(Exchange::BINANCE, 101.0, amount)],
+        };
+        stats.record(&book(1.0), 0);
+        stats.record(&book(5.0), 10_000_000);
+        let summaries = stats.summaries(12_000_000);
+
+        let all = summary(&summaries, None);
+        assert_eq!(all.observed, Duration::from_secs(2));
+        assert_eq!(all.mean_bid_size, 5.0);
+
+        let metrics = LiquidityStats::render_metrics(&summaries);
+        assert!(metrics.contains("orderbook_top_bid_size_mean{exchange=\"all\",window=\"2s\"} 5"));
+        assert!(metrics.contains("orderbook_best_bid_share{exchange=\"Binance\",window=\"2s\"} 1"));
+    }
+}
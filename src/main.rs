@@ -1,63 +1,35 @@
-pub mod combined_book;
-pub mod config;
-pub mod exchange;
-pub mod grpc;
-pub mod orderbook_processor;
-pub mod orderbook {
-    tonic::include_proto!("orderbook");
-}
-use config::load_config;
-use grpc::orderbook_service::OrderbookService;
-use orderbook::orderbook_aggregator_server::OrderbookAggregatorServer;
-use orderbook_processor::OrderbookProcessor;
-use tonic::transport::Server;
+use orderbooks::config::load_config;
+use orderbooks::server;
+use std::process::ExitCode;
 use tracing::{debug, error, info};
-use tracing_subscriber;
 
-const GRPC_SERVER_ADDR: &str = "127.0.0.1:50051";
+const DEFAULT_CONFIG_PATH: &str = "config/config.json5";
 
+/// Usage: `orderbooks [config path]`.
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     tracing_subscriber::fmt::init();
 
-    info!("Starting the application, loading configuration file");
-    let config = match load_config("config/config.json5") {
+    let config_path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string());
+    info!("Starting the application, loading {}", config_path);
+    let config = match load_config(&config_path) {
         Ok(config) => {
             debug!("Configuration loaded: {:?}", config);
             config
         }
         Err(err) => {
             error!("Failed to load config file: {:?}", err);
-            return;
+            return ExitCode::FAILURE;
         }
     };
 
-    info!("Creating orderbook processor");
-    let mut orderbook_processor = OrderbookProcessor::new(config);
-
-    info!("Creating orderbook receiver");
-    let receiver = orderbook_processor.subscribe();
-
-    info!("Spawning orderbook processor drive loop..");
-    tokio::spawn(async move {
-        orderbook_processor
-            .initialise_exchanges()
-            .await
-            .expect("Error initializing exhanges");
-        orderbook_processor.drive_and_broadcast().await;
-    });
-
-    info!("Creating orderbook service");
-    let orderbook_service = OrderbookService::new(receiver);
-
-    info!("Setting up gRPC service listening on {}", GRPC_SERVER_ADDR);
-    if let Err(err) = Server::builder()
-        .add_service(OrderbookAggregatorServer::new(orderbook_service))
-        .serve(GRPC_SERVER_ADDR.parse().unwrap())
-        .await
-    {
-        error!("Error running gRPC server: {:?}", err);
+    if let Err(err) = server::run(&config).await {
+        error!("Error running the aggregator: {}", err);
+        return ExitCode::FAILURE;
     }
 
     info!("Shutting down");
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,193 @@
+use futures_util::stream::Stream;
+use futures_util::StreamExt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+#[derive(Debug, PartialEq)]
+pub enum MultiplexerEvent<K, T> {
+    /// An item produced by the stream registered under `K`.
+    Item(K, T),
+    /// The stream registered under `K` finished and has been removed.
+    Ended(K),
+}
+
+/// Polls a keyed set of streams fairly. Polling starts from the stream after
+/// the one that produced the previous item, so a busy venue cannot starve
+/// the others. Streams can be inserted and removed between polls.
+///
+/// Like `StreamMap`, the multiplexer yields `None` while it is empty.
+pub struct StreamMultiplexer<K, S> {
+    streams: Vec<(K, S)>,
+    next: usize,
+}
+
+impl<K, S> Default for StreamMultiplexer<K, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, S> StreamMultiplexer<K, S> {
+    pub fn new() -> Self {
+        Self {
+            streams: Vec::new(),
+            next: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.streams.iter().map(|(key, _)| key)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut S)> {
+        self.streams.iter_mut().map(|(key, stream)| (&*key, stream))
+    }
+}
+
+impl<K: PartialEq, S> StreamMultiplexer<K, S> {
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.streams.iter().any(|(k, _)| k == key)
+    }
+
+    /// Registers `stream` under `key`, returning the stream it replaced.
+    pub fn insert(&mut self, key: K, stream: S) -> Option<S> {
+        match self.streams.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => Some(std::mem::replace(existing, stream)),
+            None => {
+                self.streams.push((key, stream));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<S> {
+        let index = self.streams.iter().position(|(k, _)| k == key)?;
+        Some(self.remove_at(index).1)
+    }
+
+    fn remove_at(&mut self, index: usize) -> (K, S) {
+        let removed = self.streams.remove(index);
+        if index < self.next {
+            self.next -= 1;
+        }
+        if self.next >= self.streams.len() {
+            self.next = 0;
+        }
+        removed
+    }
+}
+
+impl<K, S> Stream for StreamMultiplexer<K, S>
+where
+    K: PartialEq + Clone + Unpin,
+    S: Stream + Unpin,
+{
+    type Item = MultiplexerEvent<K, S::Item>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let len = this.streams.len();
+        if len == 0 {
+            return Poll::Ready(None);
+        }
+
+        for offset in 0..len {
+            let index = (this.next + offset) % len;
+            let (key, stream) = &mut this.streams[index];
+            match stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(item)) => {
+                    let key = key.clone();
+                    this.next = (index + 1) % len;
+                    return Poll::Ready(Some(MultiplexerEvent::Item(key, item)));
+                }
+                Poll::Ready(None) => {
+                    let (key, _) = this.remove_at(index);
+                    return Poll::Ready(Some(MultiplexerEvent::Ended(key)));
+                }
+                Poll::Pending => {}
+            }
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::stream::{self, BoxStream};
+
+    fn repeat(value: u32) -> BoxStream<'static, u32> {
+        stream::repeat(value).boxed()
+    }
+
+    #[tokio::test]
+    async fn test_round_robin_between_ready_streams() {
+        let mut multiplexer = StreamMultiplexer::new();
+        multiplexer.insert("a", repeat(1));
+        multiplexer.insert("b", repeat(2));
+        multiplexer.insert("c", repeat(3));
+
+        let keys: Vec<_> = multiplexer
+            .by_ref()
+            .take(6)
+            .map(|event| match event {
+                MultiplexerEvent::Item(key, _) => key,
+                MultiplexerEvent::Ended(key) => panic!("{} ended", key),
+            })
+            .collect()
+            .await;
+
+        assert_eq!(keys, vec!["a", "b", "c", "a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_reports_ended_streams() {
+        let mut multiplexer = StreamMultiplexer::new();
+        multiplexer.insert("short", stream::iter(vec![1]).boxed());
+        multiplexer.insert("long", stream::iter(vec![10, 20]).boxed());
+
+        let events: Vec<_> = multiplexer.collect().await;
+
+        assert_eq!(
+            events,
+            vec![
+                MultiplexerEvent::Item("short", 1),
+                MultiplexerEvent::Item("long", 10),
+                MultiplexerEvent::Ended("short"),
+                MultiplexerEvent::Item("long", 20),
+                MultiplexerEvent::Ended("long"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_insert_and_remove() {
+        let mut multiplexer = StreamMultiplexer::new();
+        assert!(multiplexer.insert("a", repeat(1)).is_none());
+        assert!(multiplexer.insert("b", repeat(2)).is_none());
+        assert!(multiplexer.insert("a", repeat(3)).is_some());
+        assert_eq!(multiplexer.len(), 2);
+
+        assert_eq!(
+            multiplexer.next().await,
+            Some(MultiplexerEvent::Item("a", 3))
+        );
+        assert!(multiplexer.remove(&"b").is_some());
+        assert!(!multiplexer.contains_key(&"b"));
+        assert_eq!(
+            multiplexer.next().await,
+            Some(MultiplexerEvent::Item("a", 3))
+        );
+
+        assert!(multiplexer.remove(&"a").is_some());
+        assert_eq!(multiplexer.next().await, None);
+    }
+}
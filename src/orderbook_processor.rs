@@ -1,51 +1,225 @@
 use crate::combined_book::{CombinedBook, CombinedBookSnapshot};
-use crate::config::Config;
-use crate::exchange::{instantiate_exchange_websocket, ExchangeError, ExchangeStream};
+use crate::config::{Config, HeartbeatConfig, VenueConfig};
+use crate::exchange::conversion::QuoteConversion;
+use crate::exchange::synthetic::register_synthetic;
+use crate::exchange::{instantiate_exchange_websocket, Exchange, ExchangeError, ExchangeStream};
+use crate::feed_stats::{FeedStats, DEFAULT_WINDOW};
+use crate::instrument::Instrument;
+use crate::multiplexer::{MultiplexerEvent, StreamMultiplexer};
 use futures_util::stream::Stream;
 use futures_util::StreamExt;
+use std::collections::HashMap;
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use tokio::sync::watch;
-use tokio_stream::StreamMap;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::time::{interval, MissedTickBehavior};
 use tracing::{debug, info, warn};
 
+/// How often per-venue feed statistics are logged.
+const FEED_STATS_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Merges venue feeds into one book and broadcasts a snapshot whenever its
+/// top levels change. Build one with [`OrderbookProcessor::builder`] and
+/// read snapshots from [`OrderbookProcessor::subscribe`].
 pub struct OrderbookProcessor {
-    exchanges: Vec<Box<dyn ExchangeStream>>,
+    exchanges: StreamMultiplexer<Exchange, Box<dyn ExchangeStream>>,
     combined_book: crate::combined_book::CombinedBook,
     snapshot_sender: watch::Sender<CombinedBookSnapshot>,
+    venue_tops_sender: watch::Sender<CombinedBookSnapshot>,
+    feed_stats: FeedStats,
+    commands: mpsc::UnboundedReceiver<ProcessorCommand>,
+    /// Holds the command channel open until the feeds run out, after which
+    /// only handles do.
+    command_sender: Option<mpsc::UnboundedSender<ProcessorCommand>>,
+    weak_command_sender: mpsc::WeakUnboundedSender<ProcessorCommand>,
 }
 
-impl OrderbookProcessor {
-    pub fn new(config: Config) -> Self {
-        let initial_snapshot = CombinedBookSnapshot::default(); // Ensure CombinedBookSnapshot implements Default
-        let (snapshot_sender, _) = watch::channel(initial_snapshot);
-
-        let mut exchanges = Vec::new();
-        for exchange_name in config.exchanges {
-            match instantiate_exchange_websocket(
-                &exchange_name,
-                &config.trading_pair,
-                config.max_orders,
-            ) {
-                Ok(websocket) => exchanges.push(websocket),
-                Err(e) => {
-                    panic!(
-                        "Error instantiating websocket for '{}': {:?}",
-                        exchange_name, e
-                    );
-                }
+/// A change to the set of venues, applied by the running processor.
+pub enum ProcessorCommand {
+    /// An initialised feed, replacing any feed for the same exchange.
+    Add(Box<dyn ExchangeStream>),
+    Remove(Exchange),
+}
+
+/// Adds and removes venues on a running [`OrderbookProcessor`], which
+/// keeps running without feeds while any handle is alive. Cheap to clone.
+#[derive(Clone)]
+pub struct ProcessorHandle {
+    commands: mpsc::UnboundedSender<ProcessorCommand>,
+}
+
+impl ProcessorHandle {
+    /// Starts merging an already initialised venue feed.
+    pub fn add_exchange(&self, exchange: Box<dyn ExchangeStream>) -> Result<(), ExchangeError> {
+        self.send(ProcessorCommand::Add(exchange))
+    }
+
+    /// Stops consuming a venue feed and publishes the book without it.
+    pub fn remove_exchange(&self, exchange: Exchange) -> Result<(), ExchangeError> {
+        self.send(ProcessorCommand::Remove(exchange))
+    }
+
+    fn send(&self, command: ProcessorCommand) -> Result<(), ExchangeError> {
+        self.commands
+            .send(command)
+            .map_err(|_| ExchangeError::Unknown("Orderbook processor has stopped".to_string()))
+    }
+}
+
+/// Collects the venues and book settings for an [`OrderbookProcessor`].
+pub struct OrderbookProcessorBuilder {
+    trading_pair: String,
+    max_orders: usize,
+    exchanges: Vec<String>,
+    heartbeats: HashMap<String, HeartbeatConfig>,
+    venues: HashMap<String, VenueConfig>,
+    instrument: Option<Instrument>,
+    streams: Vec<Box<dyn ExchangeStream>>,
+    feed_stats_window: usize,
+}
+
+impl OrderbookProcessorBuilder {
+    pub fn new(trading_pair: &str, max_orders: usize) -> Self {
+        Self {
+            trading_pair: trading_pair.to_string(),
+            max_orders,
+            exchanges: Vec::new(),
+            heartbeats: HashMap::new(),
+            venues: HashMap::new(),
+            instrument: None,
+            streams: Vec::new(),
+            feed_stats_window: DEFAULT_WINDOW,
+        }
+    }
+
+    /// Adds a venue from the exchange registry by name.
+    pub fn with_exchange(mut self, name: &str) -> Self {
+        self.exchanges.push(name.to_string());
+        self
+    }
+
+    /// Overrides a registry venue's heartbeat defaults.
+    pub fn with_heartbeat(mut self, name: &str, heartbeat: HeartbeatConfig) -> Self {
+        self.heartbeats.insert(name.to_string(), heartbeat);
+        self
+    }
+
+    /// Sets a registry venue's quote conversion.
+    pub fn with_venue(mut self, name: &str, venue: VenueConfig) -> Self {
+        self.venues.insert(name.to_string(), venue);
+        self
+    }
+
+    /// Maps registry venues to their own symbols for the pair, and rounds
+    /// and validates levels against its tick and lot sizes.
+    pub fn with_instrument(mut self, instrument: Instrument) -> Self {
+        self.instrument = Some(instrument);
+        self
+    }
+
+    /// Adds a feed built outside the registry. It is initialised along
+    /// with the registry venues.
+    pub fn with_exchange_stream(mut self, stream: Box<dyn ExchangeStream>) -> Self {
+        self.streams.push(stream);
+        self
+    }
+
+    /// Latency samples kept per venue for feed statistics.
+    pub fn with_feed_stats_window(mut self, window: usize) -> Self {
+        self.feed_stats_window = window;
+        self
+    }
+
+    /// Instantiates the registry venues, failing on unknown names.
+    pub fn build(self) -> Result<OrderbookProcessor, ExchangeError> {
+        let (snapshot_sender, _) = watch::channel(CombinedBookSnapshot::default());
+
+        let mut exchanges = StreamMultiplexer::new();
+        let default_heartbeat = HeartbeatConfig::default();
+        let heartbeat = |name: &str| self.heartbeats.get(name).unwrap_or(&default_heartbeat);
+        for exchange_name in &self.exchanges {
+            let venue = self.venues.get(exchange_name);
+            let symbol = match &self.instrument {
+                Some(instrument) => instrument.symbol(exchange_name),
+                None => self.trading_pair.clone(),
+            };
+            let mut websocket = instantiate_exchange_websocket(
+                exchange_name,
+                &symbol,
+                self.max_orders,
+                heartbeat(exchange_name),
+            )?;
+            if let Some(conversion) = venue.and_then(|venue| venue.quote_conversion.as_ref()) {
+                let rate = instantiate_exchange_websocket(
+                    &conversion.exchange,
+                    &conversion.trading_pair,
+                    1,
+                    heartbeat(&conversion.exchange),
+                )?;
+                websocket = Box::new(QuoteConversion::new(websocket, rate, conversion.invert));
             }
+            exchanges.insert(websocket.get_exchange(), websocket);
+        }
+        for stream in self.streams {
+            exchanges.insert(stream.get_exchange(), stream);
         }
 
-        Self {
+        let mut combined_book = CombinedBook::new(self.max_orders);
+        if let Some(instrument) = &self.instrument {
+            combined_book = combined_book.with_instrument(instrument);
+        }
+        let (command_sender, commands) = mpsc::unbounded_channel();
+        Ok(OrderbookProcessor {
             exchanges,
-            combined_book: CombinedBook::new(config.max_orders),
+            combined_book,
             snapshot_sender,
+            venue_tops_sender: watch::channel(CombinedBookSnapshot::default()).0,
+            feed_stats: FeedStats::new(self.feed_stats_window),
+            commands,
+            weak_command_sender: command_sender.downgrade(),
+            command_sender: Some(command_sender),
+        })
+    }
+}
+
+impl OrderbookProcessor {
+    pub fn builder(trading_pair: &str, max_orders: usize) -> OrderbookProcessorBuilder {
+        OrderbookProcessorBuilder::new(trading_pair, max_orders)
+    }
+
+    /// Builds the processor for the venues and settings in `config`,
+    /// registering its synthetic venues.
+    pub fn from_config(config: &Config) -> Result<Self, ExchangeError> {
+        let mut builder = Self::builder(&config.trading_pair, config.max_orders);
+        for exchange_name in &config.exchanges {
+            builder = builder.with_exchange(exchange_name);
+        }
+        for synthetic in &config.synthetics {
+            builder = builder.with_exchange(register_synthetic(synthetic).name());
+        }
+        for (exchange_name, heartbeat) in &config.heartbeats {
+            builder = builder.with_heartbeat(exchange_name, heartbeat.clone());
         }
+        if let Some(instrument) = &config.instrument {
+            builder = builder.with_instrument(instrument.clone());
+        }
+        for (exchange_name, venue) in &config.venues {
+            builder = builder.with_venue(exchange_name, venue.clone());
+        }
+        builder.build()
+    }
+
+    /// Connects every venue, then merges and broadcasts until all feeds
+    /// end and no handle is left to add more.
+    pub async fn run(mut self) -> Result<(), ExchangeError> {
+        self.initialise_exchanges().await?;
+        self.drive_and_broadcast().await;
+        Ok(())
     }
 
     pub async fn initialise_exchanges(&mut self) -> Result<(), ExchangeError> {
-        for exchange in &mut self.exchanges {
+        for (_, exchange) in self.exchanges.iter_mut() {
             info!("initialising exchange ws: {}", exchange.get_exchange());
             if let Err(err) = exchange.initialise().await {
                 return Err(ExchangeError::Unknown(format!(
@@ -58,16 +232,80 @@ impl OrderbookProcessor {
         Ok(())
     }
 
+    /// Adds a venue feed, replacing any existing feed for the same exchange.
+    /// The feed must already be initialised.
+    pub fn add_exchange(&mut self, exchange: Box<dyn ExchangeStream>) {
+        info!("Adding exchange stream: {}", exchange.get_exchange());
+        self.exchanges.insert(exchange.get_exchange(), exchange);
+    }
+
+    /// Stops consuming a venue feed and drops its levels from the book.
+    pub fn remove_exchange(&mut self, exchange: &Exchange) -> Option<Box<dyn ExchangeStream>> {
+        info!("Removing exchange stream: {}", exchange);
+        self.combined_book.remove_exchange(exchange);
+        self.exchanges.remove(exchange)
+    }
+
     pub async fn drive_and_broadcast(mut self) {
-        while let Some(result) = self.next().await {
-            match result {
-                Ok(snapshot) => {
-                    debug!("Sending combined book to subscribers");
-                    self.send_snapshot_update(snapshot);
-                }
-                Err(e) => {
-                    warn!("Error processing snapshot: {:?}", e);
-                }
+        let mut log_interval = interval(FEED_STATS_LOG_INTERVAL);
+        log_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        log_interval.reset();
+        loop {
+            tokio::select! {
+                result = self.next() => match result {
+                    Some(Ok(snapshot)) => {
+                        debug!("Sending combined book to subscribers");
+                        self.send_snapshot_update(snapshot);
+                    }
+                    Some(Err(e)) => {
+                        warn!("Error processing snapshot: {:?}", e);
+                    }
+                    None => break,
+                },
+                _ = log_interval.tick() => self.log_feed_stats(),
+            }
+        }
+    }
+
+    /// Changes the venues once the processor is running, e.g. after
+    /// [`OrderbookProcessor::drive_and_broadcast`] has taken it.
+    pub fn handle(&self) -> ProcessorHandle {
+        ProcessorHandle {
+            // A stopped processor hands out a handle whose commands fail.
+            commands: self
+                .weak_command_sender
+                .upgrade()
+                .unwrap_or_else(|| mpsc::unbounded_channel().0),
+        }
+    }
+
+    /// Per-venue latency and sequence statistics, shared with the caller.
+    pub fn feed_stats(&self) -> FeedStats {
+        self.feed_stats.clone()
+    }
+
+    fn log_feed_stats(&self) {
+        for venue in self.feed_stats.venues() {
+            match venue.latency {
+                Some(latency) => info!(
+                    exchange = %venue.exchange,
+                    messages = venue.messages,
+                    out_of_order = venue.out_of_order,
+                    sequence_gaps = venue.sequence_gaps,
+                    p50_us = latency.p50_us,
+                    p99_us = latency.p99_us,
+                    max_us = latency.max_us,
+                    clock_skew_us = latency.clock_skew_us,
+                    "Feed stats"
+                ),
+                None => info!(
+                    exchange = %venue.exchange,
+                    messages = venue.messages,
+                    last_sequence = ?venue.last_sequence,
+                    out_of_order = venue.out_of_order,
+                    sequence_gaps = venue.sequence_gaps,
+                    "Feed stats"
+                ),
             }
         }
     }
@@ -77,6 +315,23 @@ impl OrderbookProcessor {
         self.snapshot_sender.subscribe()
     }
 
+    /// Each venue's best bid and ask, whether or not they make the merged
+    /// view, updated whenever one moves.
+    pub fn subscribe_venue_tops(&self) -> watch::Receiver<CombinedBookSnapshot> {
+        self.venue_tops_sender.subscribe()
+    }
+
+    fn send_venue_tops(&self) {
+        let tops = self.combined_book.venue_tops();
+        self.venue_tops_sender.send_if_modified(|current| {
+            let modified = current.bids != tops.bids || current.asks != tops.asks;
+            if modified {
+                *current = tops;
+            }
+            modified
+        });
+    }
+
     fn send_snapshot_update(&mut self, snapshot: CombinedBookSnapshot) {
         if let Err(e) = self.snapshot_sender.send(snapshot) {
             warn!("Failed to send snapshot update: {:?}", e);
@@ -89,25 +344,183 @@ impl Stream for OrderbookProcessor {
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.get_mut();
-        let mut stream_map = StreamMap::new();
-        for (index, exchange) in this.exchanges.iter_mut().enumerate() {
-            stream_map.insert(index, Pin::new(exchange));
-        }
-
-        match stream_map.poll_next_unpin(cx) {
-            Poll::Ready(Some((_, Ok(orderbook)))) => {
-                debug!(
-                    "Received new orderbook update for {}",
-                    orderbook.bids.first().unwrap().exchange,
-                );
-                this.combined_book.update(orderbook);
-                let snapshot = this.combined_book.get_snapshot();
-                debug!("Updated combined book, new spread: {}", snapshot.spread);
-                Poll::Ready(Some(Ok(snapshot)))
+        loop {
+            match this.commands.poll_recv(cx) {
+                Poll::Ready(Some(ProcessorCommand::Add(exchange))) => {
+                    this.add_exchange(exchange);
+                    continue;
+                }
+                Poll::Ready(Some(ProcessorCommand::Remove(exchange))) => {
+                    if this.remove_exchange(&exchange).is_some() {
+                        this.send_venue_tops();
+                        return Poll::Ready(Some(Ok(this.combined_book.get_snapshot())));
+                    }
+                    continue;
+                }
+                Poll::Ready(None) if this.exchanges.is_empty() => return Poll::Ready(None),
+                Poll::Ready(None) | Poll::Pending => {}
+            }
+            match this.exchanges.poll_next_unpin(cx) {
+                Poll::Ready(Some(MultiplexerEvent::Item(exchange, Ok(orderbook)))) => {
+                    debug!("Received new orderbook update for {}", exchange);
+                    this.feed_stats.record(&exchange, &orderbook);
+                    let changes = this.combined_book.update(orderbook);
+                    this.send_venue_tops();
+                    if changes.is_empty() {
+                        // Nothing reached the top of the book, skip the broadcast.
+                        continue;
+                    }
+                    let snapshot = this.combined_book.get_snapshot();
+                    debug!("Updated combined book, new spread: {}", snapshot.spread);
+                    return Poll::Ready(Some(Ok(snapshot)));
+                }
+                Poll::Ready(Some(MultiplexerEvent::Item(_, Err(e)))) => {
+                    if let ExchangeError::OutOfSequence { exchange, .. } = &e {
+                        this.feed_stats.record_out_of_order(exchange);
+                    }
+                    return Poll::Ready(Some(Err(e)));
+                }
+                Poll::Ready(Some(MultiplexerEvent::Ended(exchange))) => {
+                    // Publish a book without the venue's now stale levels.
+                    warn!("Exchange stream ended: {}", exchange);
+                    this.combined_book.remove_exchange(&exchange);
+                    this.send_venue_tops();
+                    return Poll::Ready(Some(Ok(this.combined_book.get_snapshot())));
+                }
+                Poll::Ready(None) => {
+                    // Out of feeds: wait for a handle to add one, ending
+                    // once the last handle is dropped.
+                    if this.command_sender.take().is_none() {
+                        return Poll::Pending;
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
             }
-            Poll::Ready(Some((_, Err(e)))) => Poll::Ready(Some(Err(e))),
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Pending => Poll::Pending,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::fake_feed::FakeFeed;
+    use crate::exchange::{ExchangeOrder, Orderbook};
+    use futures_util::FutureExt;
+
+    fn feed(exchange: Exchange, bid: f64) -> Box<FakeFeed<Orderbook>> {
+        let book = Orderbook {
+            bids: vec![ExchangeOrder::new(exchange, bid, 1.0)],
+            ..Default::default()
+        };
+        Box::new(FakeFeed::open(exchange, vec![Ok(book)]))
+    }
+
+    #[tokio::test]
+    async fn test_handle_adds_and_removes_venues_while_running() {
+        let processor = OrderbookProcessor::builder("ethbtc", 10)
+            .with_exchange_stream(feed(Exchange::BINANCE, 100.0))
+            .build()
+            .unwrap();
+        let mut snapshots = processor.subscribe();
+        let handle = processor.handle();
+        let running = tokio::spawn(processor.drive_and_broadcast());
+
+        snapshots.changed().await.unwrap();
+        assert_eq!(snapshots.borrow_and_update().bids.len(), 1);
+
+        handle
+            .add_exchange(feed(Exchange::BITSTAMP, 101.0))
+            .unwrap();
+        snapshots.changed().await.unwrap();
+        let bids = snapshots.borrow_and_update().bids.clone();
+        assert_eq!(bids[0].exchange, Exchange::BITSTAMP);
+        assert_eq!(bids.len(), 2);
+
+        handle.remove_exchange(Exchange::BITSTAMP).unwrap();
+        snapshots.changed().await.unwrap();
+        let bids = snapshots.borrow_and_update().bids.clone();
+        assert_eq!(bids.len(), 1);
+        assert_eq!(bids[0].exchange, Exchange::BINANCE);
+
+        running.abort();
+    }
+
+    #[tokio::test]
+    async fn test_out_of_order_counts_against_the_rejecting_venue() {
+        // A synthetic feed keyed under one venue surfaces its legs' errors.
+        let rejected = ExchangeError::OutOfSequence {
+            exchange: Exchange::BITSTAMP,
+            last: 5,
+            received: 4,
+        };
+        let mut processor = OrderbookProcessor::builder("ethbtc", 10)
+            .with_exchange_stream(Box::new(FakeFeed::<Orderbook>::open(
+                Exchange::BINANCE,
+                vec![Err(rejected)],
+            )))
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            processor.next().await,
+            Some(Err(ExchangeError::OutOfSequence { .. }))
+        ));
+        let venues = processor.feed_stats().venues();
+        assert_eq!(venues.len(), 1);
+        assert_eq!(venues[0].exchange, Exchange::BITSTAMP);
+        assert_eq!(venues[0].out_of_order, 1);
+    }
+
+    #[tokio::test]
+    async fn test_outlives_its_feeds_while_handles_remain() {
+        let ending_feed = |exchange, bid| {
+            let book = Orderbook {
+                bids: vec![ExchangeOrder::new(exchange, bid, 1.0)],
+                ..Default::default()
+            };
+            Box::new(FakeFeed::new(exchange, vec![Ok(book)]))
+        };
+        let mut processor = OrderbookProcessor::builder("ethbtc", 10)
+            .with_exchange_stream(ending_feed(Exchange::BINANCE, 100.0))
+            .build()
+            .unwrap();
+        let handle = processor.handle();
+
+        assert_eq!(processor.next().await.unwrap().unwrap().bids.len(), 1);
+        // Binance's feed ending publishes an empty book.
+        assert!(processor.next().await.unwrap().unwrap().bids.is_empty());
+        assert!(processor.next().now_or_never().is_none());
+
+        handle
+            .add_exchange(ending_feed(Exchange::BITSTAMP, 101.0))
+            .unwrap();
+        let bids = processor.next().await.unwrap().unwrap().bids;
+        assert_eq!(bids[0].exchange, Exchange::BITSTAMP);
+
+        drop(handle);
+        assert!(processor.next().await.unwrap().unwrap().bids.is_empty());
+        assert!(processor.next().await.is_none());
+        assert!(processor
+            .handle()
+            .remove_exchange(Exchange::BINANCE)
+            .is_err());
+    }
+
+    #[test]
+    fn test_builder_rejects_unknown_exchanges() {
+        let processor = OrderbookProcessor::builder("ethbtc", 10)
+            .with_exchange("Binance")
+            .with_exchange("Bitstamp")
+            .build()
+            .unwrap();
+        assert_eq!(
+            processor.exchanges.keys().copied().collect::<Vec<_>>(),
+            vec![Exchange::BINANCE, Exchange::BITSTAMP]
+        );
+
+        let result = OrderbookProcessor::builder("ethbtc", 10)
+            .with_exchange("Kraken")
+            .build();
+        assert!(matches!(result, Err(ExchangeError::Unsupported(name)) if name == "Kraken"));
+    }
+}
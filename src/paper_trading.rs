@@ -0,0 +1,701 @@
+use crate::combined_book::CombinedBookSnapshot;
+use crate::config::FeeSchedule;
+use crate::exchange::{unix_time_us, Exchange, ExchangeOrder, TradeSide};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::{broadcast, watch};
+use tokio::time::{interval, MissedTickBehavior};
+
+/// Fills buffered per subscriber before the slowest start missing them.
+const FILL_CHANNEL_CAPACITY: usize = 1024;
+
+/// How often pending orders are checked against the latest book between
+/// updates.
+const ACTIVATION_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PaperTradingError {
+    #[error("Invalid order: {0}")]
+    InvalidOrder(String),
+    #[error("No open order {0}")]
+    UnknownOrder(u64),
+}
+
+/// A simulated order. Without a limit price it is a market order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperOrderRequest {
+    pub side: TradeSide,
+    pub amount: f64,
+    pub limit_price: Option<f64>,
+    /// Venues the order may fill on; `None` for all of them.
+    pub venues: Option<Vec<Exchange>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaperOrderStatus {
+    /// Waiting out the simulated latency.
+    Pending,
+    /// Resting at its limit price.
+    Open,
+    Filled,
+    /// Cancelled by the client, or a market order the book could not fill.
+    Cancelled,
+}
+
+impl PaperOrderStatus {
+    fn is_live(self) -> bool {
+        matches!(self, PaperOrderStatus::Pending | PaperOrderStatus::Open)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperOrder {
+    pub id: u64,
+    pub client: String,
+    pub request: PaperOrderRequest,
+    pub status: PaperOrderStatus,
+    pub filled: f64,
+    /// Quote value of the fills, before fees.
+    pub notional: f64,
+    /// Displayed size assumed ahead of the order at its price while it
+    /// rests.
+    pub queue_ahead: f64,
+    /// When the order reaches the book, in microseconds since the epoch.
+    pub active_at_us: u64,
+}
+
+impl PaperOrder {
+    pub fn average_price(&self) -> Option<f64> {
+        (self.filled > 0.0).then(|| self.notional / self.filled)
+    }
+
+    fn remaining(&self) -> f64 {
+        self.request.amount - self.filled
+    }
+
+    fn on_venue(&self, exchange: Exchange) -> bool {
+        self.request
+            .venues
+            .as_ref()
+            .is_none_or(|venues| venues.contains(&exchange))
+    }
+
+    /// Whether a level on the other side is at or through the limit.
+    fn crosses(&self, price: f64) -> bool {
+        match (self.request.side, self.request.limit_price) {
+            (_, None) => true,
+            (TradeSide::Buy, Some(limit)) => price <= limit,
+            (TradeSide::Sell, Some(limit)) => price >= limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperFill {
+    pub order_id: u64,
+    pub client: String,
+    pub exchange: Exchange,
+    pub side: TradeSide,
+    pub price: f64,
+    pub amount: f64,
+    pub fee: f64,
+    pub liquidity: Liquidity,
+    pub time_us: u64,
+}
+
+/// A client's simulated holdings on one venue. `quote` is net of fees.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperPosition {
+    pub exchange: Exchange,
+    pub base: f64,
+    pub quote: f64,
+    pub fees: f64,
+}
+
+struct PaperTradingInner {
+    next_id: u64,
+    /// Pending and open orders in arrival order.
+    orders: Vec<PaperOrder>,
+    positions: HashMap<(String, Exchange), PaperPosition>,
+    /// Fills on each (venue, price bits) level the book has not yet shown
+    /// leaving it, so no two fills, or two updates, take the same liquidity.
+    consumed: HashMap<(Exchange, u64), Consumed>,
+}
+
+/// Size filled from one level, and the level's size when last seen.
+struct Consumed {
+    amount: f64,
+    level_amount: f64,
+}
+
+impl PaperTradingInner {
+    /// What is left of `level` after earlier fills against it.
+    fn available(&self, level: &ExchangeOrder) -> f64 {
+        let consumed = self
+            .consumed
+            .get(&(level.exchange, level.price.to_bits()))
+            .map_or(0.0, |consumed| consumed.amount);
+        (level.amount - consumed).max(0.0)
+    }
+
+    fn consume(&mut self, level: &ExchangeOrder, amount: f64) {
+        let consumed = self
+            .consumed
+            .entry((level.exchange, level.price.to_bits()))
+            .or_insert(Consumed {
+                amount: 0.0,
+                level_amount: level.amount,
+            });
+        consumed.amount += amount;
+        consumed.level_amount = level.amount;
+    }
+
+    /// Carries fills over to `snapshot`. A level that shrank is taken to
+    /// have traded away that much of what was filled; one that is gone is
+    /// forgotten.
+    fn carry_consumed(&mut self, snapshot: &CombinedBookSnapshot) {
+        self.consumed.retain(|(exchange, price_bits), consumed| {
+            let Some(level) =
+                snapshot.bids.iter().chain(&snapshot.asks).find(|level| {
+                    level.exchange == *exchange && level.price.to_bits() == *price_bits
+                })
+            else {
+                return false;
+            };
+            consumed.amount -= (consumed.level_amount - level.amount).max(0.0);
+            consumed.level_amount = level.amount;
+            consumed.amount > 0.0
+        });
+    }
+}
+
+/// Matches simulated orders against the live combined book. Orders reach
+/// the book after the configured latency; marketable size takes liquidity
+/// at the displayed levels and the rest of a limit order joins the back of
+/// the displayed queue at its price. A resting order moves up as the size
+/// ahead of it shrinks, and fills as a maker once the other side trades
+/// through its price. Cheap to clone.
+#[derive(Clone)]
+pub struct PaperTrading {
+    inner: Arc<Mutex<PaperTradingInner>>,
+    fills: broadcast::Sender<PaperFill>,
+    latency: Duration,
+    queue_share: f64,
+    fees: HashMap<Exchange, FeeSchedule>,
+}
+
+impl PaperTrading {
+    pub fn new(latency: Duration) -> Self {
+        let (fills, _) = broadcast::channel(FILL_CHANNEL_CAPACITY);
+        Self {
+            inner: Arc::new(Mutex::new(PaperTradingInner {
+                next_id: 1,
+                orders: Vec::new(),
+                positions: HashMap::new(),
+                consumed: HashMap::new(),
+            })),
+            fills,
+            latency,
+            queue_share: 1.0,
+            fees: HashMap::new(),
+        }
+    }
+
+    /// Share of the displayed size at a resting order's price assumed to be
+    /// ahead of it; 1 joins the back of the queue, 0 the front.
+    pub fn with_queue_share(mut self, queue_share: f64) -> Self {
+        self.queue_share = queue_share.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_fees(mut self, exchange: Exchange, fees: FeeSchedule) -> Self {
+        self.fees.insert(exchange, fees);
+        self
+    }
+
+    pub fn submit(
+        &self,
+        client: &str,
+        request: PaperOrderRequest,
+        now_us: u64,
+    ) -> Result<PaperOrder, PaperTradingError> {
+        if !(request.amount.is_finite() && request.amount > 0.0) {
+            return Err(PaperTradingError::InvalidOrder(
+                "quantity must be positive".to_string(),
+            ));
+        }
+        if request
+            .limit_price
+            .is_some_and(|price| !(price.is_finite() && price > 0.0))
+        {
+            return Err(PaperTradingError::InvalidOrder(
+                "limit price must be positive".to_string(),
+            ));
+        }
+        let mut inner = self.inner.lock().expect("paper trading lock poisoned");
+        let order = PaperOrder {
+            id: inner.next_id,
+            client: client.to_string(),
+            request,
+            status: PaperOrderStatus::Pending,
+            filled: 0.0,
+            notional: 0.0,
+            queue_ahead: 0.0,
+            active_at_us: now_us + self.latency.as_micros() as u64,
+        };
+        inner.next_id += 1;
+        inner.orders.push(order.clone());
+        Ok(order)
+    }
+
+    /// Cancels one of `client`'s pending or open orders.
+    pub fn cancel(&self, client: &str, order_id: u64) -> Result<PaperOrder, PaperTradingError> {
+        let mut inner = self.inner.lock().expect("paper trading lock poisoned");
+        let index = inner
+            .orders
+            .iter()
+            .position(|order| order.id == order_id && order.client == client)
+            .ok_or(PaperTradingError::UnknownOrder(order_id))?;
+        let mut order = inner.orders.remove(index);
+        order.status = PaperOrderStatus::Cancelled;
+        Ok(order)
+    }
+
+    pub fn open_orders(&self, client: &str) -> Vec<PaperOrder> {
+        let inner = self.inner.lock().expect("paper trading lock poisoned");
+        inner
+            .orders
+            .iter()
+            .filter(|order| order.client == client)
+            .cloned()
+            .collect()
+    }
+
+    pub fn positions(&self, client: &str) -> Vec<PaperPosition> {
+        let inner = self.inner.lock().expect("paper trading lock poisoned");
+        let mut positions: Vec<_> = inner
+            .positions
+            .iter()
+            .filter(|((owner, _), _)| owner == client)
+            .map(|(_, position)| position.clone())
+            .collect();
+        positions.sort_by_key(|position| position.exchange);
+        positions
+    }
+
+    /// Receives every fill from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<PaperFill> {
+        self.fills.subscribe()
+    }
+
+    /// Brings orders whose latency has passed to `snapshot`: they take the
+    /// liquidity they cross, then a limit order rests and a market order
+    /// drops whatever the book's depth could not fill. `snapshot` must be
+    /// the one last passed to [`PaperTrading::on_update`], whose liquidity
+    /// earlier fills have already taken from.
+    pub fn activate_due(&self, snapshot: &CombinedBookSnapshot, now_us: u64) {
+        let mut inner = self.inner.lock().expect("paper trading lock poisoned");
+        self.activate_locked(&mut inner, snapshot, now_us);
+    }
+
+    fn activate_locked(
+        &self,
+        inner: &mut PaperTradingInner,
+        snapshot: &CombinedBookSnapshot,
+        now_us: u64,
+    ) {
+        let mut orders = std::mem::take(&mut inner.orders);
+        for order in orders.iter_mut() {
+            if order.status != PaperOrderStatus::Pending || order.active_at_us > now_us {
+                continue;
+            }
+            for level in opposite_side(snapshot, order.request.side) {
+                if order.remaining() <= 0.0 || !order.crosses(level.price) {
+                    break;
+                }
+                if order.on_venue(level.exchange) {
+                    let amount = order.remaining().min(inner.available(level));
+                    if amount > 0.0 {
+                        self.fill(order, inner, level, level.price, amount, now_us);
+                    }
+                }
+            }
+            order.status = match order.request.limit_price {
+                _ if order.remaining() <= 0.0 => PaperOrderStatus::Filled,
+                Some(price) => {
+                    order.queue_ahead = self.queue_share * displayed(snapshot, order, price);
+                    PaperOrderStatus::Open
+                }
+                None => PaperOrderStatus::Cancelled,
+            };
+        }
+        orders.retain(|order| order.status.is_live());
+        inner.orders = orders;
+    }
+
+    /// Applies a book update: activates due orders, then works the queue of
+    /// resting ones. Each update where the other side is at or through a
+    /// resting price is taken as that size trading against the queue.
+    pub fn on_update(&self, snapshot: &CombinedBookSnapshot, now_us: u64) {
+        let mut inner = self.inner.lock().expect("paper trading lock poisoned");
+        inner.carry_consumed(snapshot);
+        self.activate_locked(&mut inner, snapshot, now_us);
+        let mut orders = std::mem::take(&mut inner.orders);
+        for order in orders.iter_mut() {
+            let (PaperOrderStatus::Open, Some(price)) = (order.status, order.request.limit_price)
+            else {
+                continue;
+            };
+            order.queue_ahead = order.queue_ahead.min(displayed(snapshot, order, price));
+            for level in opposite_side(snapshot, order.request.side) {
+                if order.remaining() <= 0.0 || !order.crosses(level.price) {
+                    break;
+                }
+                if !order.on_venue(level.exchange) {
+                    continue;
+                }
+                let available = inner.available(level);
+                let past_queue = (available - order.queue_ahead).max(0.0);
+                order.queue_ahead = (order.queue_ahead - available).max(0.0);
+                let amount = order.remaining().min(past_queue);
+                if amount > 0.0 {
+                    self.fill(order, &mut inner, level, price, amount, now_us);
+                }
+            }
+            if order.remaining() <= 0.0 {
+                order.status = PaperOrderStatus::Filled;
+            }
+        }
+        orders.retain(|order| order.status.is_live());
+        inner.orders = orders;
+    }
+
+    fn fill(
+        &self,
+        order: &mut PaperOrder,
+        inner: &mut PaperTradingInner,
+        level: &ExchangeOrder,
+        price: f64,
+        amount: f64,
+        time_us: u64,
+    ) {
+        let liquidity = if order.status == PaperOrderStatus::Open {
+            Liquidity::Maker
+        } else {
+            Liquidity::Taker
+        };
+        let fees = self.fees.get(&level.exchange).copied().unwrap_or_default();
+        let fee_bps = match liquidity {
+            Liquidity::Maker => fees.maker_bps,
+            Liquidity::Taker => fees.taker_bps,
+        };
+        let notional = price * amount;
+        let fee = notional * fee_bps / 10_000.0;
+        order.filled += amount;
+        order.notional += notional;
+        inner.consume(level, amount);
+
+        let position = inner
+            .positions
+            .entry((order.client.clone(), level.exchange))
+            .or_insert_with(|| PaperPosition {
+                exchange: level.exchange,
+                base: 0.0,
+                quote: 0.0,
+                fees: 0.0,
+            });
+        match order.request.side {
+            TradeSide::Buy => {
+                position.base += amount;
+                position.quote -= notional;
+            }
+            TradeSide::Sell => {
+                position.base -= amount;
+                position.quote += notional;
+            }
+        }
+        position.quote -= fee;
+        position.fees += fee;
+
+        // No subscribers is not an error; positions still reflect the fill.
+        let _ = self.fills.send(PaperFill {
+            order_id: order.id,
+            client: order.client.clone(),
+            exchange: level.exchange,
+            side: order.request.side,
+            price,
+            amount,
+            fee,
+            liquidity,
+            time_us,
+        });
+    }
+
+    /// Matches against every book update until the processor goes away,
+    /// checking for orders past their latency in between.
+    pub async fn run(self, mut snapshots: watch::Receiver<CombinedBookSnapshot>) {
+        let mut activation_check = interval(ACTIVATION_CHECK_INTERVAL);
+        activation_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                changed = snapshots.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    let snapshot = snapshots.borrow_and_update().clone();
+                    self.on_update(&snapshot, unix_time_us());
+                }
+                _ = activation_check.tick() => {
+                    let snapshot = snapshots.borrow().clone();
+                    self.activate_due(&snapshot, unix_time_us());
+                }
+            }
+        }
+    }
+}
+
+/// The levels an order on `side` takes from, best first.
+fn opposite_side(snapshot: &CombinedBookSnapshot, side: TradeSide) -> &[ExchangeOrder] {
+    match side {
+        TradeSide::Buy => &snapshot.asks,
+        TradeSide::Sell => &snapshot.bids,
+    }
+}
+
+/// Size shown at `price` on the order's own side, on its venues.
+fn displayed(snapshot: &CombinedBookSnapshot, order: &PaperOrder, price: f64) -> f64 {
+    let own_side = match order.request.side {
+        TradeSide::Buy => &snapshot.bids,
+        TradeSide::Sell => &snapshot.asks,
+    };
+    own_side
+        .iter()
+        .filter(|level| level.price == price && order.on_venue(level.exchange))
+        .map(|level| level.amount)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(bids: Vec<ExchangeOrder>, asks: Vec<ExchangeOrder>) -> CombinedBookSnapshot {
+        CombinedBookSnapshot {
+            spread: asks[0].price - bids[0].price,
+            bids,
+            asks,
+        }
+    }
+
+    fn request(side: TradeSide, amount: f64, limit_price: Option<f64>) -> PaperOrderRequest {
+        PaperOrderRequest {
+            side,
+            amount,
+            limit_price,
+            venues: None,
+        }
+    }
+
+    #[test]
+    fn test_market_order_walks_book_after_latency() {
+        let paper = PaperTrading::new(Duration::from_millis(5)).with_fees(
+            Exchange::BINANCE,
+            FeeSchedule {
+                maker_bps: 0.0,
+                taker_bps: 10.0,
+            },
+        );
+        let mut fills = paper.subscribe();
+        let snapshot = book(
+            vec![ExchangeOrder::new(Exchange::BINANCE, 99.0, 1.0)],
+            vec![
+                ExchangeOrder::new(Exchange::BITSTAMP, 100.0, 1.0),
+                ExchangeOrder::new(Exchange::BINANCE, 101.0, 2.0),
+            ],
+        );
+        let submitted = paper
+            .submit("alice", request(TradeSide::Buy, 2.0, None), 1_000)
+            .unwrap();
+        assert_eq!(submitted.active_at_us, 6_000);
+
+        paper.activate_due(&snapshot, 5_999);
+        assert!(fills.try_recv().is_err());
+
+        paper.activate_due(&snapshot, 6_000);
+        let first = fills.try_recv().unwrap();
+        assert_eq!(
+            (first.exchange, first.price, first.amount),
+            (Exchange::BITSTAMP, 100.0, 1.0)
+        );
+        assert_eq!(first.fee, 0.0);
+        let second = fills.try_recv().unwrap();
+        assert_eq!(
+            (second.exchange, second.price, second.amount),
+            (Exchange::BINANCE, 101.0, 1.0)
+        );
+        assert!((second.fee - 0.101).abs() < 1e-12);
+        assert_eq!(second.liquidity, Liquidity::Taker);
+
+        assert!(paper.open_orders("alice").is_empty());
+        let positions = paper.positions("alice");
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].exchange, Exchange::BINANCE);
+        assert_eq!(positions[0].base, 1.0);
+        assert!((positions[0].quote + 101.101).abs() < 1e-9);
+        assert!(paper.positions("bob").is_empty());
+    }
+
+    #[test]
+    fn test_limit_order_rests_behind_queue() {
+        let paper = PaperTrading::new(Duration::ZERO);
+        let mut fills = paper.subscribe();
+        let bid = |amount| ExchangeOrder::new(Exchange::BINANCE, 99.0, amount);
+        paper
+            .submit("alice", request(TradeSide::Buy, 1.0, Some(99.0)), 0)
+            .unwrap();
+
+        paper.on_update(
+            &book(
+                vec![bid(3.0)],
+                vec![ExchangeOrder::new(Exchange::BINANCE, 100.0, 1.0)],
+            ),
+            0,
+        );
+        let resting = &paper.open_orders("alice")[0];
+        assert_eq!(resting.status, PaperOrderStatus::Open);
+        assert_eq!(resting.queue_ahead, 3.0);
+
+        // Cancellations ahead of the order move it up the queue.
+        paper.on_update(
+            &book(
+                vec![bid(2.0)],
+                vec![ExchangeOrder::new(Exchange::BINANCE, 100.0, 1.0)],
+            ),
+            1,
+        );
+        assert_eq!(paper.open_orders("alice")[0].queue_ahead, 2.0);
+
+        // 2.5 sold at 99 clears the queue and fills half the order.
+        paper.on_update(
+            &book(
+                vec![bid(2.0)],
+                vec![ExchangeOrder::new(Exchange::BITSTAMP, 99.0, 2.5)],
+            ),
+            2,
+        );
+        let fill = fills.try_recv().unwrap();
+        assert_eq!((fill.price, fill.amount), (99.0, 0.5));
+        assert_eq!(fill.liquidity, Liquidity::Maker);
+        let resting = &paper.open_orders("alice")[0];
+        assert_eq!((resting.filled, resting.queue_ahead), (0.5, 0.0));
+        assert_eq!(resting.average_price(), Some(99.0));
+
+        let cancelled = paper.cancel("alice", resting.id).unwrap();
+        assert_eq!(cancelled.status, PaperOrderStatus::Cancelled);
+        assert_eq!(
+            paper.cancel("alice", resting.id),
+            Err(PaperTradingError::UnknownOrder(resting.id))
+        );
+    }
+
+    #[test]
+    fn test_orders_do_not_share_liquidity() {
+        let paper = PaperTrading::new(Duration::ZERO).with_queue_share(0.0);
+        let mut fills = paper.subscribe();
+        let snapshot = book(
+            vec![ExchangeOrder::new(Exchange::BINANCE, 99.0, 1.0)],
+            vec![
+                ExchangeOrder::new(Exchange::BITSTAMP, 100.0, 1.0),
+                ExchangeOrder::new(Exchange::BINANCE, 101.0, 2.0),
+            ],
+        );
+        paper.on_update(&snapshot, 0);
+
+        // Both market orders reach the same snapshot on separate checks.
+        for _ in 0..2 {
+            paper
+                .submit("alice", request(TradeSide::Buy, 1.0, None), 0)
+                .unwrap();
+            paper.activate_due(&snapshot, 0);
+        }
+        let (first, second) = (fills.try_recv().unwrap(), fills.try_recv().unwrap());
+        assert_eq!((first.exchange, first.price), (Exchange::BITSTAMP, 100.0));
+        assert_eq!((second.exchange, second.price), (Exchange::BINANCE, 101.0));
+
+        // Two resting bids split the 1.5 sold through their price.
+        for _ in 0..2 {
+            paper
+                .submit("bob", request(TradeSide::Buy, 1.0, Some(99.0)), 0)
+                .unwrap();
+        }
+        paper.on_update(&snapshot, 1);
+        paper.on_update(
+            &book(
+                vec![ExchangeOrder::new(Exchange::BINANCE, 98.0, 1.0)],
+                vec![ExchangeOrder::new(Exchange::BITSTAMP, 99.0, 1.5)],
+            ),
+            2,
+        );
+        let amounts: Vec<f64> = std::iter::from_fn(|| fills.try_recv().ok())
+            .map(|fill| fill.amount)
+            .collect();
+        assert_eq!(amounts, vec![1.0, 0.5]);
+        assert_eq!(paper.open_orders("bob")[0].filled, 0.5);
+    }
+
+    #[test]
+    fn test_repeated_updates_do_not_refill() {
+        let paper = PaperTrading::new(Duration::ZERO).with_queue_share(0.0);
+        let mut fills = paper.subscribe();
+        paper
+            .submit("alice", request(TradeSide::Buy, 3.0, Some(99.0)), 0)
+            .unwrap();
+        let crossing = |amount| {
+            book(
+                vec![ExchangeOrder::new(Exchange::BINANCE, 98.0, 1.0)],
+                vec![ExchangeOrder::new(Exchange::BITSTAMP, 99.0, amount)],
+            )
+        };
+        paper.on_update(
+            &book(
+                vec![ExchangeOrder::new(Exchange::BINANCE, 98.0, 1.0)],
+                vec![ExchangeOrder::new(Exchange::BINANCE, 100.0, 1.0)],
+            ),
+            0,
+        );
+
+        paper.on_update(&crossing(1.0), 1);
+        paper.on_update(&crossing(1.0), 2);
+        // Shrinking by 0.6 leaves 0.4 of the filled size still shown.
+        paper.on_update(&crossing(0.4), 3);
+        // New size behind it can fill.
+        paper.on_update(&crossing(1.4), 4);
+
+        let amounts: Vec<f64> = std::iter::from_fn(|| fills.try_recv().ok())
+            .map(|fill| fill.amount)
+            .collect();
+        assert_eq!(amounts.len(), 2);
+        assert_eq!(amounts[0], 1.0);
+        assert!((amounts[1] - 1.0).abs() < 1e-9);
+        assert!((paper.open_orders("alice")[0].filled - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rejects_invalid_orders() {
+        let paper = PaperTrading::new(Duration::ZERO);
+        assert!(paper
+            .submit("alice", request(TradeSide::Sell, 0.0, None), 0)
+            .is_err());
+        assert!(paper
+            .submit("alice", request(TradeSide::Sell, 1.0, Some(f64::NAN)), 0)
+            .is_err());
+    }
+}
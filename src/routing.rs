@@ -0,0 +1,263 @@
+use crate::combined_book::CombinedBookSnapshot;
+use crate::config::FeeSchedule;
+use crate::exchange::{Exchange, ExchangeOrder, TradeSide};
+use std::collections::HashMap;
+
+/// An order to split across venues. Without a limit price every level on
+/// the other side may be taken.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteRequest {
+    pub side: TradeSide,
+    pub amount: f64,
+    pub limit_price: Option<f64>,
+}
+
+/// The share of a routed order to send to one venue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChildOrder {
+    pub exchange: Exchange,
+    pub amount: f64,
+    /// The worst price level the child takes, to send as its limit.
+    pub limit_price: f64,
+    /// Volume-weighted price over the levels taken, before fees.
+    pub expected_price: f64,
+    pub expected_fee: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Route {
+    /// In routing order, best net price first.
+    pub children: Vec<ChildOrder>,
+    /// Size the visible book could not take within the limit and minimum
+    /// order sizes.
+    pub unfilled: f64,
+}
+
+impl Route {
+    pub fn filled(&self) -> f64 {
+        self.children.iter().map(|child| child.amount).sum()
+    }
+
+    pub fn expected_fees(&self) -> f64 {
+        self.children.iter().map(|child| child.expected_fee).sum()
+    }
+
+    /// Volume-weighted price across the children, before fees.
+    pub fn average_price(&self) -> Option<f64> {
+        let filled = self.filled();
+        (filled > 0.0).then(|| {
+            self.children
+                .iter()
+                .map(|child| child.expected_price * child.amount)
+                .sum::<f64>()
+                / filled
+        })
+    }
+}
+
+/// Splits orders across venues by walking the combined book in order of
+/// price net of each venue's taker fee. A venue whose share comes out under
+/// its minimum order size is dropped and the walk repeated without it.
+#[derive(Debug, Clone, Default)]
+pub struct Router {
+    fees: HashMap<Exchange, FeeSchedule>,
+    min_order_sizes: HashMap<Exchange, f64>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_fees(mut self, exchange: Exchange, fees: FeeSchedule) -> Self {
+        self.fees.insert(exchange, fees);
+        self
+    }
+
+    pub fn with_min_order_size(mut self, exchange: Exchange, min_order_size: f64) -> Self {
+        self.min_order_sizes.insert(exchange, min_order_size);
+        self
+    }
+
+    /// Routes `request` against `snapshot`, which should already be limited
+    /// to the venues the order may go to.
+    pub fn route(&self, snapshot: &CombinedBookSnapshot, request: &RouteRequest) -> Route {
+        let levels = match request.side {
+            TradeSide::Buy => &snapshot.asks,
+            TradeSide::Sell => &snapshot.bids,
+        };
+        let mut levels: Vec<&ExchangeOrder> = levels
+            .iter()
+            .filter(|level| match (request.side, request.limit_price) {
+                (_, None) => true,
+                (TradeSide::Buy, Some(limit)) => level.price <= limit,
+                (TradeSide::Sell, Some(limit)) => level.price >= limit,
+            })
+            .collect();
+        // Stable, so venues quoting the same net price keep book order.
+        levels.sort_by(|a, b| {
+            let (a, b) = (
+                self.net_price(a, request.side),
+                self.net_price(b, request.side),
+            );
+            match request.side {
+                TradeSide::Buy => a.total_cmp(&b),
+                TradeSide::Sell => b.total_cmp(&a),
+            }
+        });
+
+        let mut excluded: Vec<Exchange> = Vec::new();
+        loop {
+            let route = self.allocate(&levels, request, &excluded);
+            let under_minimum: Vec<Exchange> = route
+                .children
+                .iter()
+                .filter(|child| child.amount < self.min_order_size(child.exchange))
+                .map(|child| child.exchange)
+                .collect();
+            if under_minimum.is_empty() {
+                return route;
+            }
+            excluded.extend(under_minimum);
+        }
+    }
+
+    /// Takes levels in order until the order is filled, skipping
+    /// `excluded` venues, and folds the takes into one child per venue.
+    fn allocate(
+        &self,
+        levels: &[&ExchangeOrder],
+        request: &RouteRequest,
+        excluded: &[Exchange],
+    ) -> Route {
+        let mut children: Vec<ChildOrder> = Vec::new();
+        let mut remaining = request.amount;
+        for level in levels {
+            if remaining <= 0.0 {
+                break;
+            }
+            if excluded.contains(&level.exchange) {
+                continue;
+            }
+            let amount = remaining.min(level.amount);
+            remaining -= amount;
+            let fee = level.price * amount * self.taker_bps(level.exchange) / 10_000.0;
+            match children
+                .iter_mut()
+                .find(|child| child.exchange == level.exchange)
+            {
+                Some(child) => {
+                    let total = child.amount + amount;
+                    child.expected_price =
+                        (child.expected_price * child.amount + level.price * amount) / total;
+                    child.amount = total;
+                    child.limit_price = level.price;
+                    child.expected_fee += fee;
+                }
+                None => children.push(ChildOrder {
+                    exchange: level.exchange,
+                    amount,
+                    limit_price: level.price,
+                    expected_price: level.price,
+                    expected_fee: fee,
+                }),
+            }
+        }
+        Route {
+            children,
+            unfilled: remaining.max(0.0),
+        }
+    }
+
+    fn taker_bps(&self, exchange: Exchange) -> f64 {
+        self.fees.get(&exchange).map_or(0.0, |fees| fees.taker_bps)
+    }
+
+    fn min_order_size(&self, exchange: Exchange) -> f64 {
+        self.min_order_sizes.get(&exchange).copied().unwrap_or(0.0)
+    }
+
+    /// What a unit costs or brings in at `level` after the taker fee.
+    fn net_price(&self, level: &ExchangeOrder, side: TradeSide) -> f64 {
+        let fee = self.taker_bps(level.exchange) / 10_000.0;
+        match side {
+            TradeSide::Buy => level.price * (1.0 + fee),
+            TradeSide::Sell => level.price * (1.0 - fee),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book() -> CombinedBookSnapshot {
+        CombinedBookSnapshot {
+            spread: 1.0,
+            bids: vec![ExchangeOrder::new(Exchange::BITSTAMP, 99.0, 1.0)],
+            asks: vec![
+                ExchangeOrder::new(Exchange::BINANCE, 100.0, 1.0),
+                ExchangeOrder::new(Exchange::BITSTAMP, 100.05, 2.0),
+                ExchangeOrder::new(Exchange::BINANCE, 100.2, 3.0),
+            ],
+        }
+    }
+
+    fn buy(amount: f64, limit_price: Option<f64>) -> RouteRequest {
+        RouteRequest {
+            side: TradeSide::Buy,
+            amount,
+            limit_price,
+        }
+    }
+
+    #[test]
+    fn test_routes_by_price_net_of_fees() {
+        // 10bps on Binance makes its 100.0 dearer than Bitstamp's 100.05.
+        let router = Router::new().with_fees(
+            Exchange::BINANCE,
+            FeeSchedule {
+                maker_bps: 0.0,
+                taker_bps: 10.0,
+            },
+        );
+        let route = router.route(&book(), &buy(4.0, None));
+
+        assert_eq!(route.children.len(), 2);
+        let bitstamp = &route.children[0];
+        assert_eq!(bitstamp.exchange, Exchange::BITSTAMP);
+        assert_eq!((bitstamp.amount, bitstamp.limit_price), (2.0, 100.05));
+        assert_eq!(bitstamp.expected_fee, 0.0);
+        let binance = &route.children[1];
+        assert_eq!((binance.amount, binance.limit_price), (2.0, 100.2));
+        assert!((binance.expected_price - 100.1).abs() < 1e-9);
+        assert!((binance.expected_fee - 0.2002).abs() < 1e-9);
+        assert_eq!(route.filled(), 4.0);
+        assert_eq!(route.unfilled, 0.0);
+    }
+
+    #[test]
+    fn test_respects_limit_and_minimum_sizes() {
+        let route = Router::new().route(&book(), &buy(5.0, Some(100.1)));
+        assert_eq!(route.filled(), 3.0);
+        assert_eq!(route.unfilled, 2.0);
+
+        // Binance's 0.5 share is under its minimum, so Bitstamp takes it.
+        let router = Router::new().with_min_order_size(Exchange::BINANCE, 1.0);
+        let route = router.route(&book(), &buy(0.5, None));
+        assert_eq!(route.children.len(), 1);
+        assert_eq!(route.children[0].exchange, Exchange::BITSTAMP);
+        assert_eq!(route.average_price(), Some(100.05));
+
+        let route = Router::new().route(
+            &book(),
+            &RouteRequest {
+                side: TradeSide::Sell,
+                amount: 1.0,
+                limit_price: Some(99.5),
+            },
+        );
+        assert!(route.children.is_empty());
+        assert_eq!(route.average_price(), None);
+    }
+}
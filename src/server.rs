@@ -0,0 +1,208 @@
+use crate::candles::Candles;
+use crate::config::Config;
+use crate::exchange::Exchange;
+use crate::grpc::auth::Authenticator;
+use crate::grpc::orderbook_service::OrderbookService;
+use crate::grpc::tls::load_server_tls_config;
+use crate::http::rest_api::RestApi;
+use crate::liquidity_stats::LiquidityStats;
+use crate::orderbook::orderbook_aggregator_server::OrderbookAggregatorServer;
+use crate::orderbook_processor::OrderbookProcessor;
+use crate::paper_trading::PaperTrading;
+use crate::routing::Router;
+use crate::subscription::Subscriptions;
+use crate::trade_processor::TradeProcessor;
+use crate::websocket::json_publisher::JsonPublisher;
+use crate::websocket::tls::load_tls_acceptor;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tonic::transport::Server;
+use tracing::{error, info};
+
+/// Runs the aggregator with every endpoint enabled in `config`: the gRPC
+/// service, plus the WebSocket publisher and REST API if configured.
+/// Returns when the gRPC server stops, or with an error once the orderbook
+/// processor fails or runs out of feeds.
+pub async fn run(config: &Config) -> Result<(), Box<dyn Error>> {
+    let grpc_config = &config.grpc;
+    let grpc_addr: SocketAddr = grpc_config
+        .addr
+        .parse()
+        .map_err(|err| format!("Invalid gRPC listen address {}: {}", grpc_config.addr, err))?;
+
+    let mut server = Server::builder();
+    let mut allowed_client_subjects = Vec::new();
+    if let Some(tls) = &grpc_config.tls {
+        info!(
+            "Enabling TLS for gRPC server (mutual TLS: {})",
+            tls.client_ca_path.is_some()
+        );
+        server = server.tls_config(load_server_tls_config(tls)?)?;
+        allowed_client_subjects = tls.allowed_client_subjects.clone();
+    }
+
+    if grpc_config.auth.is_none() {
+        info!("No auth configured, subscribers are unrestricted");
+    }
+    let authenticator = Authenticator::new(grpc_config.auth.as_ref());
+
+    info!("Creating orderbook processor");
+    let orderbook_processor = OrderbookProcessor::from_config(config)?;
+    let receiver = orderbook_processor.subscribe();
+    let venue_tops = orderbook_processor.subscribe_venue_tops();
+    let feed_stats = orderbook_processor.feed_stats();
+
+    info!("Spawning orderbook processor drive loop..");
+    let processor = tokio::spawn(orderbook_processor.run());
+
+    let trades = match TradeProcessor::from_config(config)? {
+        Some(trade_processor) => {
+            info!("Spawning trade processor");
+            let sender = trade_processor.sender();
+            tokio::spawn(async move {
+                if let Err(err) = trade_processor.run().await {
+                    error!("Error initializing trade feeds: {}", err);
+                }
+            });
+            Some(sender)
+        }
+        None => None,
+    };
+
+    let candles = config.candles.as_ref().map(|candles_config| {
+        let intervals = candles_config
+            .intervals_ms
+            .iter()
+            .map(|ms| Duration::from_millis(*ms))
+            .collect();
+        let candles = Candles::new(intervals, candles_config.history);
+        info!("Spawning candle builder");
+        tokio::spawn(candles.clone().run(
+            receiver.clone(),
+            venue_tops.clone(),
+            trades.as_ref().map(|t| t.subscribe()),
+        ));
+        candles
+    });
+
+    let liquidity_stats = LiquidityStats::new(
+        config
+            .liquidity
+            .windows_ms
+            .iter()
+            .map(|ms| Duration::from_millis(*ms))
+            .collect(),
+    );
+    tokio::spawn(liquidity_stats.clone().run(venue_tops));
+
+    let venue_settings: Vec<_> = config
+        .venues
+        .iter()
+        .filter_map(|(name, venue)| Some((Exchange::from_str(name).ok()?, venue)))
+        .collect();
+
+    let router = venue_settings
+        .iter()
+        .fold(Router::new(), |router, (exchange, venue)| {
+            router
+                .with_fees(*exchange, venue.fees)
+                .with_min_order_size(*exchange, venue.min_order_size)
+        });
+
+    let paper_trading = config.paper_trading.as_ref().map(|paper_config| {
+        let paper_trading = venue_settings.iter().fold(
+            PaperTrading::new(Duration::from_millis(paper_config.latency_ms))
+                .with_queue_share(paper_config.queue_share),
+            |paper_trading, (exchange, venue)| paper_trading.with_fees(*exchange, venue.fees),
+        );
+        info!("Spawning paper trading engine");
+        tokio::spawn(paper_trading.clone().run(receiver.clone()));
+        paper_trading
+    });
+
+    let subscriptions = Subscriptions::new(receiver, &config.trading_pair, &config.subscriptions);
+
+    if let Some(websocket_config) = &config.websocket {
+        info!(
+            "Setting up WebSocket publisher listening on {}",
+            websocket_config.addr
+        );
+        let listener = bind(&websocket_config.addr).await?;
+        let mut publisher = JsonPublisher::new(subscriptions.clone(), authenticator.clone());
+        if let Some(tls) = &websocket_config.tls {
+            info!(
+                "Enabling TLS for WebSocket publisher (mutual TLS: {})",
+                tls.client_ca_path.is_some()
+            );
+            publisher = publisher
+                .with_tls(load_tls_acceptor(tls)?)
+                .with_allowed_client_subjects(tls.allowed_client_subjects.clone());
+        }
+        tokio::spawn(async move {
+            if let Err(err) = publisher.serve(listener).await {
+                error!("Error running WebSocket publisher: {:?}", err);
+            }
+        });
+    }
+
+    if let Some(http_config) = &config.http {
+        info!("Setting up REST API listening on {}", http_config.addr);
+        let listener = bind(&http_config.addr).await?;
+        let venues = config
+            .exchanges
+            .iter()
+            .chain(config.synthetics.iter().map(|synthetic| &synthetic.name))
+            .filter_map(|name| Exchange::from_str(name).ok())
+            .collect();
+        let rest_api = RestApi::new(subscriptions.clone(), authenticator.clone(), venues)
+            .with_liquidity_stats(liquidity_stats.clone());
+        tokio::spawn(async move {
+            if let Err(err) = rest_api.serve(listener).await {
+                error!("Error running REST API: {:?}", err);
+            }
+        });
+    }
+
+    info!("Creating orderbook service");
+    let mut orderbook_service = OrderbookService::new(subscriptions)
+        .with_feed_stats(feed_stats)
+        .with_analytics(config.analytics.clone())
+        .with_liquidity_stats(liquidity_stats)
+        .with_router(router)
+        .with_allowed_client_subjects(allowed_client_subjects);
+    if let Some(trades) = trades {
+        orderbook_service = orderbook_service.with_trades(trades);
+    }
+    if let Some(candles) = candles {
+        orderbook_service = orderbook_service.with_candles(candles);
+    }
+    if let Some(paper_trading) = paper_trading {
+        orderbook_service = orderbook_service.with_paper_trading(paper_trading);
+    }
+
+    info!("Setting up gRPC service listening on {}", grpc_addr);
+    let grpc = server
+        .add_service(OrderbookAggregatorServer::with_interceptor(
+            orderbook_service,
+            authenticator,
+        ))
+        .serve(grpc_addr);
+    // Without the processor every endpoint would serve a frozen book.
+    tokio::select! {
+        result = grpc => result?,
+        result = processor => match result? {
+            Ok(()) => return Err("Every exchange feed has ended".into()),
+            Err(err) => return Err(format!("Orderbook processor failed: {}", err).into()),
+        },
+    }
+    Ok(())
+}
+
+async fn bind(addr: &str) -> Result<TcpListener, Box<dyn Error>> {
+    TcpListener::bind(addr)
+        .await
+        .map_err(|err| format!("Failed to bind to {}: {}", addr, err).into())
+}
@@ -0,0 +1,553 @@
+use crate::combined_book::CombinedBookSnapshot;
+use crate::config::SubscriptionConfig;
+use crate::exchange::{Exchange, ExchangeOrder};
+use futures_util::stream::{self, Stream};
+use serde::Deserialize;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
+use tokio::time::{sleep_until, Instant};
+
+/// What an authenticated client may stream. `None` means unrestricted.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Entitlements {
+    #[serde(default)]
+    pub instruments: Option<Vec<String>>,
+    #[serde(default)]
+    pub venues: Option<Vec<String>>,
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+}
+
+/// Options a subscriber may set, shared by every publisher. Empty
+/// `instrument`/`exchanges` and a zero `depth` mean "everything".
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubscriptionRequest {
+    pub instrument: String,
+    pub exchanges: Vec<String>,
+    pub depth: usize,
+    pub min_interval: Duration,
+    pub conflate: bool,
+    pub top_of_book_only: bool,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SubscriptionError {
+    #[error("Instrument '{0}' is not served")]
+    UnknownInstrument(String),
+    #[error("Unknown exchange '{0}'")]
+    UnknownExchange(String),
+    #[error("Not entitled to {0}")]
+    NotEntitled(String),
+    #[error("Too many subscribers")]
+    TooManySubscribers,
+}
+
+/// Venues and depth a subscriber receives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookFilter {
+    pub venues: Option<Vec<Exchange>>,
+    pub depth: usize,
+}
+
+impl Default for BookFilter {
+    fn default() -> Self {
+        Self {
+            venues: None,
+            depth: usize::MAX,
+        }
+    }
+}
+
+/// How often a subscriber is sent updates.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UpdatePolicy {
+    /// Minimum time between two updates.
+    pub min_interval: Duration,
+    /// When an update is held back by `min_interval`, send the latest book
+    /// once the interval has passed rather than waiting for the next change.
+    pub conflate: bool,
+    /// Only send updates that change the best bid or ask.
+    pub top_of_book_only: bool,
+}
+
+/// Held by a subscriber stream for as long as it is alive.
+pub struct SubscriberPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+/// Turns subscription requests into filtered, throttled snapshot streams.
+/// Clones share the subscriber cap, so every publisher draws from the same
+/// pool.
+#[derive(Clone)]
+pub struct Subscriptions {
+    receiver: watch::Receiver<CombinedBookSnapshot>,
+    instrument: String,
+    semaphore: Option<Arc<Semaphore>>,
+    min_interval_floor: Duration,
+}
+
+impl Subscriptions {
+    pub fn new(
+        receiver: watch::Receiver<CombinedBookSnapshot>,
+        instrument: &str,
+        config: &SubscriptionConfig,
+    ) -> Self {
+        Self {
+            receiver,
+            instrument: instrument.to_string(),
+            semaphore: config
+                .max_subscribers
+                .map(|max| Arc::new(Semaphore::new(max))),
+            min_interval_floor: Duration::from_millis(config.min_interval_ms),
+        }
+    }
+
+    pub fn resolve_filter(
+        &self,
+        request: &SubscriptionRequest,
+        entitlements: &Entitlements,
+    ) -> Result<BookFilter, SubscriptionError> {
+        let instrument = match request.instrument.as_str() {
+            "" => self.instrument.as_str(),
+            instrument => instrument,
+        };
+        if instrument != self.instrument {
+            return Err(SubscriptionError::UnknownInstrument(instrument.to_string()));
+        }
+        if let Some(instruments) = &entitlements.instruments {
+            if !instruments.iter().any(|i| i == instrument) {
+                return Err(SubscriptionError::NotEntitled(format!(
+                    "instrument '{}'",
+                    instrument
+                )));
+            }
+        }
+
+        let requested = request
+            .exchanges
+            .iter()
+            .map(|name| {
+                Exchange::from_str(name)
+                    .map_err(|_| SubscriptionError::UnknownExchange(name.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let venues = match &entitlements.venues {
+            Some(entitled) => {
+                let entitled = entitled
+                    .iter()
+                    .map(|name| {
+                        Exchange::from_str(name)
+                            .map_err(|_| SubscriptionError::UnknownExchange(name.to_string()))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if let Some(venue) = requested.iter().find(|v| !entitled.contains(v)) {
+                    return Err(SubscriptionError::NotEntitled(format!(
+                        "exchange '{}'",
+                        venue
+                    )));
+                }
+                Some(if requested.is_empty() {
+                    entitled
+                } else {
+                    requested
+                })
+            }
+            None if requested.is_empty() => None,
+            None => Some(requested),
+        };
+
+        let depth = match request.depth {
+            0 => usize::MAX,
+            depth => depth,
+        };
+        let depth = depth.min(entitlements.max_depth.unwrap_or(usize::MAX));
+
+        Ok(BookFilter { venues, depth })
+    }
+
+    pub fn resolve_policy(&self, request: &SubscriptionRequest) -> UpdatePolicy {
+        UpdatePolicy {
+            min_interval: request.min_interval.max(self.min_interval_floor),
+            conflate: request.conflate,
+            top_of_book_only: request.top_of_book_only,
+        }
+    }
+
+    /// Resolves `request` against `entitlements` and takes a subscriber slot.
+    pub fn subscribe(
+        &self,
+        request: &SubscriptionRequest,
+        entitlements: &Entitlements,
+    ) -> Result<impl Stream<Item = CombinedBookSnapshot> + Send, SubscriptionError> {
+        let filter = self.resolve_filter(request, entitlements)?;
+        let policy = self.resolve_policy(request);
+        let permit = self.try_acquire()?;
+        Ok(snapshot_stream(
+            self.receiver.clone(),
+            filter,
+            policy,
+            permit,
+        ))
+    }
+
+    /// The current book, filtered for `request`. One-off queries do not
+    /// count against the subscriber cap.
+    pub fn current(
+        &self,
+        request: &SubscriptionRequest,
+        entitlements: &Entitlements,
+    ) -> Result<CombinedBookSnapshot, SubscriptionError> {
+        let filter = self.resolve_filter(request, entitlements)?;
+        Ok(self
+            .receiver
+            .borrow()
+            .filtered(filter.venues.as_deref(), filter.depth))
+    }
+
+    /// Takes a subscriber slot for a stream not built by `subscribe`, such
+    /// as trades, to hold for as long as the stream lives.
+    pub fn try_acquire(&self) -> Result<SubscriberPermit, SubscriptionError> {
+        let permit = match &self.semaphore {
+            Some(semaphore) => Some(
+                semaphore
+                    .clone()
+                    .try_acquire_owned()
+                    .map_err(|_| SubscriptionError::TooManySubscribers)?,
+            ),
+            None => None,
+        };
+        Ok(SubscriberPermit { _permit: permit })
+    }
+}
+
+struct SubscriptionState {
+    receiver: watch::Receiver<CombinedBookSnapshot>,
+    filter: BookFilter,
+    policy: UpdatePolicy,
+    last_sent_at: Option<Instant>,
+    last_top: Option<(Option<ExchangeOrder>, Option<ExchangeOrder>)>,
+    _permit: SubscriberPermit,
+}
+
+/// Streams filtered snapshots from `receiver` according to `policy`,
+/// starting with the current book. Ends when the processor goes away.
+fn snapshot_stream(
+    mut receiver: watch::Receiver<CombinedBookSnapshot>,
+    filter: BookFilter,
+    policy: UpdatePolicy,
+    permit: SubscriberPermit,
+) -> impl Stream<Item = CombinedBookSnapshot> + Send {
+    receiver.mark_changed();
+    let state = SubscriptionState {
+        receiver,
+        filter,
+        policy,
+        last_sent_at: None,
+        last_top: None,
+        _permit: permit,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        loop {
+            state.receiver.changed().await.ok()?;
+
+            if let Some(last_sent_at) = state.last_sent_at {
+                let next_allowed = last_sent_at + state.policy.min_interval;
+                if Instant::now() < next_allowed {
+                    if !state.policy.conflate {
+                        continue;
+                    }
+                    sleep_until(next_allowed).await;
+                }
+            }
+
+            let snapshot = state
+                .receiver
+                .borrow_and_update()
+                .filtered(state.filter.venues.as_deref(), state.filter.depth);
+
+            if state.policy.top_of_book_only {
+                let top = (
+                    snapshot.bids.first().cloned(),
+                    snapshot.asks.first().cloned(),
+                );
+                if state.last_top.as_ref() == Some(&top) {
+                    continue;
+                }
+                state.last_top = Some(top);
+            }
+
+            state.last_sent_at = Some(Instant::now());
+            return Some((snapshot, state));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    fn book(bid: f64, ask: f64, bid_amount: f64) -> CombinedBookSnapshot {
+        CombinedBookSnapshot {
+            spread: ask - bid,
+            bids: vec![
+                ExchangeOrder {
+                    exchange: Exchange::BINANCE,
+                    price: bid,
+                    amount: bid_amount,
+                    original_price: None,
+                },
+                ExchangeOrder {
+                    exchange: Exchange::BITSTAMP,
+                    price: bid - 1.0,
+                    amount: 1.0,
+                    original_price: None,
+                },
+            ],
+            asks: vec![ExchangeOrder {
+                exchange: Exchange::BINANCE,
+                price: ask,
+                amount: 1.0,
+                original_price: None,
+            }],
+        }
+    }
+
+    fn permit() -> SubscriberPermit {
+        SubscriberPermit { _permit: None }
+    }
+
+    fn subscriptions(max_subscribers: Option<usize>, min_interval_ms: u64) -> Subscriptions {
+        let (_, receiver) = watch::channel(CombinedBookSnapshot::default());
+        let config = SubscriptionConfig {
+            max_subscribers,
+            min_interval_ms,
+        };
+        Subscriptions::new(receiver, "ethbtc", &config)
+    }
+
+    fn request(exchanges: &[&str], depth: usize) -> SubscriptionRequest {
+        SubscriptionRequest {
+            exchanges: exchanges.iter().map(|s| s.to_string()).collect(),
+            depth,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_filter_unrestricted() {
+        let subscriptions = subscriptions(None, 0);
+        let filter = subscriptions
+            .resolve_filter(&request(&[], 0), &Entitlements::default())
+            .unwrap();
+        assert_eq!(filter, BookFilter::default());
+
+        let filter = subscriptions
+            .resolve_filter(&request(&["Bitstamp"], 5), &Entitlements::default())
+            .unwrap();
+        assert_eq!(filter.venues, Some(vec![Exchange::BITSTAMP]));
+        assert_eq!(filter.depth, 5);
+
+        let unknown = subscriptions
+            .resolve_filter(&request(&["Kraken"], 0), &Entitlements::default())
+            .unwrap_err();
+        assert_eq!(
+            unknown,
+            SubscriptionError::UnknownExchange("Kraken".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_filter_applies_entitlements() {
+        let subscriptions = subscriptions(None, 0);
+        let entitlements = Entitlements {
+            instruments: Some(vec!["ethbtc".to_string()]),
+            venues: Some(vec!["Binance".to_string()]),
+            max_depth: Some(3),
+        };
+
+        let filter = subscriptions
+            .resolve_filter(&request(&[], 10), &entitlements)
+            .unwrap();
+        assert_eq!(filter.venues, Some(vec![Exchange::BINANCE]));
+        assert_eq!(filter.depth, 3);
+
+        let denied = subscriptions
+            .resolve_filter(&request(&["Bitstamp"], 0), &entitlements)
+            .unwrap_err();
+        assert!(matches!(denied, SubscriptionError::NotEntitled(_)));
+
+        let misconfigured = Entitlements {
+            venues: Some(vec!["Binance".to_string(), "Bogus".to_string()]),
+            ..entitlements
+        };
+        let unknown = subscriptions
+            .resolve_filter(&request(&[], 0), &misconfigured)
+            .unwrap_err();
+        assert_eq!(
+            unknown,
+            SubscriptionError::UnknownExchange("Bogus".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_filter_rejects_instruments() {
+        let subscriptions = subscriptions(None, 0);
+        let mut request = request(&[], 0);
+        request.instrument = "btcusd".to_string();
+        let not_served = subscriptions
+            .resolve_filter(&request, &Entitlements::default())
+            .unwrap_err();
+        assert_eq!(
+            not_served,
+            SubscriptionError::UnknownInstrument("btcusd".to_string())
+        );
+
+        request.instrument = "ethbtc".to_string();
+        let entitlements = Entitlements {
+            instruments: Some(vec!["btcusd".to_string()]),
+            ..Default::default()
+        };
+        let denied = subscriptions
+            .resolve_filter(&request, &entitlements)
+            .unwrap_err();
+        assert!(matches!(denied, SubscriptionError::NotEntitled(_)));
+    }
+
+    #[test]
+    fn test_resolve_policy_applies_floor() {
+        let subscriptions = subscriptions(None, 250);
+        let mut request = request(&[], 0);
+        request.min_interval = Duration::from_millis(100);
+        request.conflate = true;
+        assert_eq!(
+            subscriptions.resolve_policy(&request),
+            UpdatePolicy {
+                min_interval: Duration::from_millis(250),
+                conflate: true,
+                top_of_book_only: false,
+            }
+        );
+
+        request.min_interval = Duration::from_secs(1);
+        assert_eq!(
+            subscriptions.resolve_policy(&request).min_interval,
+            Duration::from_secs(1)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_conflates_to_latest_after_interval() {
+        let (sender, receiver) = watch::channel(book(100.0, 101.0, 1.0));
+        let policy = UpdatePolicy {
+            min_interval: Duration::from_millis(100),
+            conflate: true,
+            top_of_book_only: false,
+        };
+        let mut stream = Box::pin(snapshot_stream(
+            receiver,
+            BookFilter::default(),
+            policy,
+            permit(),
+        ));
+
+        let start = Instant::now();
+        assert_eq!(stream.next().await.unwrap().bids[0].price, 100.0);
+
+        sender.send(book(100.5, 101.0, 1.0)).unwrap();
+        sender.send(book(100.7, 101.0, 1.0)).unwrap();
+        let second = stream.next().await.unwrap();
+        assert_eq!(second.bids[0].price, 100.7);
+        assert!(Instant::now() - start >= Duration::from_millis(100));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_drops_throttled_updates_without_conflation() {
+        let (sender, receiver) = watch::channel(book(100.0, 101.0, 1.0));
+        let policy = UpdatePolicy {
+            min_interval: Duration::from_millis(100),
+            conflate: false,
+            top_of_book_only: false,
+        };
+        let mut stream = Box::pin(snapshot_stream(
+            receiver,
+            BookFilter::default(),
+            policy,
+            permit(),
+        ));
+        stream.next().await.unwrap();
+
+        sender.send(book(100.5, 101.0, 1.0)).unwrap();
+        let dropped = tokio::time::timeout(Duration::from_millis(200), stream.next()).await;
+        assert!(dropped.is_err());
+
+        sender.send(book(100.9, 101.0, 1.0)).unwrap();
+        assert_eq!(stream.next().await.unwrap().bids[0].price, 100.9);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_reader_skips_to_latest_without_conflation() {
+        let (sender, receiver) = watch::channel(book(100.0, 101.0, 1.0));
+        let mut stream = Box::pin(snapshot_stream(
+            receiver,
+            BookFilter::default(),
+            UpdatePolicy::default(),
+            permit(),
+        ));
+        stream.next().await.unwrap();
+
+        // Updates sent while the reader is busy don't queue up behind it.
+        for bid in [100.1, 100.2, 100.3] {
+            sender.send(book(bid, 101.0, 1.0)).unwrap();
+        }
+        assert_eq!(stream.next().await.unwrap().bids[0].price, 100.3);
+        let caught_up = tokio::time::timeout(Duration::from_millis(100), stream.next()).await;
+        assert!(caught_up.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_top_of_book_only() {
+        let (sender, receiver) = watch::channel(book(100.0, 101.0, 1.0));
+        let policy = UpdatePolicy {
+            top_of_book_only: true,
+            ..Default::default()
+        };
+        let mut stream = Box::pin(snapshot_stream(
+            receiver,
+            BookFilter::default(),
+            policy,
+            permit(),
+        ));
+        stream.next().await.unwrap();
+
+        // Only the second level moves, the top of book is unchanged.
+        let mut deeper_change = book(100.0, 101.0, 1.0);
+        deeper_change.bids[1].amount = 5.0;
+        sender.send(deeper_change).unwrap();
+        let skipped = tokio::time::timeout(Duration::from_millis(100), stream.next()).await;
+        assert!(skipped.is_err());
+
+        sender.send(book(100.0, 101.0, 2.0)).unwrap();
+
+        assert_eq!(stream.next().await.unwrap().bids[0].amount, 2.0);
+    }
+
+    #[test]
+    fn test_subscriber_limit_is_shared_between_clones() {
+        let subscriptions = subscriptions(Some(1), 0);
+        let other_publisher = subscriptions.clone();
+        let permit = subscriptions.try_acquire();
+        assert!(permit.is_ok());
+        assert_eq!(
+            other_publisher.try_acquire().err(),
+            Some(SubscriptionError::TooManySubscribers)
+        );
+        drop(permit);
+        assert!(other_publisher.try_acquire().is_ok());
+    }
+}
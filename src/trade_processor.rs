@@ -0,0 +1,160 @@
+use crate::config::{Config, HeartbeatConfig};
+use crate::exchange::{instantiate_trade_websocket, Exchange, ExchangeError, Trade, TradeStream};
+use crate::multiplexer::{MultiplexerEvent, StreamMultiplexer};
+use futures_util::StreamExt;
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+/// Trades buffered per subscriber before the slowest start missing prints.
+const TRADE_CHANNEL_CAPACITY: usize = 4096;
+
+/// Merges venue trade feeds and broadcasts every print as it arrives.
+pub struct TradeProcessor {
+    feeds: StreamMultiplexer<Exchange, Box<dyn TradeStream>>,
+    sender: broadcast::Sender<Trade>,
+}
+
+impl Default for TradeProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TradeProcessor {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(TRADE_CHANNEL_CAPACITY);
+        Self {
+            feeds: StreamMultiplexer::new(),
+            sender,
+        }
+    }
+
+    /// Builds trade feeds for the configured exchanges, or `None` if
+    /// trades are not enabled.
+    pub fn from_config(config: &Config) -> Result<Option<Self>, ExchangeError> {
+        let Some(trades) = &config.trades else {
+            return Ok(None);
+        };
+        let mut processor = Self::new();
+        for exchange_name in &config.exchanges {
+            let symbol = match &config.instrument {
+                Some(instrument) => instrument.symbol(exchange_name),
+                None => config.trading_pair.clone(),
+            };
+            let feed = instantiate_trade_websocket(
+                exchange_name,
+                &symbol,
+                trades.aggregated,
+                config
+                    .heartbeats
+                    .get(exchange_name)
+                    .unwrap_or(&HeartbeatConfig::default()),
+            )?;
+            processor.add_feed(feed);
+        }
+        Ok(Some(processor))
+    }
+
+    /// Adds a venue trade feed, replacing any existing feed for the same
+    /// exchange. Feeds are initialised by [`TradeProcessor::run`].
+    pub fn add_feed(&mut self, feed: Box<dyn TradeStream>) {
+        self.feeds.insert(feed.get_exchange(), feed);
+    }
+
+    /// Receives every trade from now on. The sender can also be handed to
+    /// publishers that subscribe per client.
+    pub fn subscribe(&self) -> broadcast::Receiver<Trade> {
+        self.sender.subscribe()
+    }
+
+    pub fn sender(&self) -> broadcast::Sender<Trade> {
+        self.sender.clone()
+    }
+
+    /// Connects every venue, then broadcasts trades until all feeds end.
+    pub async fn run(mut self) -> Result<(), ExchangeError> {
+        for (_, feed) in self.feeds.iter_mut() {
+            info!("initialising trade ws: {}", feed.get_exchange());
+            if let Err(err) = feed.initialise().await {
+                return Err(ExchangeError::Unknown(format!(
+                    "Error initializing {} trades: {}",
+                    feed.get_exchange(),
+                    err
+                )));
+            }
+        }
+
+        while let Some(event) = self.feeds.next().await {
+            match event {
+                MultiplexerEvent::Item(exchange, Ok(trade)) => {
+                    debug!("Received trade from {}: {:?}", exchange, trade);
+                    // No subscribers is not an error; the print is dropped.
+                    let _ = self.sender.send(trade);
+                }
+                MultiplexerEvent::Item(exchange, Err(err)) => {
+                    warn!("Error reading {} trades: {:?}", exchange, err);
+                }
+                MultiplexerEvent::Ended(exchange) => {
+                    warn!("Trade stream ended: {}", exchange);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::fake_feed::FakeFeed;
+    use crate::exchange::TradeSide;
+
+    fn trade(exchange: Exchange, trade_id: u64) -> Trade {
+        Trade {
+            exchange,
+            price: 0.05,
+            amount: 1.0,
+            aggressor: TradeSide::Buy,
+            trade_id,
+            event_time_us: 1_000,
+            received_time_us: 1_250,
+        }
+    }
+
+    fn feed(exchange: Exchange, trades: Vec<Result<Trade, ExchangeError>>) -> Box<FakeFeed<Trade>> {
+        Box::new(FakeFeed::new(exchange, trades))
+    }
+
+    #[tokio::test]
+    async fn test_merges_trades_across_venues() {
+        let mut processor = TradeProcessor::new();
+        processor.add_feed(feed(
+            Exchange::BINANCE,
+            vec![
+                Ok(trade(Exchange::BINANCE, 1)),
+                Err(ExchangeError::ConversionError),
+                Ok(trade(Exchange::BINANCE, 2)),
+            ],
+        ));
+        processor.add_feed(feed(
+            Exchange::BITSTAMP,
+            vec![Ok(trade(Exchange::BITSTAMP, 7))],
+        ));
+        let mut trades = processor.subscribe();
+
+        processor.run().await.unwrap();
+
+        let mut received = Vec::new();
+        while let Ok(trade) = trades.try_recv() {
+            received.push((trade.exchange, trade.trade_id));
+        }
+        assert_eq!(
+            received,
+            vec![
+                (Exchange::BINANCE, 1),
+                (Exchange::BITSTAMP, 7),
+                (Exchange::BINANCE, 2),
+            ]
+        );
+    }
+}
@@ -0,0 +1,465 @@
+use crate::combined_book::CombinedBookSnapshot;
+use crate::exchange::ExchangeOrder;
+use crate::grpc::auth::Authenticator;
+use crate::grpc::tls::certificate_subject;
+use crate::json::{JsonBook, JsonLevel};
+use crate::subscription::{SubscriptionRequest, Subscriptions};
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::pin::pin;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tokio_tungstenite::accept_hdr_async;
+use tokio_tungstenite::tungstenite::handshake::server::{
+    Callback, ErrorResponse, Request, Response,
+};
+use tokio_tungstenite::tungstenite::http::header::{AUTHORIZATION, SEC_WEBSOCKET_PROTOCOL};
+use tokio_tungstenite::tungstenite::http::{HeaderValue, StatusCode};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+
+/// Offered in `Sec-WebSocket-Protocol` ahead of the token by clients, such
+/// as browsers, that can't set an `authorization` header.
+const BEARER_PROTOCOL: &str = "bearer";
+
+/// A `snapshot` replaces the client's book. A `delta` lists the levels,
+/// keyed by exchange and price, that changed since the previous message;
+/// an amount of zero removes the level.
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BookMessage {
+    Snapshot(JsonBook),
+    Delta(JsonBook),
+}
+
+impl BookMessage {
+    fn snapshot(book: &CombinedBookSnapshot) -> Self {
+        BookMessage::Snapshot(book.into())
+    }
+
+    fn delta(previous: &CombinedBookSnapshot, book: &CombinedBookSnapshot) -> Self {
+        BookMessage::Delta(JsonBook {
+            spread: book.spread,
+            bids: level_changes(&previous.bids, &book.bids),
+            asks: level_changes(&previous.asks, &book.asks),
+        })
+    }
+}
+
+fn same_level(a: &ExchangeOrder, b: &ExchangeOrder) -> bool {
+    a.exchange == b.exchange && a.price == b.price
+}
+
+fn level_changes(previous: &[ExchangeOrder], current: &[ExchangeOrder]) -> Vec<JsonLevel> {
+    let updated = current
+        .iter()
+        .filter(|order| !previous.contains(order))
+        .map(JsonLevel::from);
+    let removed = previous
+        .iter()
+        .filter(|order| !current.iter().any(|o| same_level(o, order)))
+        .map(|order| JsonLevel {
+            amount: 0.0,
+            ..JsonLevel::from(order)
+        });
+    updated.chain(removed).collect()
+}
+
+/// Subscription options from the connection URL, e.g.
+/// `ws://host:port/?exchanges=Binance,Bitstamp&depth=5&deltas=true`.
+#[derive(Debug, Default, PartialEq)]
+struct ConnectionParams {
+    request: SubscriptionRequest,
+    deltas: bool,
+}
+
+fn parse_query(query: &str) -> Result<ConnectionParams, String> {
+    let mut params = ConnectionParams::default();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let invalid = || format!("Invalid value for '{}': '{}'", key, value);
+        let flag = || parse_flag(value).ok_or_else(invalid);
+        match key {
+            "instrument" => params.request.instrument = value.to_string(),
+            "exchanges" => {
+                params.request.exchanges = value
+                    .split(',')
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            }
+            "depth" => params.request.depth = value.parse().map_err(|_| invalid())?,
+            "min_interval_ms" => {
+                let millis = value.parse().map_err(|_| invalid())?;
+                params.request.min_interval = Duration::from_millis(millis)
+            }
+            "conflate" => params.request.conflate = flag()?,
+            "top_of_book_only" => params.request.top_of_book_only = flag()?,
+            "deltas" => params.deltas = flag()?,
+            // URLs end up in access logs.
+            "token" => {
+                return Err(
+                    "Pass the token in an authorization or Sec-WebSocket-Protocol header"
+                        .to_string(),
+                )
+            }
+            _ => return Err(format!("Unknown parameter '{}'", key)),
+        }
+    }
+    Ok(params)
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value {
+        "" | "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn header_token(request: &Request) -> Option<&str> {
+    request
+        .headers()
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// The token from a `Sec-WebSocket-Protocol: bearer, <token>` offer.
+fn protocol_token(request: &Request) -> Option<&str> {
+    let mut protocols = request
+        .headers()
+        .get(SEC_WEBSOCKET_PROTOCOL)?
+        .to_str()
+        .ok()?
+        .split(',')
+        .map(str::trim);
+    protocols.find(|protocol| *protocol == BEARER_PROTOCOL)?;
+    protocols.next()
+}
+
+/// An HTTP status and message to refuse a WebSocket upgrade with.
+type Rejection = (StatusCode, String);
+
+fn error_response((status, message): Rejection) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(message));
+    *response.status_mut() = status;
+    response
+}
+
+/// Runs `check` on an upgrade request, refusing it on a [`Rejection`] or
+/// echoing the subprotocol `check` returns.
+struct Handshake<F>(F);
+
+impl<F> Callback for Handshake<F>
+where
+    F: FnOnce(&Request) -> Result<Option<HeaderValue>, Rejection>,
+{
+    fn on_request(
+        self,
+        request: &Request,
+        mut response: Response,
+    ) -> Result<Response, ErrorResponse> {
+        let protocol = (self.0)(request).map_err(error_response)?;
+        if let Some(protocol) = protocol {
+            response
+                .headers_mut()
+                .insert(SEC_WEBSOCKET_PROTOCOL, protocol);
+        }
+        Ok(response)
+    }
+}
+
+/// Publishes the combined book as JSON over plain WebSockets, for clients
+/// that cannot speak gRPC. Takes the same subscription options as
+/// `BookSummary` from the URL query and authenticates with the gRPC
+/// tokens, passed in an `authorization` header or, from browsers, offered
+/// as the `Sec-WebSocket-Protocol` pair `bearer, <token>`. With TLS,
+/// client certificates are checked against the allowed subjects as on the
+/// gRPC side.
+#[derive(Clone)]
+pub struct JsonPublisher {
+    subscriptions: Subscriptions,
+    authenticator: Authenticator,
+    tls: Option<TlsAcceptor>,
+    allowed_client_subjects: Vec<String>,
+}
+
+impl JsonPublisher {
+    pub fn new(subscriptions: Subscriptions, authenticator: Authenticator) -> Self {
+        Self {
+            subscriptions,
+            authenticator,
+            tls: None,
+            allowed_client_subjects: Vec::new(),
+        }
+    }
+
+    pub fn with_tls(mut self, tls: TlsAcceptor) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    pub fn with_allowed_client_subjects(mut self, subjects: Vec<String>) -> Self {
+        self.allowed_client_subjects = subjects;
+        self
+    }
+
+    pub async fn serve(self, listener: TcpListener) -> std::io::Result<()> {
+        loop {
+            let (stream, client_addr) = listener.accept().await?;
+            let publisher = self.clone();
+            tokio::spawn(async move {
+                let result = match &publisher.tls {
+                    Some(tls) => match tls.accept(stream).await {
+                        Ok(stream) => {
+                            let client_subject = stream
+                                .get_ref()
+                                .1
+                                .peer_certificates()
+                                .and_then(|certs| certs.first())
+                                .and_then(|cert| certificate_subject(cert));
+                            publisher
+                                .handle_connection(stream, client_addr, client_subject)
+                                .await
+                        }
+                        Err(err) => Err(err.into()),
+                    },
+                    None => publisher.handle_connection(stream, client_addr, None).await,
+                };
+                if let Err(err) = result {
+                    warn!(client_addr = %client_addr, "WebSocket connection failed: {}", err);
+                }
+            });
+        }
+    }
+
+    fn authorise_client(&self, client_subject: Option<&str>) -> Result<(), Rejection> {
+        if self.allowed_client_subjects.is_empty() {
+            return Ok(());
+        }
+        match client_subject {
+            Some(subject) if self.allowed_client_subjects.iter().any(|s| s == subject) => Ok(()),
+            Some(subject) => Err((
+                StatusCode::FORBIDDEN,
+                format!("Client certificate '{}' is not authorised", subject),
+            )),
+            None => Err((
+                StatusCode::UNAUTHORIZED,
+                "Client certificate required".to_string(),
+            )),
+        }
+    }
+
+    async fn handle_connection<S>(
+        &self,
+        stream: S,
+        client_addr: SocketAddr,
+        client_subject: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let request_id = uuid::Uuid::new_v4();
+        let mut accepted = None;
+        let check = |request: &Request| {
+            self.authorise_client(client_subject.as_deref())?;
+            let params = parse_query(request.uri().query().unwrap_or(""))
+                .map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+            let protocol_token = protocol_token(request);
+            let auth = self
+                .authenticator
+                .authenticate(header_token(request).or(protocol_token))
+                .map_err(|status| (StatusCode::UNAUTHORIZED, status.message().to_string()))?;
+            let snapshots = self
+                .subscriptions
+                .subscribe(&params.request, &auth.entitlements)
+                .map_err(|err| ((&err).into(), err.to_string()))?;
+
+            info!(
+                request_id = %request_id,
+                client_addr = %client_addr,
+                client = %auth.client,
+                request = ?params.request,
+                deltas = params.deltas,
+                "New WebSocket subscriber"
+            );
+            accepted = Some((snapshots, params.deltas));
+            // Browsers drop the connection unless one offered protocol is
+            // echoed back.
+            Ok(protocol_token.map(|_| HeaderValue::from_static(BEARER_PROTOCOL)))
+        };
+
+        let ws_stream = accept_hdr_async(stream, Handshake(check)).await?;
+        let Some((snapshots, deltas)) = accepted else {
+            return Ok(());
+        };
+
+        let (mut write, mut read) = ws_stream.split();
+        let mut snapshots = pin!(snapshots);
+        let mut previous: Option<CombinedBookSnapshot> = None;
+
+        loop {
+            tokio::select! {
+                snapshot = snapshots.next() => {
+                    let Some(snapshot) = snapshot else { break };
+                    let message = match &previous {
+                        Some(previous) if deltas => BookMessage::delta(previous, &snapshot),
+                        _ => BookMessage::snapshot(&snapshot),
+                    };
+                    debug!(request_id = %request_id, "Sending data to WebSocket subscriber");
+                    write
+                        .send(Message::Text(serde_json::to_string(&message)?.into()))
+                        .await?;
+                    previous = Some(snapshot);
+                }
+                incoming = read.next() => match incoming {
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Err(err)) => return Err(err.into()),
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+
+        info!(request_id = %request_id, "WebSocket subscriber disconnected");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AuthConfig, StaticToken, SubscriptionConfig};
+    use crate::exchange::Exchange;
+    use crate::subscription::Entitlements;
+    use tokio::sync::watch;
+    use tokio_tungstenite::connect_async;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+    #[test]
+    fn test_parse_query() {
+        let params = parse_query(
+            "exchanges=Binance,Bitstamp&depth=5&min_interval_ms=250&conflate&deltas=true",
+        )
+        .unwrap();
+        assert_eq!(
+            params.request.exchanges,
+            vec!["Binance".to_string(), "Bitstamp".to_string()]
+        );
+        assert_eq!(params.request.depth, 5);
+        assert_eq!(params.request.min_interval, Duration::from_millis(250));
+        assert!(params.request.conflate);
+        assert!(!params.request.top_of_book_only);
+        assert!(params.deltas);
+
+        assert_eq!(parse_query("").unwrap(), ConnectionParams::default());
+        assert!(parse_query("depth=many").is_err());
+        assert!(parse_query("colour=red").is_err());
+        assert!(parse_query("token=abc").is_err());
+    }
+
+    #[test]
+    fn test_level_changes() {
+        let previous = vec![
+            ExchangeOrder::new(Exchange::BINANCE, 100.0, 1.0),
+            ExchangeOrder::new(Exchange::BITSTAMP, 99.0, 2.0),
+        ];
+        let current = vec![
+            ExchangeOrder::new(Exchange::BINANCE, 100.0, 1.5),
+            ExchangeOrder::new(Exchange::BINANCE, 99.5, 1.0),
+        ];
+
+        let changes = level_changes(&previous, &current);
+
+        assert_eq!(
+            changes,
+            vec![
+                JsonLevel::from(&ExchangeOrder::new(Exchange::BINANCE, 100.0, 1.5)),
+                JsonLevel::from(&ExchangeOrder::new(Exchange::BINANCE, 99.5, 1.0)),
+                JsonLevel::from(&ExchangeOrder::new(Exchange::BITSTAMP, 99.0, 0.0)),
+            ]
+        );
+        assert!(level_changes(&current, &current).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_publishes_snapshot_then_deltas() {
+        let book = |bid: f64| CombinedBookSnapshot {
+            spread: 101.0 - bid,
+            bids: vec![ExchangeOrder::new(Exchange::BINANCE, bid, 1.0)],
+            asks: vec![ExchangeOrder::new(Exchange::BITSTAMP, 101.0, 1.0)],
+        };
+        let (sender, receiver) = watch::channel(book(100.0));
+        let subscriptions = Subscriptions::new(receiver, "ethbtc", &SubscriptionConfig::default());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(JsonPublisher::new(subscriptions, Authenticator::new(None)).serve(listener));
+
+        let (mut client, _) = connect_async(format!("ws://{}/?deltas=true", addr))
+            .await
+            .unwrap();
+
+        let first = client.next().await.unwrap().unwrap();
+        let first: serde_json::Value = serde_json::from_str(first.to_text().unwrap()).unwrap();
+        assert_eq!(first["type"], "snapshot");
+        assert_eq!(first["bids"][0]["price"], 100.0);
+        assert_eq!(first["asks"][0]["exchange"], "Bitstamp");
+
+        sender.send(book(100.5)).unwrap();
+        let second = client.next().await.unwrap().unwrap();
+        let second: serde_json::Value = serde_json::from_str(second.to_text().unwrap()).unwrap();
+        assert_eq!(second["type"], "delta");
+        assert_eq!(second["bids"].as_array().unwrap().len(), 2);
+        assert_eq!(second["asks"].as_array().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_accepts_tokens_from_headers_only() {
+        let book = CombinedBookSnapshot {
+            spread: 1.0,
+            bids: vec![ExchangeOrder::new(Exchange::BINANCE, 100.0, 1.0)],
+            asks: vec![ExchangeOrder::new(Exchange::BINANCE, 101.0, 1.0)],
+        };
+        let (_sender, receiver) = watch::channel(book);
+        let subscriptions = Subscriptions::new(receiver, "ethbtc", &SubscriptionConfig::default());
+        let authenticator = Authenticator::new(Some(&AuthConfig {
+            tokens: vec![StaticToken {
+                client: "dashboard".to_string(),
+                token: "secret-token".to_string(),
+                entitlements: Entitlements::default(),
+            }],
+            jwt_hmac_secret: None,
+            jwt_issuer: None,
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(JsonPublisher::new(subscriptions, authenticator).serve(listener));
+        let url = format!("ws://{}/", addr);
+        let with_header = |name, value: &str| {
+            let mut request = url.as_str().into_client_request().unwrap();
+            request.headers_mut().insert(name, value.parse().unwrap());
+            request
+        };
+
+        let (mut client, _) = connect_async(with_header(AUTHORIZATION, "Bearer secret-token"))
+            .await
+            .unwrap();
+        assert!(client.next().await.unwrap().unwrap().is_text());
+
+        let (mut client, response) =
+            connect_async(with_header(SEC_WEBSOCKET_PROTOCOL, "bearer, secret-token"))
+                .await
+                .unwrap();
+        assert_eq!(response.headers()[SEC_WEBSOCKET_PROTOCOL], BEARER_PROTOCOL);
+        assert!(client.next().await.unwrap().unwrap().is_text());
+
+        assert!(connect_async(url.as_str()).await.is_err());
+        assert!(connect_async(format!("{}?token=secret-token", url))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,2 @@
+pub mod json_publisher;
+pub mod tls;
@@ -0,0 +1,38 @@
+use crate::config::TlsConfig;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+/// TLS for the WebSocket publisher from the same settings as the gRPC
+/// server. Setting `client_ca_path` requires a client certificate signed by
+/// that CA.
+pub fn load_tls_acceptor(config: &TlsConfig) -> Result<TlsAcceptor, Box<dyn std::error::Error>> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(&config.cert_path)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    let key = rustls_pemfile::private_key(&mut BufReader::new(File::open(&config.key_path)?))?
+        .ok_or_else(|| format!("No private key in {}", config.key_path))?;
+
+    let provider = Arc::new(ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+    let builder = match &config.client_ca_path {
+        Some(client_ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in rustls_pemfile::certs(&mut BufReader::new(File::open(client_ca_path)?)) {
+                roots.add(cert?)?;
+            }
+            let verifier =
+                WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider).build()?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    Ok(TlsAcceptor::from(Arc::new(
+        builder.with_single_cert(certs, key)?,
+    )))
+}